@@ -0,0 +1,16 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // protoc がインストールされていない環境でもビルドできるよう、同梱版を使う
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+
+    tonic_build::configure().build_server(true).compile_protos(
+        &[
+            "proto/google/cloud/texttospeech/v1/cloud_tts.proto",
+            "proto/google/cloud/texttospeech/v1beta1/cloud_tts.proto",
+        ],
+        &["proto"],
+    )?;
+
+    Ok(())
+}
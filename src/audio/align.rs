@@ -0,0 +1,193 @@
+use std::{io, time::Duration};
+
+/// 無音を探す窓の長さ
+const WINDOW: Duration = Duration::from_millis(10);
+
+/// 音量が `threshold_db` (dBFS) 以下の窓が `min_silence` 以上続く区間を返す
+/// 窓ごとに全チャンネルの二乗平均をとる
+pub fn silences(
+    samples: &[f32],
+    channels: u16,
+    sample_rate: u32,
+    threshold_db: f64,
+    min_silence: Duration,
+) -> Vec<(Duration, Duration)> {
+    let channels = channels.max(1) as usize;
+    let frames = samples.len() / channels;
+    let window = ((WINDOW.as_secs_f64() * sample_rate as f64) as usize).max(1);
+    let threshold = 10f64.powf(threshold_db / 10.0);
+    let time =
+        |frame: usize| Duration::from_secs_f64(frame.min(frames) as f64 / sample_rate as f64);
+
+    let mut silences = Vec::new();
+    let mut run_start = None;
+    for start in (0..frames).step_by(window) {
+        let block = &samples[start * channels..((start + window).min(frames)) * channels];
+        let power = block.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / block.len() as f64;
+        match (power <= threshold, run_start) {
+            (true, None) => run_start = Some(start),
+            (false, Some(run)) => {
+                silences.push((time(run), time(start)));
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(run) = run_start {
+        silences.push((time(run), time(frames)));
+    }
+    silences.retain(|(start, end)| *end - *start >= min_silence);
+    silences
+}
+
+/// 録音を `lines` 行に分ける区間（行の声の開始・終了）を決める
+/// 区切りには長い無音から順に使い、`pins`（行の番号と開始時刻）で指定した位置は必ず区切る
+/// 無音が足りなければ、閾値を変えるか区切りを指定するよう促すエラーにする
+pub fn align(
+    silences: &[(Duration, Duration)],
+    total: Duration,
+    lines: usize,
+    pins: &[(usize, Duration)],
+) -> Result<Vec<(Duration, Duration)>, io::Error> {
+    if lines == 0 {
+        return Ok(Vec::new());
+    }
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+
+    // 先頭と末尾の無音は区切りに使わず、声の範囲を決める
+    let speech_start = match silences.first() {
+        Some((start, end)) if start.is_zero() => *end,
+        _ => Duration::ZERO,
+    };
+    let speech_end = match silences.last() {
+        Some((start, end)) if *end >= total => *start,
+        _ => total,
+    };
+    let gaps: Vec<(Duration, Duration)> = silences
+        .iter()
+        .filter(|(start, end)| !start.is_zero() && *end < total)
+        .copied()
+        .collect();
+
+    let mut pins = pins.to_vec();
+    pins.sort();
+    for pair in pins.windows(2) {
+        if pair[0].0 == pair[1].0 || pair[0].1 >= pair[1].1 {
+            return Err(invalid(format!(
+                "pins for lines {} and {} are not in order",
+                pair[0].0 + 1,
+                pair[1].0 + 1
+            )));
+        }
+    }
+    if let Some((line, _)) = pins.iter().find(|(line, _)| *line >= lines) {
+        return Err(invalid(format!(
+            "pin for line {} is out of range",
+            line + 1
+        )));
+    }
+
+    // 区切りの位置（行の開始）を、指定した区切りの間ごとに長い無音から選ぶ
+    let mut anchors = vec![(0, speech_start)];
+    anchors.extend(pins.iter().filter(|(line, _)| *line > 0));
+    if let Some((0, time)) = pins.first() {
+        anchors[0].1 = *time;
+    }
+    anchors.push((lines, speech_end));
+
+    let mut starts: Vec<(Duration, Duration)> = Vec::new();
+    for pair in anchors.windows(2) {
+        let ((first, from), (last, to)) = (pair[0], pair[1]);
+        starts.push(match gaps.iter().find(|(s, e)| *s <= from && from <= *e) {
+            // 無音の中に指定した区切りは、その無音で区切る
+            Some(gap) => (gap.0, from),
+            None => (from, from),
+        });
+
+        let mut candidates: Vec<(Duration, Duration)> = gaps
+            .iter()
+            .filter(|(start, end)| from < *start && *end < to)
+            .copied()
+            .collect();
+        let needed = last - first - 1;
+        if candidates.len() < needed {
+            return Err(invalid(format!(
+                "found {} pauses for {} lines between {:.2}s and {:.2}s; \
+                 lower the silence threshold or pin more lines",
+                candidates.len(),
+                last - first,
+                from.as_secs_f64(),
+                to.as_secs_f64()
+            )));
+        }
+        candidates.sort_by_key(|(start, end)| std::cmp::Reverse(*end - *start));
+        candidates.truncate(needed);
+        candidates.sort();
+        starts.extend(candidates);
+    }
+
+    // 各行は区切りの無音の終わりから、次の区切りの無音の始まりまで
+    let segments = (0..lines)
+        .map(|i| {
+            let start = starts[i].1;
+            let end = starts.get(i + 1).map_or(speech_end, |next| next.0);
+            (start, end.max(start))
+        })
+        .collect();
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: f64) -> Duration {
+        Duration::from_secs_f64(seconds)
+    }
+
+    /// (開始, 終了) を秒にする
+    fn seconds(segments: &[(Duration, Duration)]) -> Vec<(f64, f64)> {
+        segments
+            .iter()
+            .map(|(s, e)| (s.as_secs_f64(), e.as_secs_f64()))
+            .collect()
+    }
+
+    #[test]
+    fn test_silences() {
+        // 100Hz で 0.5秒 無音、1秒 声、0.2秒 無音、1秒 声、0.6秒 無音
+        let mut samples = vec![0.0; 50];
+        samples.extend([0.5; 100]);
+        samples.extend([0.001; 20]);
+        samples.extend([0.5; 100]);
+        samples.extend([0.0; 60]);
+        let found = silences(&samples, 1, 100, -40.0, secs(0.3));
+        assert_eq!(seconds(&found), vec![(0.0, 0.5), (2.7, 3.3)]);
+
+        let found = silences(&samples, 1, 100, -40.0, secs(0.1));
+        assert_eq!(seconds(&found), vec![(0.0, 0.5), (1.5, 1.7), (2.7, 3.3)]);
+    }
+
+    #[test]
+    fn test_align() {
+        let silences = [
+            (secs(0.0), secs(0.5)),
+            (secs(2.0), secs(2.2)),
+            (secs(4.0), secs(5.0)),
+            (secs(6.0), secs(6.6)),
+            (secs(8.0), secs(9.0)),
+        ];
+        // 長い無音から2つを選ぶ
+        let segments = align(&silences, secs(9.0), 3, &[]).unwrap();
+        assert_eq!(seconds(&segments), vec![(0.5, 4.0), (5.0, 6.0), (6.6, 8.0)]);
+
+        // 指定した区切りは無音の長さによらず使う
+        let segments = align(&silences, secs(9.0), 3, &[(1, secs(2.1))]).unwrap();
+        assert_eq!(seconds(&segments), vec![(0.5, 2.0), (2.1, 4.0), (5.0, 8.0)]);
+
+        // 無音が足りなければエラー
+        assert!(align(&silences, secs(9.0), 6, &[]).is_err());
+        assert!(align(&silences, secs(9.0), 3, &[(3, secs(1.0))]).is_err());
+        assert!(align(&silences, secs(9.0), 3, &[(1, secs(5.0)), (2, secs(3.0))]).is_err());
+    }
+}
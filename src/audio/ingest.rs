@@ -0,0 +1,162 @@
+use std::{fs, io, path::Path};
+
+use super::wav::{self, SampleFormat, WavError, WavInfo};
+use crate::{
+    ffmpeg,
+    models::config::{AudioFormat, SampleType},
+    speech::cache::fnv1a,
+};
+
+/// WAV がプロジェクトの形式どおりかどうか
+pub fn matches(info: &WavInfo, format: &AudioFormat) -> bool {
+    let sample = match format.sample_format {
+        SampleType::S16 => (SampleFormat::Pcm, 16),
+        SampleType::F32 => (SampleFormat::Float, 32),
+    };
+    info.sample_rate == format.sample_rate
+        && info.channels == format.channels
+        && (info.format, info.bits_per_sample) == sample
+}
+
+/// 音声を取り込み、プロジェクトの形式の WAV のパスを返す
+/// 形式どおりの WAV はそのまま使い、それ以外は内容と形式から決めた名前で
+/// `cache_dir` に変換して残す（同じ音声は次回から変換しない）
+pub fn ingest(path: &str, format: &AudioFormat) -> Result<String, io::Error> {
+    if WavInfo::from_file(path).is_ok_and(|info| matches(&info, format)) {
+        return Ok(path.to_string());
+    }
+
+    let output = cached_path(path, &fs::read(path)?, format);
+    if Path::new(&output).exists() {
+        return Ok(output);
+    }
+    fs::create_dir_all(&format.cache_dir)?;
+    // 途中で失敗したファイルをキャッシュとして使わないよう、書き終えてから名前を変える
+    let partial = format!("{}.part.wav", output.trim_end_matches(".wav"));
+    convert(path, &partial, format)?;
+    fs::rename(&partial, &output)?;
+    Ok(output)
+}
+
+/// 変換した音声の保存先（元の名前に内容と形式のハッシュを付ける）
+fn cached_path(path: &str, content: &[u8], format: &AudioFormat) -> String {
+    let stem = Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let mut source = content.to_vec();
+    source.extend_from_slice(format.key().as_bytes());
+    Path::new(&format.cache_dir)
+        .join(format!("{}-{:016x}.wav", stem, fnv1a(&source)))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// 音声をプロジェクトの形式に変換する
+/// サンプリングレートが同じ WAV は Rust でサンプル形式・チャンネル数だけを変え、
+/// それ以外（サンプリングレートの変換を含む）は ffmpeg で変換する
+pub fn convert(input: &str, output: &str, format: &AudioFormat) -> Result<(), io::Error> {
+    match wav::read_samples(input) {
+        Ok((info, samples)) if info.sample_rate == format.sample_rate => write(
+            output,
+            format,
+            &remix(&samples, info.channels, format.channels),
+        ),
+        Err(WavError::Io(e)) => Err(e),
+        _ => ffmpeg::command::convert_audio(input, output, format),
+    }
+}
+
+/// サンプルをプロジェクトの形式の WAV に書き出す（16bit では 1.0 を超える部分を丸める）
+pub fn write(path: &str, format: &AudioFormat, samples: &[f32]) -> Result<(), io::Error> {
+    match format.sample_format {
+        SampleType::S16 => {
+            let samples: Vec<i16> = samples.iter().map(|s| wav::to_i16(*s)).collect();
+            wav::write_wav(path, format.sample_rate, format.channels, &samples)
+        }
+        SampleType::F32 => wav::write_wav_f32(path, format.sample_rate, format.channels, samples),
+    }
+}
+
+/// チャンネル数を変換する（モノラルへは平均、モノラルからは複製）
+pub fn remix(samples: &[f32], from: u16, to: u16) -> Vec<f32> {
+    let (from, to) = (from as usize, to as usize);
+    if from == to {
+        return samples.to_vec();
+    }
+    samples
+        .chunks_exact(from)
+        .flat_map(|frame| {
+            (0..to).map(move |c| match to {
+                1 => frame.iter().sum::<f32>() / from as f32,
+                _ => frame[c % from],
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remix() {
+        assert_eq!(remix(&[0.2, 0.4, 1.0, 0.0], 2, 1), vec![0.3, 0.5]);
+        assert_eq!(remix(&[0.25], 1, 2), vec![0.25, 0.25]);
+    }
+
+    #[test]
+    fn test_ingest() {
+        let dir = std::env::temp_dir().join("auto-mv-ingest");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        let format = AudioFormat {
+            cache_dir: path("converted"),
+            ..AudioFormat::default()
+        };
+
+        // 48kHz モノラルはステレオに変換してキャッシュする
+        wav::write_wav(&path("voice.wav"), 48000, 1, &[8192; 4800]).unwrap();
+        let converted = ingest(&path("voice.wav"), &format).unwrap();
+        assert!(converted.starts_with(&format.cache_dir));
+        let (info, samples) = wav::read_samples(&converted).unwrap();
+        assert!(matches(&info, &format));
+        assert_eq!(info.frame_count, 4800);
+        assert!(samples.iter().all(|s| (*s - 0.25).abs() < 1e-4));
+
+        // 2回目は変換済みのファイルを使う
+        fs::write(&converted, b"cached").unwrap();
+        assert_eq!(ingest(&path("voice.wav"), &format).unwrap(), converted);
+        assert_eq!(fs::read(&converted).unwrap(), b"cached");
+
+        // 形式が変われば別のファイルになり、形式どおりの WAV はそのまま使う
+        let f32_format = AudioFormat {
+            sample_format: SampleType::F32,
+            ..format.clone()
+        };
+        let other = ingest(&path("voice.wav"), &f32_format).unwrap();
+        assert_ne!(other, converted);
+        assert_eq!(ingest(&other, &f32_format).unwrap(), other);
+    }
+
+    #[test]
+    #[ignore = "requires ffmpeg on PATH"]
+    fn test_ingest_resample() {
+        let dir = std::env::temp_dir().join("auto-mv-ingest-resample");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        let format = AudioFormat {
+            cache_dir: path("converted"),
+            ..AudioFormat::default()
+        };
+
+        // サンプリングレートの変換は ffmpeg で行う
+        wav::write_wav(&path("voice.wav"), 24000, 1, &[8192; 2400]).unwrap();
+        let converted = ingest(&path("voice.wav"), &format).unwrap();
+        let (info, _) = wav::read_samples(&converted).unwrap();
+        assert!(matches(&info, &format));
+        assert_eq!(info.frame_count, 4800);
+    }
+}
@@ -0,0 +1,353 @@
+use std::{f64::consts::PI, io};
+
+use serde::Deserialize;
+
+use super::wav::{self, WavError};
+use crate::{ffmpeg, models::config::LoudnessConfig};
+
+/// ゲーティングのブロック長 (秒)
+const BLOCK_SECONDS: f64 = 0.4;
+/// ブロックの間隔 (秒)。75% ずつ重ねる
+const STEP_SECONDS: f64 = 0.1;
+/// 絶対ゲート (LUFS)
+const ABSOLUTE_GATE: f64 = -70.0;
+/// 相対ゲート (LU)
+const RELATIVE_GATE: f64 = -10.0;
+/// トゥルーピークを求めるオーバーサンプリングの倍率
+const OVERSAMPLE: usize = 4;
+/// 補間フィルタの片側のタップ数
+const HALF_TAPS: isize = 8;
+
+/// EBU R128 (ITU-R BS.1770) で測った音量
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Loudness {
+    /// 統合ラウドネス (LUFS)。無音なら None
+    pub integrated: Option<f64>,
+    /// トゥルーピーク (dBTP)。無音なら None
+    pub true_peak: Option<f64>,
+}
+
+impl Loudness {
+    /// 目標の音量に揃えるための補正量 (dB)
+    /// トゥルーピークが上限を超えない範囲で上げ、無音なら補正しない
+    pub fn gain_to(&self, config: &LoudnessConfig) -> f64 {
+        let Some(integrated) = self.integrated else {
+            return 0.0;
+        };
+        let gain = config.target_lufs - integrated;
+        match self.true_peak {
+            Some(peak) => gain.min(config.max_true_peak - peak),
+            None => gain,
+        }
+    }
+}
+
+/// インターリーブした [-1.0, 1.0] のサンプルの音量を測る
+pub fn measure(samples: &[f32], channels: u16, sample_rate: u32) -> Loudness {
+    let channels = channels.max(1) as usize;
+    Loudness {
+        integrated: integrated(samples, channels, sample_rate),
+        true_peak: true_peak(samples, channels),
+    }
+}
+
+/// 音声ファイルの音量を測る
+/// WAV は Rust で計算し、それ以外は ffmpeg の loudnorm で解析する
+pub fn measure_file(path: &str) -> Result<Loudness, io::Error> {
+    match wav::read_samples(path) {
+        Ok((info, samples)) => Ok(measure(&samples, info.channels, info.sample_rate)),
+        Err(WavError::Io(e)) => Err(e),
+        Err(_) => parse_loudnorm(&ffmpeg::command::loudnorm_analysis(path)?),
+    }
+}
+
+/// 統合ラウドネス（K 特性をかけた 400ms ブロックの平均二乗を、絶対・相対ゲートで選ぶ）
+fn integrated(samples: &[f32], channels: usize, sample_rate: u32) -> Option<f64> {
+    let frames = samples.len() / channels;
+    if frames == 0 {
+        return None;
+    }
+
+    // チャンネルごとに K 特性をかけた二乗値
+    let mut squared = vec![0.0; frames * channels];
+    for channel in 0..channels {
+        let mut filter = KWeighting::new(sample_rate);
+        for frame in 0..frames {
+            let y = filter.process(samples[frame * channels + channel] as f64);
+            squared[frame * channels + channel] = y * y;
+        }
+    }
+
+    // ブロックごとの平均二乗（全チャンネルの和）。ブロックより短い音声は全体を1ブロックとする
+    let block = ((BLOCK_SECONDS * sample_rate as f64) as usize).clamp(1, frames);
+    let step = ((STEP_SECONDS * sample_rate as f64) as usize).max(1);
+    let powers: Vec<f64> = (0..=(frames - block) / step)
+        .map(|b| {
+            squared[b * step * channels..(b * step + block) * channels]
+                .iter()
+                .sum::<f64>()
+                / block as f64
+        })
+        .collect();
+
+    let gated_mean = |threshold: f64| {
+        let gated: Vec<f64> = powers
+            .iter()
+            .copied()
+            .filter(|p| to_lufs(*p) > threshold)
+            .collect();
+        (!gated.is_empty()).then(|| gated.iter().sum::<f64>() / gated.len() as f64)
+    };
+    let absolute = gated_mean(ABSOLUTE_GATE)?;
+    let relative = gated_mean(to_lufs(absolute) + RELATIVE_GATE)?;
+    Some(to_lufs(relative))
+}
+
+fn to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// トゥルーピーク（4倍にオーバーサンプリングしたサンプルの最大値）
+fn true_peak(samples: &[f32], channels: usize) -> Option<f64> {
+    let frames = samples.len() / channels;
+    // 窓関数をかけた sinc による補間係数（位相ごと）
+    let phases: Vec<Vec<f64>> = (1..OVERSAMPLE)
+        .map(|phase| {
+            let offset = phase as f64 / OVERSAMPLE as f64;
+            (-HALF_TAPS + 1..=HALF_TAPS)
+                .map(|k| {
+                    let x = k as f64 - offset;
+                    let window = 0.5 + 0.5 * (PI * x / HALF_TAPS as f64).cos();
+                    sinc(x) * window
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut peak: f64 = 0.0;
+    for channel in 0..channels {
+        let at = |i: isize| match i >= 0 && (i as usize) < frames {
+            true => samples[i as usize * channels + channel] as f64,
+            false => 0.0,
+        };
+        for frame in 0..frames as isize {
+            peak = peak.max(at(frame).abs());
+            for coefficients in &phases {
+                let value: f64 = (-HALF_TAPS + 1..=HALF_TAPS)
+                    .zip(coefficients)
+                    .map(|(k, c)| at(frame + k) * c)
+                    .sum();
+                peak = peak.max(value.abs());
+            }
+        }
+    }
+    (peak > 0.0).then(|| 20.0 * peak.log10())
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// K 特性フィルタ（高域シェルフと高域通過の2段の双二次フィルタ）
+/// 係数は任意のサンプリングレートに合わせて BS.1770 の特性から求める
+struct KWeighting {
+    stages: [Biquad; 2],
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+
+        let k = (PI * 1681.974450955533 / rate).tan();
+        let q = 0.7071752369554196;
+        let vh = 10f64.powf(3.999843853973347 / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let k = (PI * 38.13547087602444 / rate).tan();
+        let q = 0.5003270373238773;
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        KWeighting {
+            stages: [shelf, high_pass],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        self.stages.iter_mut().fold(x, |x, stage| stage.process(x))
+    }
+}
+
+/// 双二次フィルタ（転置直接形 II）
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Biquad { b, a, z: [0.0; 2] }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// loudnorm の解析結果（`print_format=json`）
+#[derive(Deserialize)]
+struct LoudnormReport {
+    input_i: String,
+    input_tp: String,
+}
+
+/// ffmpeg の loudnorm が標準エラーに出力する解析結果を読む
+pub fn parse_loudnorm(stderr: &str) -> Result<Loudness, io::Error> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "loudnorm: analysis result not found",
+        )
+    };
+    let start = stderr.rfind('{').ok_or_else(invalid)?;
+    let end = stderr[start..].find('}').ok_or_else(invalid)? + start;
+    let report: LoudnormReport = serde_json::from_str(&stderr[start..=end])
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    // 無音は "-inf" になる
+    let level = |value: &str| value.parse::<f64>().ok().filter(|v| v.is_finite());
+    Ok(Loudness {
+        integrated: level(&report.input_i),
+        true_peak: level(&report.input_tp),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 振幅と位相を指定した正弦波（インターリーブ）
+    fn sine(frequency: f64, amplitude: f64, phase: f64, channels: u16, seconds: f64) -> Vec<f32> {
+        let rate = 48000.0;
+        (0..(rate * seconds) as usize)
+            .flat_map(|i| {
+                let value = amplitude * (2.0 * PI * frequency * i as f64 / rate + phase).sin();
+                std::iter::repeat_n(value as f32, channels as usize)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_integrated() {
+        // ステレオの -20 dBFS の 1kHz は約 -20 LUFS、モノラルは 3dB 低い
+        let stereo = measure(&sine(1000.0, 0.1, 0.0, 2, 3.0), 2, 48000);
+        assert!(
+            (stereo.integrated.unwrap() + 20.0).abs() < 0.1,
+            "{:?}",
+            stereo
+        );
+        let mono = measure(&sine(1000.0, 0.1, 0.0, 1, 3.0), 1, 48000);
+        assert!((mono.integrated.unwrap() + 23.0).abs() < 0.1, "{:?}", mono);
+
+        // 短いクリップも測れる
+        let short = measure(&sine(1000.0, 0.1, 0.0, 2, 0.2), 2, 48000);
+        assert!(
+            (short.integrated.unwrap() + 20.0).abs() < 0.5,
+            "{:?}",
+            short
+        );
+
+        // 無音は測れない
+        let silence = measure(&[0.0; 48000], 1, 48000);
+        assert_eq!(silence.integrated, None);
+        assert_eq!(silence.true_peak, None);
+    }
+
+    #[test]
+    fn test_gating() {
+        // 無音の区間は平均に含めない（含めると約 -27.8 LUFS になる）
+        let mut samples = sine(1000.0, 0.1, 0.0, 1, 2.0);
+        samples.extend(vec![0.0; 48000 * 4]);
+        let loudness = measure(&samples, 1, 48000);
+        assert!(
+            (loudness.integrated.unwrap() + 23.0).abs() < 0.5,
+            "{:?}",
+            loudness
+        );
+    }
+
+    #[test]
+    fn test_true_peak() {
+        // fs/4 の正弦波を 45° ずらすと、サンプルの最大値は 0.707 だが実際の波形は 1.0 に達する
+        let samples = sine(12000.0, 1.0, PI / 4.0, 1, 0.1);
+        let sample_peak = samples.iter().fold(0f32, |m, s| m.max(s.abs()));
+        assert!((sample_peak - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.001);
+        let loudness = measure(&samples, 1, 48000);
+        assert!(loudness.true_peak.unwrap().abs() < 0.3, "{:?}", loudness);
+    }
+
+    #[test]
+    fn test_gain_to() {
+        let config = LoudnessConfig::default();
+        let quiet = Loudness {
+            integrated: Some(-26.0),
+            true_peak: Some(-12.0),
+        };
+        assert_eq!(quiet.gain_to(&config), 10.0);
+        // ピークが上限を超えない範囲に抑える
+        let peaky = Loudness {
+            integrated: Some(-20.0),
+            true_peak: Some(-3.5),
+        };
+        assert_eq!(peaky.gain_to(&config), 2.0);
+        let silence = Loudness {
+            integrated: None,
+            true_peak: None,
+        };
+        assert_eq!(silence.gain_to(&config), 0.0);
+    }
+
+    #[test]
+    fn test_parse_loudnorm() {
+        let stderr = "size=N/A time=00:00:01.50 bitrate=N/A speed= 120x\n\
+            [Parsed_loudnorm_0 @ 0x55d0c8a1f2c0] \n\
+            {\n\
+            \t\"input_i\" : \"-23.54\",\n\
+            \t\"input_tp\" : \"-7.96\",\n\
+            \t\"input_lra\" : \"0.00\",\n\
+            \t\"input_thresh\" : \"-34.17\",\n\
+            \t\"target_offset\" : \"0.00\"\n\
+            }\n";
+        assert_eq!(
+            parse_loudnorm(stderr).unwrap(),
+            Loudness {
+                integrated: Some(-23.54),
+                true_peak: Some(-7.96),
+            }
+        );
+
+        let silence = "{\"input_i\" : \"-inf\", \"input_tp\" : \"-inf\"}";
+        assert_eq!(parse_loudnorm(silence).unwrap().integrated, None);
+        assert!(parse_loudnorm("Error opening input").is_err());
+    }
+}
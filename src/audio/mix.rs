@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// ミックスするトラックの種類
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Track {
+    /// 行ごとの読み上げ
+    #[default]
+    Narration,
+    /// BGM
+    Music,
+    /// 効果音
+    Effect,
+}
+
+/// ミックスする音声
+/// すべての音声を1回でミックスし、互いの音量は `gain_db` だけで決める
+#[derive(Debug, Clone, PartialEq)]
+pub struct MixInput {
+    pub path: String,
+    pub track: Track,
+    /// 動画の先頭からの開始時刻
+    pub start: Duration,
+    /// 元の音量からの補正 (dB)
+    pub gain_db: f64,
+    /// ファイルの先頭から使う長さ（None なら最後まで）
+    pub length: Option<Duration>,
+    pub fade_in: Duration,
+    /// 終わりのフェードアウト（`length` を指定した場合のみ）
+    pub fade_out: Duration,
+    /// 読み上げに合わせて音量を下げる
+    pub ducking: Option<Ducking>,
+}
+
+impl MixInput {
+    pub fn new(path: &str, track: Track, start: Duration, gain_db: f64) -> Self {
+        MixInput {
+            path: path.to_string(),
+            track,
+            start,
+            gain_db,
+            length: None,
+            fade_in: Duration::ZERO,
+            fade_out: Duration::ZERO,
+            ducking: None,
+        }
+    }
+
+    /// ファイルの先頭から `length` だけ使い、前後をフェードする
+    pub fn with_length(mut self, length: Duration, fade_in: Duration, fade_out: Duration) -> Self {
+        self.length = Some(length);
+        self.fade_in = fade_in.min(length);
+        self.fade_out = fade_out.min(length);
+        self
+    }
+
+    pub fn with_ducking(mut self, ducking: Option<Ducking>) -> Self {
+        self.ducking = ducking;
+        self
+    }
+}
+
+/// 読み上げの区間で音量を下げるエンベロープ（時刻は動画の先頭から）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ducking {
+    pub depth_db: f64,
+    pub attack: Duration,
+    pub release: Duration,
+    /// 下げる区間（重なりや、戻りきらないうちに次が始まるものはまとめてある）
+    pub intervals: Vec<(Duration, Duration)>,
+}
+
+impl Ducking {
+    /// 区間の開始 `attack` 前から下げ始め、終了から `release` かけて戻す
+    pub fn new(
+        depth_db: f64,
+        attack: Duration,
+        release: Duration,
+        intervals: &[(Duration, Duration)],
+    ) -> Self {
+        let mut sorted = intervals.to_vec();
+        sorted.sort();
+        let mut merged: Vec<(Duration, Duration)> = Vec::new();
+        for (start, end) in sorted {
+            match merged.last_mut() {
+                Some(last) if start.saturating_sub(attack) <= last.1 + release => {
+                    last.1 = last.1.max(end);
+                }
+                _ => merged.push((start, end)),
+            }
+        }
+        Ducking {
+            depth_db,
+            attack,
+            release,
+            intervals: merged,
+        }
+    }
+
+    /// 時刻 `t` での下げ幅の割合（0.0〜1.0）。まとめた区間は重ならないため和をとる
+    pub fn amount_at(&self, t: f64) -> f64 {
+        self.intervals
+            .iter()
+            .map(|(start, end)| {
+                let rise = ramp(
+                    t - (start.as_secs_f64() - self.attack.as_secs_f64()),
+                    self.attack,
+                );
+                let fall = ramp(
+                    end.as_secs_f64() + self.release.as_secs_f64() - t,
+                    self.release,
+                );
+                rise.min(fall)
+            })
+            .sum()
+    }
+
+    /// 時刻 `t` での補正 (dB)
+    pub fn gain_db_at(&self, t: f64) -> f64 {
+        -self.depth_db * self.amount_at(t)
+    }
+}
+
+/// 経過時間 `elapsed` に対する 0.0〜1.0 の傾斜（長さ 0 なら段差）
+fn ramp(elapsed: f64, length: Duration) -> f64 {
+    match length.is_zero() {
+        true => (elapsed >= 0.0) as u8 as f64,
+        false => (elapsed / length.as_secs_f64()).clamp(0.0, 1.0),
+    }
+}
+
+/// dB を振幅の倍率にする
+pub fn db_to_linear(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: f64) -> Duration {
+        Duration::from_secs_f64(seconds)
+    }
+
+    #[test]
+    fn test_ducking() {
+        let ducking = Ducking::new(
+            10.0,
+            secs(0.5),
+            secs(1.0),
+            &[
+                (secs(6.0), secs(7.0)),
+                (secs(2.0), secs(3.0)),
+                (secs(3.8), secs(4.0)),
+            ],
+        );
+        // 戻りきる前に次が始まる区間はまとめる
+        assert_eq!(
+            ducking.intervals,
+            vec![(secs(2.0), secs(4.0)), (secs(6.0), secs(7.0))]
+        );
+
+        assert_eq!(ducking.gain_db_at(0.0), 0.0);
+        assert_eq!(ducking.gain_db_at(1.75), -5.0);
+        assert_eq!(ducking.gain_db_at(3.0), -10.0);
+        assert_eq!(ducking.gain_db_at(4.5), -5.0);
+        assert_eq!(ducking.gain_db_at(5.25), 0.0);
+        assert_eq!(ducking.gain_db_at(7.5), -5.0);
+        assert_eq!(ducking.gain_db_at(8.0), 0.0);
+    }
+
+    #[test]
+    fn test_db_to_linear() {
+        assert_eq!(db_to_linear(0.0), 1.0);
+        assert!((db_to_linear(-6.0) - 0.5012).abs() < 1e-4);
+        assert!((db_to_linear(20.0) - 10.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,8 @@
+pub mod align;
+pub mod ingest;
+pub mod loudness;
+pub mod mix;
+pub mod music;
+pub mod narration;
+pub mod trim;
+pub mod wav;
@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use super::mix::{Ducking, MixInput, Track};
+use crate::models::config::MusicConfig;
+
+/// 時刻を決めたプレイリストの1曲
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub path: String,
+    /// 曲を始める時刻
+    pub start: Duration,
+    /// 曲の長さ
+    pub length: Duration,
+}
+
+/// BGM をミックスする音声に分ける
+/// 各曲は次の曲が始まるまで（最後の曲は `end` まで）繰り返し、繰り返しや曲の切り替えは
+/// `crossfade_seconds` だけ重ねる。`speech` は読み上げの区間で、BGM を下げるのに使う
+pub fn music_inputs(
+    cues: &[Cue],
+    end: Duration,
+    speech: &[(Duration, Duration)],
+    config: &MusicConfig,
+) -> Vec<MixInput> {
+    let mut cues: Vec<&Cue> = cues
+        .iter()
+        .filter(|c| c.start < end && !c.length.is_zero())
+        .collect();
+    cues.sort_by_key(|c| c.start);
+
+    let ducking = config.ducking.enabled.then(|| {
+        Ducking::new(
+            config.ducking.depth_db,
+            Duration::from_millis(config.ducking.attack_ms),
+            Duration::from_millis(config.ducking.release_ms),
+            speech,
+        )
+    });
+    let seconds = |s: f64| Duration::from_secs_f64(s.max(0.0));
+    let crossfade = seconds(config.crossfade_seconds);
+
+    let mut inputs = Vec::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let (first_cue, last_cue) = (i == 0, i + 1 == cues.len());
+        // 次の曲とは重ねる長さだけ長く流す
+        let stop = match cues.get(i + 1) {
+            Some(next) => (next.start + crossfade).min(end),
+            None => end,
+        };
+        // 曲より長く重ねることはできない
+        let overlap = crossfade.min(cue.length / 2);
+
+        let mut start = cue.start;
+        let mut first_piece = true;
+        while start < stop {
+            let length = cue.length.min(stop - start);
+            let last_piece = !config.looped || start + length >= stop;
+            let fade_in = match (first_piece, first_cue) {
+                (true, true) => seconds(config.fade_in_seconds),
+                (true, false) => crossfade,
+                (false, _) => overlap,
+            };
+            let fade_out = match (last_piece, last_cue) {
+                (true, true) => seconds(config.fade_out_seconds),
+                (true, false) => crossfade,
+                (false, _) => overlap,
+            };
+            inputs.push(
+                MixInput::new(&cue.path, Track::Music, start, config.gain_db)
+                    .with_length(length, fade_in, fade_out)
+                    .with_ducking(ducking.clone()),
+            );
+
+            if last_piece {
+                break;
+            }
+            start += cue.length - overlap;
+            first_piece = false;
+        }
+    }
+    inputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: f64) -> Duration {
+        Duration::from_secs_f64(seconds)
+    }
+
+    fn cue(path: &str, start: f64, length: f64) -> Cue {
+        Cue {
+            path: path.to_string(),
+            start: secs(start),
+            length: secs(length),
+        }
+    }
+
+    /// (開始, 長さ, フェードイン, フェードアウト)
+    fn pieces(inputs: &[MixInput]) -> Vec<(f64, f64, f64, f64)> {
+        inputs
+            .iter()
+            .map(|i| {
+                (
+                    i.start.as_secs_f64(),
+                    i.length.unwrap().as_secs_f64(),
+                    i.fade_in.as_secs_f64(),
+                    i.fade_out.as_secs_f64(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_loop() {
+        let config = MusicConfig::default();
+        let inputs = music_inputs(&[cue("bgm.mp3", 0.0, 10.0)], secs(25.0), &[], &config);
+        // 2秒ずつ重ねて繰り返し、最後は動画の終わりでフェードアウトする
+        assert_eq!(
+            pieces(&inputs),
+            vec![
+                (0.0, 10.0, 1.0, 2.0),
+                (8.0, 10.0, 2.0, 2.0),
+                (16.0, 9.0, 2.0, 3.0)
+            ]
+        );
+        assert!(inputs
+            .iter()
+            .all(|i| i.track == Track::Music && i.gain_db == -20.0));
+
+        // 繰り返さなければ1回だけ
+        let config = MusicConfig {
+            looped: false,
+            ..MusicConfig::default()
+        };
+        let inputs = music_inputs(&[cue("bgm.mp3", 0.0, 10.0)], secs(25.0), &[], &config);
+        assert_eq!(pieces(&inputs), vec![(0.0, 10.0, 1.0, 3.0)]);
+    }
+
+    #[test]
+    fn test_playlist() {
+        let config = MusicConfig::default();
+        let cues = [
+            cue("outro.mp3", 10.0, 100.0),
+            cue("intro.mp3", 0.0, 100.0),
+            cue("late.mp3", 30.0, 100.0),
+        ];
+        let inputs = music_inputs(&cues, secs(20.0), &[], &config);
+        // 次の曲と重ねて切り替え、動画より後の曲は使わない
+        assert_eq!(inputs[0].path, "intro.mp3");
+        assert_eq!(
+            pieces(&inputs),
+            vec![(0.0, 12.0, 1.0, 2.0), (10.0, 10.0, 2.0, 3.0)]
+        );
+    }
+
+    #[test]
+    fn test_ducking() {
+        let speech = [(secs(2.0), secs(4.0))];
+        let inputs = music_inputs(
+            &[cue("bgm.mp3", 0.0, 60.0)],
+            secs(10.0),
+            &speech,
+            &MusicConfig::default(),
+        );
+        let ducking = inputs[0].ducking.as_ref().unwrap();
+        assert_eq!(ducking.gain_db_at(3.0), -12.0);
+
+        let mut config = MusicConfig::default();
+        config.ducking.enabled = false;
+        let inputs = music_inputs(&[cue("bgm.mp3", 0.0, 60.0)], secs(10.0), &speech, &config);
+        assert_eq!(inputs[0].ducking, None);
+    }
+}
@@ -0,0 +1,229 @@
+use std::{fs, io, time::Duration};
+
+use super::{
+    ingest::remix,
+    mix::{db_to_linear, MixInput},
+    wav::{self, WavError},
+};
+use crate::{
+    ffmpeg,
+    models::config::{AudioFormat, SampleType},
+};
+
+/// 読み上げのトラック。行ごとのクリップを時刻どおりに並べた1本の音声
+/// `samples` はチャンネルをインターリーブしたもの
+#[derive(Debug, Clone, PartialEq)]
+pub struct NarrationTrack {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl NarrationTrack {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        NarrationTrack {
+            sample_rate,
+            channels,
+            samples: Vec::new(),
+        }
+    }
+
+    /// チャンネルあたりのサンプル数
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / self.sample_rate as f64)
+    }
+
+    /// 時刻に最も近いフレームの位置
+    fn frame_at(&self, time: Duration) -> usize {
+        (time.as_secs_f64() * self.sample_rate as f64).round() as usize
+    }
+
+    /// トラックと同じサンプリングレートのクリップを `start` の位置に `gain_db` をかけて足し込む
+    /// クリップはトラックのチャンネル数に変換し、間は無音で埋める
+    pub fn place(&mut self, clip: &[f32], channels: u16, start: Duration, gain_db: f64) {
+        let clip = remix(clip, channels, self.channels);
+        let offset = self.frame_at(start) * self.channels as usize;
+        if self.samples.len() < offset + clip.len() {
+            self.samples.resize(offset + clip.len(), 0.0);
+        }
+
+        let gain = db_to_linear(gain_db) as f32;
+        for (out, sample) in self.samples[offset..].iter_mut().zip(&clip) {
+            *out += sample * gain;
+        }
+    }
+
+    /// 32bit 浮動小数点の WAV に書き出す（重なりで 1.0 を超えても丸めない）
+    /// ミックスの中間ファイルのため、リミッターより前で音を潰さないようプロジェクトのサンプル形式は使わない
+    pub fn write(&self, path: &str) -> Result<(), io::Error> {
+        wav::write_wav_f32(path, self.sample_rate, self.channels, &self.samples)
+    }
+}
+
+/// クリップをプロジェクトのサンプリングレートで読み込む
+/// WAV 以外やサンプリングレートの異なる WAV は、ffmpeg で 32bit 浮動小数点の WAV に変換してから読む
+fn read_clip(path: &str, format: &AudioFormat) -> Result<(wav::WavInfo, Vec<f32>), io::Error> {
+    match wav::read_samples(path) {
+        Ok(clip) if clip.0.sample_rate == format.sample_rate => Ok(clip),
+        Err(WavError::Io(e)) => Err(e),
+        _ => {
+            let decoded = format!("{}.decoded.wav", path);
+            let float = AudioFormat {
+                sample_format: SampleType::F32,
+                ..format.clone()
+            };
+            ffmpeg::command::convert_audio(path, &decoded, &float)?;
+            let clip = wav::read_samples(&decoded);
+            fs::remove_file(&decoded)?;
+            Ok(clip?)
+        }
+    }
+}
+
+/// 読み上げのクリップをプロジェクトのサンプリングレート・チャンネル数で1本のトラックにまとめ、
+/// 32bit 浮動小数点の WAV に書き出す
+/// `start` と `gain_db` のほか、`length` を指定したクリップはその長さで切る
+pub fn assemble(
+    inputs: &[MixInput],
+    format: &AudioFormat,
+    output: &str,
+) -> Result<NarrationTrack, io::Error> {
+    let mut clips = Vec::new();
+    for input in inputs {
+        let (info, samples) = read_clip(&input.path, format)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", input.path, e)))?;
+        clips.push((input, info, samples));
+    }
+
+    let mut track = NarrationTrack::new(format.sample_rate, format.channels);
+    for (input, info, mut samples) in clips {
+        if let Some(length) = input.length {
+            let frames = (length.as_secs_f64() * info.sample_rate as f64).round() as usize;
+            samples.truncate(frames * info.channels as usize);
+        }
+        track.place(&samples, info.channels, input.start, input.gain_db);
+    }
+
+    track.write(output)?;
+    Ok(track)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        audio::{loudness, mix::Track},
+        models::config::SampleType,
+    };
+
+    #[test]
+    fn test_place() {
+        let mut track = NarrationTrack::new(10, 1);
+        track.place(&[1.0, 1.0], 1, Duration::from_millis(200), 0.0);
+        // 間は無音、重なりは足し合わせる
+        track.place(&[0.5, 0.5], 1, Duration::from_millis(300), -6.0);
+        let half = db_to_linear(-6.0) as f32 * 0.5;
+        assert_eq!(track.samples, vec![0.0, 0.0, 1.0, 1.0 + half, half]);
+        assert_eq!(track.duration(), Duration::from_millis(500));
+
+        // モノラルをステレオへ
+        let mut track = NarrationTrack::new(10, 2);
+        track.place(&[0.0, 1.0], 1, Duration::ZERO, 0.0);
+        assert_eq!(track.samples, vec![0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_assemble() {
+        let dir = std::env::temp_dir().join("auto-mv-narration");
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        wav::write_wav(&path("a.wav"), 16000, 1, &[16384; 1600]).unwrap();
+        wav::write_wav(&path("b.wav"), 16000, 2, &[-16384; 3200]).unwrap();
+
+        let inputs = [
+            MixInput::new(&path("a.wav"), Track::Narration, Duration::ZERO, 0.0),
+            MixInput::new(
+                &path("b.wav"),
+                Track::Narration,
+                Duration::from_millis(1500),
+                12.0,
+            ),
+        ];
+        // プロジェクトが 16bit でも中間ファイルは浮動小数点で書く
+        let format = AudioFormat {
+            sample_rate: 16000,
+            sample_format: SampleType::S16,
+            ..AudioFormat::default()
+        };
+        let track = assemble(&inputs, &format, &path("narration.wav")).unwrap();
+        assert_eq!((track.sample_rate, track.channels), (16000, 2));
+        assert_eq!(track.duration(), Duration::from_millis(1600));
+
+        // サンプル単位で指定の位置に置かれる
+        let (info, samples) = wav::read_samples(&path("narration.wav")).unwrap();
+        assert_eq!(info.format, wav::SampleFormat::Float);
+        assert_eq!(info.frame_count, 25600);
+        assert_eq!(samples[2 * 1599], 0.5);
+        assert_eq!(samples[2 * 1600], 0.0);
+        assert_eq!(samples[2 * 23999], 0.0);
+        // 1.0 を超えても丸めない
+        let loud = -0.5 * db_to_linear(12.0) as f32;
+        assert!(loud < -1.0);
+        assert_eq!(samples[2 * 24000], loud);
+        assert!(samples[2 * 24000..].iter().all(|s| *s == loud));
+    }
+
+    #[test]
+    fn test_assemble_levels() {
+        // 1kHz・-20dBFS の音声を、補正量を変えて別々の時刻に置く（ffmpeg を使わずに測る）
+        let dir = std::env::temp_dir().join("auto-mv-narration-levels");
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        let samples: Vec<i16> = (0..48000)
+            .flat_map(|i| {
+                let t = i as f64 / 48000.0;
+                let s = wav::to_i16((0.1 * (2.0 * std::f64::consts::PI * 1000.0 * t).sin()) as f32);
+                [s, s]
+            })
+            .collect();
+        wav::write_wav(&path("tone.wav"), 48000, 2, &samples).unwrap();
+        let source = loudness::measure_file(&path("tone.wav"))
+            .unwrap()
+            .integrated
+            .unwrap();
+
+        let gains = [6.0, -6.0, 0.0, -12.0];
+        let inputs: Vec<MixInput> = gains
+            .iter()
+            .enumerate()
+            .map(|(i, gain)| {
+                MixInput::new(
+                    &path("tone.wav"),
+                    Track::Narration,
+                    Duration::from_secs(i as u64 * 2),
+                    *gain,
+                )
+            })
+            .collect();
+        let track = assemble(&inputs, &AudioFormat::default(), &path("narration.wav")).unwrap();
+
+        // 後の行も含め、どのクリップも指定した補正量だけ変わる
+        for (i, gain) in gains.iter().enumerate() {
+            let clip = &track.samples[i * 2 * 2 * 48000..(i * 2 + 1) * 2 * 48000];
+            let level = loudness::measure(clip, track.channels, track.sample_rate)
+                .integrated
+                .unwrap();
+            assert!(
+                (level - (source + gain)).abs() < 0.1,
+                "clip {}: {} LUFS, expected {}",
+                i,
+                level,
+                source + gain
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use super::wav::{self, SampleFormat, WavError};
+use crate::models::config::TrimConfig;
+
+/// 無音を除いた発話区間（フレーム単位、終端は含まない）
+/// 閾値を超えるサンプルがなければ None
+pub fn speech_range(samples: &[f32], channels: u16, threshold_db: f64) -> Option<(usize, usize)> {
+    let threshold = 10f64.powf(threshold_db / 20.0) as f32;
+    let channels = channels.max(1) as usize;
+    let loud = |frame: &[f32]| frame.iter().any(|s| s.abs() > threshold);
+
+    let frames = samples.chunks_exact(channels);
+    let start = frames.clone().position(loud)?;
+    let end = frames.len() - frames.rev().position(loud)?;
+    Some((start, end))
+}
+
+/// 前後の無音を取り除き、指定の長さの無音を付け足す
+pub fn trim_samples(
+    samples: &[f32],
+    channels: u16,
+    sample_rate: u32,
+    config: &TrimConfig,
+) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    let padding = |ms: u64| (sample_rate as u64 * ms / 1000) as usize * channels;
+
+    let mut out = vec![0.0; padding(config.head_padding_ms)];
+    if let Some((start, end)) = speech_range(samples, channels as u16, config.threshold_db) {
+        out.extend_from_slice(&samples[start * channels..end * channels]);
+    }
+    out.resize(out.len() + padding(config.tail_padding_ms), 0.0);
+    out
+}
+
+/// 無音を整えた結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trimmed {
+    /// 書き出した音声の再生時間
+    pub duration: Duration,
+    /// 取り除いた先頭の無音
+    pub head_removed: Duration,
+    /// 付け足した先頭の無音
+    pub head_padding: Duration,
+}
+
+impl Trimmed {
+    /// 元の音声の時刻を、書き出した音声の時刻に変換する
+    pub fn map_time(&self, time: Duration) -> Duration {
+        (time.saturating_sub(self.head_removed) + self.head_padding).min(self.duration)
+    }
+}
+
+/// WAV ファイルの前後の無音を整えて書き出す
+/// 16bit 以下の PCM・G.711 は 16bit PCM、それ以外（浮動小数点・24bit など）は 32bit 浮動小数点で書き出し、
+/// 元より粗く量子化しない
+pub fn trim_file(input: &str, output: &str, config: &TrimConfig) -> Result<Trimmed, WavError> {
+    let (info, samples) = wav::read_samples(input)?;
+    let trimmed = trim_samples(&samples, info.channels, info.sample_rate, config);
+    let pcm16 = match info.format {
+        SampleFormat::Pcm => info.bits_per_sample <= 16,
+        SampleFormat::Alaw | SampleFormat::Mulaw => true,
+        SampleFormat::Float => false,
+    };
+    if pcm16 {
+        let pcm = trimmed.iter().map(|s| wav::to_i16(*s)).collect::<Vec<_>>();
+        wav::write_wav(output, info.sample_rate, info.channels, &pcm)?;
+    } else {
+        wav::write_wav_f32(output, info.sample_rate, info.channels, &trimmed)?;
+    }
+
+    let frames = samples.len() / info.channels.max(1) as usize;
+    let start = speech_range(&samples, info.channels, config.threshold_db)
+        .map_or(frames, |(start, _)| start);
+    Ok(Trimmed {
+        duration: wav::duration(output)?,
+        head_removed: Duration::from_secs_f64(start as f64 / info.sample_rate as f64),
+        head_padding: Duration::from_millis(config.head_padding_ms),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(head: u64, tail: u64) -> TrimConfig {
+        TrimConfig {
+            head_padding_ms: head,
+            tail_padding_ms: tail,
+            ..TrimConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_speech_range() {
+        // -50dBFS ≒ 0.00316
+        let samples = [0.0, 0.001, 0.5, -0.2, 0.002, 0.0];
+        assert_eq!(speech_range(&samples, 1, -50.0), Some((2, 4)));
+        assert_eq!(speech_range(&samples, 2, -50.0), Some((1, 2)));
+        assert_eq!(speech_range(&[0.0; 4], 1, -50.0), None);
+    }
+
+    #[test]
+    fn test_trim_samples() {
+        let mut samples = vec![0.0; 500];
+        samples.extend(vec![0.5; 1000]);
+        samples.extend(vec![0.0; 2000]);
+
+        // 1000Hz なので 1ms = 1フレーム
+        let trimmed = trim_samples(&samples, 1, 1000, &config(100, 200));
+        assert_eq!(trimmed.len(), 100 + 1000 + 200);
+        assert!(trimmed[..100].iter().all(|s| *s == 0.0));
+        assert!(trimmed[100..1100].iter().all(|s| *s == 0.5));
+
+        // 無音のみならパディングだけ残る
+        assert_eq!(trim_samples(&[0.0; 10], 1, 1000, &config(3, 4)).len(), 7);
+    }
+
+    #[test]
+    fn test_trim_file() {
+        let input = std::env::temp_dir().join("auto-mv-trim-in.wav");
+        let output = std::env::temp_dir().join("auto-mv-trim-out.wav");
+        let mut samples = vec![0i16; 8000];
+        samples.extend(vec![8000i16; 8000]);
+        samples.extend(vec![0i16; 8000]);
+        wav::write_wav(input.to_str().unwrap(), 8000, 1, &samples).unwrap();
+
+        let trimmed = trim_file(
+            input.to_str().unwrap(),
+            output.to_str().unwrap(),
+            &config(250, 500),
+        )
+        .unwrap();
+        assert_eq!(trimmed.duration, Duration::from_millis(1750));
+
+        // 発話の開始（1秒）は余白の直後（0.25秒）に移る
+        assert_eq!(trimmed.head_removed, Duration::from_secs(1));
+        assert_eq!(
+            trimmed.map_time(Duration::from_millis(1500)),
+            Duration::from_millis(750)
+        );
+        assert_eq!(trimmed.map_time(Duration::ZERO), Duration::from_millis(250));
+        assert_eq!(
+            trimmed.map_time(Duration::from_secs(3)),
+            Duration::from_millis(1750)
+        );
+        let (info, _) = wav::read_samples(output.to_str().unwrap()).unwrap();
+        assert_eq!((info.format, info.bits_per_sample), (SampleFormat::Pcm, 16));
+    }
+
+    #[test]
+    fn test_trim_file_float() {
+        // 浮動小数点の WAV は 16bit に量子化せず、そのままの値で書き出す
+        let input = std::env::temp_dir().join("auto-mv-trim-f32-in.wav");
+        let output = std::env::temp_dir().join("auto-mv-trim-f32-out.wav");
+        let mut samples = vec![0.0f32; 800];
+        samples.extend(vec![0.123_456_7f32; 800]);
+        wav::write_wav_f32(input.to_str().unwrap(), 8000, 1, &samples).unwrap();
+
+        trim_file(
+            input.to_str().unwrap(),
+            output.to_str().unwrap(),
+            &config(0, 0),
+        )
+        .unwrap();
+        let (info, trimmed) = wav::read_samples(output.to_str().unwrap()).unwrap();
+        assert_eq!(info.format, SampleFormat::Float);
+        assert_eq!(trimmed, vec![0.123_456_7f32; 800]);
+    }
+}
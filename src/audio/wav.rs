@@ -0,0 +1,677 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    time::Duration,
+};
+
+/// WAVE_FORMAT_EXTENSIBLE のフォーマットタグ
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// WAV の読み込みエラー
+#[derive(Debug)]
+pub enum WavError {
+    Io(io::Error),
+    /// 先頭が "RIFF" ではない
+    NotRiff,
+    /// RIFF の種別が "WAVE" ではない
+    NotWave,
+    /// fmt チャンクがない
+    MissingFmt,
+    /// data チャンクがない
+    MissingData,
+    /// チャンクがファイル末尾で途切れている
+    Truncated {
+        chunk: String,
+    },
+    /// fmt チャンクの内容が不正
+    InvalidFmt(String),
+    /// 対応していないフォーマットタグ
+    UnsupportedFormat(u16),
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WavError::Io(e) => write!(f, "wav: {}", e),
+            WavError::NotRiff => write!(f, "wav: not a RIFF file"),
+            WavError::NotWave => write!(f, "wav: RIFF type is not WAVE"),
+            WavError::MissingFmt => write!(f, "wav: fmt chunk not found"),
+            WavError::MissingData => write!(f, "wav: data chunk not found"),
+            WavError::Truncated { chunk } => write!(f, "wav: '{}' chunk is truncated", chunk),
+            WavError::InvalidFmt(reason) => write!(f, "wav: invalid fmt chunk: {}", reason),
+            WavError::UnsupportedFormat(tag) => {
+                write!(f, "wav: unsupported format tag 0x{:04x}", tag)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WavError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WavError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WavError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => WavError::Truncated {
+                chunk: "RIFF".to_string(),
+            },
+            _ => WavError::Io(e),
+        }
+    }
+}
+
+impl From<WavError> for io::Error {
+    fn from(e: WavError) -> Self {
+        match e {
+            WavError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// サンプルの符号化方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// 整数 PCM
+    Pcm,
+    /// IEEE 浮動小数点
+    Float,
+    /// G.711 A-law
+    Alaw,
+    /// G.711 μ-law
+    Mulaw,
+}
+
+impl SampleFormat {
+    fn from_tag(tag: u16) -> Result<Self, WavError> {
+        match tag {
+            0x0001 => Ok(SampleFormat::Pcm),
+            0x0003 => Ok(SampleFormat::Float),
+            0x0006 => Ok(SampleFormat::Alaw),
+            0x0007 => Ok(SampleFormat::Mulaw),
+            tag => Err(WavError::UnsupportedFormat(tag)),
+        }
+    }
+}
+
+/// WAV ファイルのフォーマット情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WavInfo {
+    pub format: SampleFormat,
+    pub channels: u16,
+    pub sample_rate: u32,
+    /// 1サンプルあたりのビット数（コンテナのサイズ）
+    pub bits_per_sample: u16,
+    /// 1フレーム（全チャンネル分）のバイト数
+    pub block_align: u16,
+    /// チャンネルあたりのサンプル数
+    pub frame_count: u64,
+    /// data チャンク本体の先頭位置
+    pub data_offset: u64,
+    /// data チャンク本体のバイト数
+    pub data_len: u64,
+}
+
+impl WavInfo {
+    /// ファイルからフォーマット情報を読み込む
+    pub fn from_file(path: &str) -> Result<Self, WavError> {
+        let file = File::open(path).map_err(WavError::Io)?;
+        WavInfo::from_reader(BufReader::new(file))
+    }
+
+    /// RIFF チャンクをたどり、fmt と data の情報を得る
+    pub fn from_reader<R: Read + Seek>(mut reader: R) -> Result<Self, WavError> {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != b"RIFF" {
+            return Err(WavError::NotRiff);
+        }
+        if &header[8..12] != b"WAVE" {
+            return Err(WavError::NotWave);
+        }
+
+        let mut fmt: Option<Fmt> = None;
+        let mut data: Option<(u64, u64)> = None;
+        let mut pos = 12u64;
+
+        while pos + 8 <= file_len {
+            let mut chunk = [0u8; 8];
+            reader.read_exact(&mut chunk)?;
+            let id = &chunk[0..4];
+            let size = u32::from_le_bytes(chunk[4..8].try_into().unwrap()) as u64;
+            let body = pos + 8;
+
+            match id {
+                b"fmt " => {
+                    if body + size > file_len {
+                        return Err(WavError::Truncated {
+                            chunk: "fmt ".to_string(),
+                        });
+                    }
+                    let mut buf = vec![0u8; size as usize];
+                    reader.read_exact(&mut buf)?;
+                    fmt = Some(Fmt::parse(&buf)?);
+                }
+                b"data" => {
+                    // ストリーミングで書き出されたファイルはサイズが未確定（0 や 0xFFFFFFFF）のことがある
+                    let len = if size == 0 || size == u32::MAX as u64 || body + size > file_len {
+                        file_len - body
+                    } else {
+                        size
+                    };
+                    data = Some((body, len));
+                }
+                _ => {}
+            }
+
+            if fmt.is_some() && data.is_some() {
+                break;
+            }
+
+            // チャンクは偶数バイト境界に揃えられる
+            pos = body + size + (size & 1);
+            if pos > file_len {
+                break;
+            }
+            reader.seek(SeekFrom::Start(pos))?;
+        }
+
+        let fmt = fmt.ok_or(WavError::MissingFmt)?;
+        let (data_offset, data_len) = data.ok_or(WavError::MissingData)?;
+
+        Ok(WavInfo {
+            format: fmt.format,
+            channels: fmt.channels,
+            sample_rate: fmt.sample_rate,
+            bits_per_sample: fmt.bits_per_sample,
+            block_align: fmt.block_align,
+            frame_count: data_len / fmt.block_align as u64,
+            data_offset,
+            data_len,
+        })
+    }
+
+    /// 再生時間（フレーム数から算出）
+    pub fn duration(&self) -> Duration {
+        let rate = self.sample_rate as u64;
+        let secs = self.frame_count / rate;
+        let nanos = (self.frame_count % rate) * 1_000_000_000 / rate;
+        Duration::new(secs, nanos as u32)
+    }
+}
+
+/// fmt チャンクの内容
+struct Fmt {
+    format: SampleFormat,
+    channels: u16,
+    sample_rate: u32,
+    block_align: u16,
+    bits_per_sample: u16,
+}
+
+impl Fmt {
+    fn parse(buf: &[u8]) -> Result<Self, WavError> {
+        if buf.len() < 16 {
+            return Err(WavError::InvalidFmt(format!(
+                "chunk size {} is smaller than 16",
+                buf.len()
+            )));
+        }
+        let u16_at = |i: usize| u16::from_le_bytes(buf[i..i + 2].try_into().unwrap());
+        let u32_at = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+
+        let mut tag = u16_at(0);
+        let channels = u16_at(2);
+        let sample_rate = u32_at(4);
+        let block_align = u16_at(12);
+        let bits_per_sample = u16_at(14);
+
+        if tag == WAVE_FORMAT_EXTENSIBLE {
+            // cbSize(2) + validBits(2) + channelMask(4) + SubFormat GUID(16)
+            if buf.len() < 40 {
+                return Err(WavError::InvalidFmt(
+                    "WAVE_FORMAT_EXTENSIBLE requires a 40 byte fmt chunk".to_string(),
+                ));
+            }
+            // SubFormat GUID の先頭2バイトが実際のフォーマットタグ
+            tag = u16_at(24);
+        }
+
+        if channels == 0 {
+            return Err(WavError::InvalidFmt("channels is 0".to_string()));
+        }
+        if sample_rate == 0 {
+            return Err(WavError::InvalidFmt("sample rate is 0".to_string()));
+        }
+        if block_align == 0 {
+            return Err(WavError::InvalidFmt("block align is 0".to_string()));
+        }
+
+        Ok(Fmt {
+            format: SampleFormat::from_tag(tag)?,
+            channels,
+            sample_rate,
+            block_align,
+            bits_per_sample,
+        })
+    }
+}
+
+/// 音声ファイルの再生時間を取得する
+pub fn duration(path: &str) -> Result<Duration, WavError> {
+    Ok(WavInfo::from_file(path)?.duration())
+}
+
+/// WAV のサンプルを読み込み、[-1.0, 1.0] の f32 に変換する
+/// 戻り値のサンプルはチャンネルをインターリーブしたもの
+pub fn read_samples(path: &str) -> Result<(WavInfo, Vec<f32>), WavError> {
+    let info = WavInfo::from_file(path)?;
+    let mut file = BufReader::new(File::open(path).map_err(WavError::Io)?);
+    file.seek(SeekFrom::Start(info.data_offset))?;
+    let mut data = vec![0u8; info.data_len as usize];
+    file.read_exact(&mut data)
+        .map_err(|_| WavError::Truncated {
+            chunk: "data".to_string(),
+        })?;
+
+    let width = (info.bits_per_sample as usize).div_ceil(8);
+    let frame = width * info.channels as usize;
+    if width == 0 || frame > info.block_align as usize {
+        return Err(WavError::InvalidFmt(format!(
+            "{} bits x {} channels does not fit block align {}",
+            info.bits_per_sample, info.channels, info.block_align
+        )));
+    }
+
+    let mut samples = Vec::with_capacity(info.frame_count as usize * info.channels as usize);
+    for block in data.chunks_exact(info.block_align as usize) {
+        for b in block[..frame].chunks_exact(width) {
+            let sample = match (info.format, width) {
+                (SampleFormat::Pcm, 1) => (b[0] as f32 - 128.0) / 128.0,
+                (SampleFormat::Pcm, 2) => i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+                (SampleFormat::Pcm, 3) => {
+                    (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0
+                }
+                (SampleFormat::Pcm, 4) => {
+                    i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0
+                }
+                (SampleFormat::Float, 4) => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                (SampleFormat::Float, 8) => f64::from_le_bytes(b.try_into().unwrap()) as f32,
+                (SampleFormat::Alaw, 1) => alaw_to_i16(b[0]) as f32 / 32768.0,
+                (SampleFormat::Mulaw, 1) => mulaw_to_i16(b[0]) as f32 / 32768.0,
+                _ => {
+                    return Err(WavError::InvalidFmt(format!(
+                        "{} bit samples are not supported for {:?}",
+                        info.bits_per_sample, info.format
+                    )))
+                }
+            };
+            samples.push(sample);
+        }
+    }
+
+    Ok((info, samples))
+}
+
+/// G.711 A-law を 16bit に伸張する
+fn alaw_to_i16(value: u8) -> i16 {
+    let value = value ^ 0x55;
+    let exponent = (value >> 4) & 0x07;
+    let mantissa = (value & 0x0f) as i16;
+    let magnitude = match exponent {
+        0 => (mantissa << 4) + 8,
+        e => ((mantissa << 4) + 0x108) << (e - 1),
+    };
+    if value & 0x80 != 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
+
+/// G.711 μ-law を 16bit に伸張する
+fn mulaw_to_i16(value: u8) -> i16 {
+    let value = !value;
+    let exponent = (value >> 4) & 0x07;
+    let mantissa = (value & 0x0f) as i16;
+    let magnitude = (((mantissa << 3) + 0x84) << exponent) - 0x84;
+    if value & 0x80 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// [-1.0, 1.0] の f32 サンプルを 16bit に変換する
+pub fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * 32767.0).round() as i16
+}
+
+/// 16bit PCM の WAV を書き出す
+/// `samples` はチャンネルをインターリーブしたもの
+pub fn write_wav(
+    path: &str,
+    sample_rate: u32,
+    channels: u16,
+    samples: &[i16],
+) -> Result<(), io::Error> {
+    let mut w = BufWriter::new(File::create(path)?);
+    write_header(&mut w, 0x0001, 16, sample_rate, channels, samples.len())?;
+    for s in samples {
+        w.write_all(&s.to_le_bytes())?;
+    }
+    w.flush()
+}
+
+/// 32bit 浮動小数点の WAV を書き出す（1.0 を超えるサンプルもそのまま残す）
+pub fn write_wav_f32(
+    path: &str,
+    sample_rate: u32,
+    channels: u16,
+    samples: &[f32],
+) -> Result<(), io::Error> {
+    let mut w = BufWriter::new(File::create(path)?);
+    write_header(&mut w, 0x0003, 32, sample_rate, channels, samples.len())?;
+    for s in samples {
+        w.write_all(&s.to_le_bytes())?;
+    }
+    w.flush()
+}
+
+/// RIFF・fmt・data のヘッダを書き出す
+fn write_header<W: Write>(
+    w: &mut W,
+    format_tag: u16,
+    bits_per_sample: u16,
+    sample_rate: u32,
+    channels: u16,
+    sample_count: usize,
+) -> Result<(), io::Error> {
+    let block_align = channels * bits_per_sample / 8;
+    let data_size = (sample_count * (bits_per_sample / 8) as usize) as u32;
+
+    w.write_all(b"RIFF")?;
+    w.write_all(&(36 + data_size).to_le_bytes())?;
+    w.write_all(b"WAVE")?;
+
+    w.write_all(b"fmt ")?;
+    w.write_all(&16u32.to_le_bytes())?;
+    w.write_all(&format_tag.to_le_bytes())?;
+    w.write_all(&channels.to_le_bytes())?;
+    w.write_all(&sample_rate.to_le_bytes())?;
+    w.write_all(&(sample_rate * block_align as u32).to_le_bytes())?; // バイトレート
+    w.write_all(&block_align.to_le_bytes())?;
+    w.write_all(&bits_per_sample.to_le_bytes())?; // ビット深度
+
+    w.write_all(b"data")?;
+    w.write_all(&data_size.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// テスト用に任意のチャンク列から WAV を組み立てる
+    fn riff(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut body = b"WAVE".to_vec();
+        for (id, data) in chunks {
+            body.extend_from_slice(*id);
+            body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            body.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+        let mut out = b"RIFF".to_vec();
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend(body);
+        out
+    }
+
+    fn fmt_chunk(tag: u16, channels: u16, rate: u32, bits: u16) -> Vec<u8> {
+        let block_align = channels * bits / 8;
+        let mut v = Vec::new();
+        v.extend_from_slice(&tag.to_le_bytes());
+        v.extend_from_slice(&channels.to_le_bytes());
+        v.extend_from_slice(&rate.to_le_bytes());
+        v.extend_from_slice(&(rate * block_align as u32).to_le_bytes());
+        v.extend_from_slice(&block_align.to_le_bytes());
+        v.extend_from_slice(&bits.to_le_bytes());
+        v
+    }
+
+    fn extensible_chunk(sub_format: u16, channels: u16, rate: u32, bits: u16) -> Vec<u8> {
+        let mut v = fmt_chunk(WAVE_FORMAT_EXTENSIBLE, channels, rate, bits);
+        v.extend_from_slice(&22u16.to_le_bytes()); // cbSize
+        v.extend_from_slice(&bits.to_le_bytes()); // validBitsPerSample
+        v.extend_from_slice(&3u32.to_le_bytes()); // channelMask
+        v.extend_from_slice(&sub_format.to_le_bytes());
+        v.extend_from_slice(&[
+            0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+        ]);
+        v
+    }
+
+    fn parse(bytes: Vec<u8>) -> Result<WavInfo, WavError> {
+        WavInfo::from_reader(Cursor::new(bytes))
+    }
+
+    #[test]
+    fn test_pcm16_mono() {
+        let info = parse(riff(&[
+            (b"fmt ", fmt_chunk(1, 1, 24000, 16)),
+            (b"data", vec![0; 48000]),
+        ]))
+        .unwrap();
+
+        assert_eq!(info.format, SampleFormat::Pcm);
+        assert_eq!(info.channels, 1);
+        assert_eq!(info.sample_rate, 24000);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.frame_count, 24000);
+        assert_eq!(info.data_offset, 44);
+        assert_eq!(info.duration(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_list_and_fact_chunks_before_data() {
+        let info = parse(riff(&[
+            (b"fmt ", fmt_chunk(1, 2, 44100, 16)),
+            (b"LIST", b"INFOISFT\x05\x00\x00\x00Lavf\x00".to_vec()),
+            (b"fact", 44100u32.to_le_bytes().to_vec()),
+            (b"data", vec![0; 44100 * 4 / 2]),
+        ]))
+        .unwrap();
+
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.frame_count, 22050);
+        assert_eq!(info.duration(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_odd_sized_chunk_is_padded() {
+        let info = parse(riff(&[
+            (b"fmt ", fmt_chunk(1, 1, 8000, 16)),
+            (b"junk", vec![1, 2, 3]),
+            (b"data", vec![0; 1600]),
+        ]))
+        .unwrap();
+
+        assert_eq!(info.frame_count, 800);
+        assert_eq!(info.duration(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_24bit_stereo() {
+        let info = parse(riff(&[
+            (b"fmt ", fmt_chunk(1, 2, 48000, 24)),
+            (b"data", vec![0; 48000 * 6]),
+        ]))
+        .unwrap();
+
+        assert_eq!(info.bits_per_sample, 24);
+        assert_eq!(info.block_align, 6);
+        assert_eq!(info.frame_count, 48000);
+        assert_eq!(info.duration(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_float32() {
+        let info = parse(riff(&[
+            (b"fmt ", fmt_chunk(3, 1, 16000, 32)),
+            (b"data", vec![0; 16000 * 4 * 3]),
+        ]))
+        .unwrap();
+
+        assert_eq!(info.format, SampleFormat::Float);
+        assert_eq!(info.duration(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_extensible() {
+        let info = parse(riff(&[
+            (b"fmt ", extensible_chunk(3, 2, 48000, 32)),
+            (b"data", vec![0; 48000 * 8 / 4]),
+        ]))
+        .unwrap();
+
+        assert_eq!(info.format, SampleFormat::Float);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.duration(), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_exact_fractional_duration() {
+        let info = parse(riff(&[
+            (b"fmt ", fmt_chunk(1, 1, 44100, 16)),
+            (b"data", vec![0; 2 * 44101]),
+        ]))
+        .unwrap();
+
+        assert_eq!(info.frame_count, 44101);
+        assert_eq!(info.duration(), Duration::new(1, 22_675));
+    }
+
+    #[test]
+    fn test_streaming_data_size() {
+        let mut bytes = riff(&[
+            (b"fmt ", fmt_chunk(1, 1, 8000, 16)),
+            (b"data", vec![0; 800]),
+        ]);
+        // サイズ未確定のまま書き出されたファイル
+        bytes[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let info = parse(bytes).unwrap();
+        assert_eq!(info.data_len, 800);
+        assert_eq!(info.duration(), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            parse(b"RIFX\0\0\0\0WAVE".to_vec()),
+            Err(WavError::NotRiff)
+        ));
+        assert!(matches!(
+            parse(b"RIFF\0\0\0\0AVI ".to_vec()),
+            Err(WavError::NotWave)
+        ));
+        assert!(matches!(
+            parse(b"RIFF".to_vec()),
+            Err(WavError::Truncated { .. })
+        ));
+        assert!(matches!(
+            parse(riff(&[(b"data", vec![0; 8])])),
+            Err(WavError::MissingFmt)
+        ));
+        assert!(matches!(
+            parse(riff(&[(b"fmt ", fmt_chunk(1, 1, 8000, 16))])),
+            Err(WavError::MissingData)
+        ));
+        assert!(matches!(
+            parse(riff(&[(b"fmt ", vec![1, 0, 1, 0])])),
+            Err(WavError::InvalidFmt(_))
+        ));
+        assert!(matches!(
+            parse(riff(&[
+                (b"fmt ", fmt_chunk(1, 0, 8000, 16)),
+                (b"data", vec![])
+            ])),
+            Err(WavError::InvalidFmt(_))
+        ));
+        assert!(matches!(
+            parse(riff(&[
+                (b"fmt ", fmt_chunk(0x0055, 1, 8000, 16)),
+                (b"data", vec![])
+            ])),
+            Err(WavError::UnsupportedFormat(0x0055))
+        ));
+
+        let mut truncated = riff(&[(b"fmt ", fmt_chunk(1, 1, 8000, 16))]);
+        truncated.truncate(30);
+        assert!(matches!(parse(truncated), Err(WavError::Truncated { .. })));
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let path = std::env::temp_dir().join("auto-mv-wav-roundtrip.wav");
+        let path = path.to_str().unwrap();
+        write_wav(path, 22050, 2, &[0; 22050 * 2]).unwrap();
+
+        let info = WavInfo::from_file(path).unwrap();
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.sample_rate, 22050);
+        assert_eq!(duration(path).unwrap(), Duration::from_secs(1));
+
+        // 浮動小数点は 1.0 を超えても丸めない
+        write_wav_f32(path, 48000, 1, &[1.5, -0.25]).unwrap();
+        let (info, samples) = read_samples(path).unwrap();
+        assert_eq!(info.format, SampleFormat::Float);
+        assert_eq!(samples, vec![1.5, -0.25]);
+    }
+
+    #[test]
+    fn test_read_samples() {
+        let path = std::env::temp_dir().join("auto-mv-wav-samples.wav");
+        let path = path.to_str().unwrap();
+        write_wav(path, 8000, 1, &[0, 16384, -32768, 32767]).unwrap();
+        let (info, samples) = read_samples(path).unwrap();
+        assert_eq!(info.frame_count, 4);
+        assert_eq!(samples, vec![0.0, 0.5, -1.0, 32767.0 / 32768.0]);
+
+        // 24bit と 32bit float
+        let mut data = Vec::new();
+        data.extend_from_slice(&[0x00, 0x00, 0x40]);
+        data.extend_from_slice(&[0x00, 0x00, 0xc0]);
+        std::fs::write(
+            path,
+            riff(&[(b"fmt ", fmt_chunk(1, 1, 8000, 24)), (b"data", data)]),
+        )
+        .unwrap();
+        assert_eq!(read_samples(path).unwrap().1, vec![0.5, -0.5]);
+
+        let data = [0.25f32.to_le_bytes(), (-1.0f32).to_le_bytes()].concat();
+        std::fs::write(
+            path,
+            riff(&[(b"fmt ", fmt_chunk(3, 1, 8000, 32)), (b"data", data)]),
+        )
+        .unwrap();
+        assert_eq!(read_samples(path).unwrap().1, vec![0.25, -1.0]);
+
+        // G.711 の無音
+        assert_eq!(mulaw_to_i16(0xff), 0);
+        assert_eq!(alaw_to_i16(0xd5), 8);
+        assert_eq!(to_i16(2.0), 32767);
+    }
+}
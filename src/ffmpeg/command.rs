@@ -1,12 +1,20 @@
 use std::{env, fs, io, process::Command, time::Duration};
 
+use crate::{
+    audio::mix::{db_to_linear, Ducking, MixInput},
+    models::config::AudioFormat,
+    speech::{
+        effects::{Band, Effects},
+        prosody::Prosody,
+    },
+};
+
 pub fn brank(
     output_path: &str,
     resolution: &str,
     duration: u32,
     frame_rate: u32,
 ) -> Result<(), io::Error> {
-    println!("env: {}", env::var("OVERWRITE").unwrap());
     let is_overwrite = match env::var("OVERWRITE") {
         Ok(val) => val == "true",
         Err(_) => false,
@@ -40,10 +48,7 @@ pub fn brank(
             "Error: Failed to generate blank video - status: {:?}",
             status
         );
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to generate blank video",
-        ));
+        return Err(io::Error::other("Failed to generate blank video"));
     }
 
     Ok(())
@@ -86,10 +91,7 @@ pub fn add_image_overlay(
 
     if !status.success() {
         eprintln!("Error: Failed to add image overlay - status: {:?}", status);
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to add image overlay",
-        ));
+        return Err(io::Error::other("Failed to add image overlay"));
     }
 
     // 一時ファイルをリネーム
@@ -127,102 +129,160 @@ pub fn add_subs(
 
     if !status.success() {
         eprintln!("Error: Failed to add subtitles - status: {:?}", status);
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to add subtitles",
-        ));
+        return Err(io::Error::other("Failed to add subtitles"));
     }
 
     Ok(())
 }
 
-pub fn add_audio(
-    input_video: &str,
-    audio_file: &str,
-    output_video: &str,
-    start: Duration,
-    weight: i32,
-) -> Result<(), io::Error> {
-    let is_overwrite = env::var("OVERWRITE").unwrap() == "true";
-    let is_nvidia = env::var("NVIDIA").unwrap() == "true";
+/// 動画の映像に音声トラックを付ける（映像に元からある音声は使わない）
+pub fn add_audio(input_video: &str, audio_file: &str, output_video: &str) -> Result<(), io::Error> {
+    let is_overwrite = env::var("OVERWRITE").is_ok_and(|v| v == "true");
+    let is_nvidia = env::var("NVIDIA").is_ok_and(|v| v == "true");
 
-    // startのチェック
-    if start.as_secs() == 0 {
-        // startが0秒の場合は、直接音声を追加する（itsoffsetは不要）
-        let status = Command::new("ffmpeg")
-            .args([
-                if is_overwrite { "-y" } else { "-n" },
-                "-i",
-                input_video,
-                "-i",
-                audio_file,
-                "-c:v",
-                if is_nvidia { "h264_nvenc" } else { "copy" }, // 映像エンコーディング
-                "-c:a",
-                "aac", // 音声をAACでエンコード
-                "-b:v",
-                "5M", // ビットレートを5Mbpsに設定
-                output_video,
-            ])
-            .status()?;
-
-        // エラーチェック
-        if !status.success() {
-            eprintln!(
-                "Error: Failed to add audio (start = 0s) - status: {:?}",
-                status
-            );
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Failed to add audio (start=0s)",
-            ));
-        }
-    } else {
-        // startが0秒ではない場合、音声にディレイを加える
-        let start_milliseconds = start.as_millis(); // start時間を秒に変換
-
-        // 一時ファイル名を生成
-        let temp_output_video = format!("{}_tmp.mp4", output_video);
-
-        let status = Command::new("ffmpeg")
-            .args([
-                if is_overwrite { "-y" } else { "-n" },
-                "-i",
-                input_video,
-                "-i",
-                audio_file,
-                "-c:v",
-                if is_nvidia { "h264_nvenc" } else { "copy" }, // 映像エンコーディング
-                "-c:a",
-                "aac", // 音声をAACでエンコード
-                "-b:v",
-                "5M", // 映像ビットレート
-                "-filter_complex",
-                // `adelay` フィルタを使用して音声ファイルを遅延させる
-                // `loudnorm` フィルタを使用して音声を正規化する
-                &format!(
-                    "[1:a]adelay={}|{},volume=1,loudnorm=I=-16:TP=-1.5:LRA=11.5[delayed_audio];[0:a][delayed_audio]amix=inputs=2:normalize=1:weights=1 {:.2}",
-                    start_milliseconds, start_milliseconds,
-                    1.0/weight as f32
-                ),
-                &temp_output_video,
-            ])
-            .status()?;
-
-        // エラーチェック
-        if !status.success() {
-            eprintln!(
-                "Error: Failed to add audio (start delay) - status: {:?}",
-                status
+    let status = Command::new("ffmpeg")
+        .args([
+            if is_overwrite { "-y" } else { "-n" },
+            "-i",
+            input_video,
+            "-i",
+            audio_file,
+            "-map",
+            "0:v",
+            "-map",
+            "1:a",
+            "-c:v",
+            if is_nvidia { "h264_nvenc" } else { "copy" }, // 映像エンコーディング
+            "-c:a",
+            "aac", // 音声をAACでエンコード
+            "-b:v",
+            "5M", // ビットレートを5Mbpsに設定
+            output_video,
+        ])
+        .status()?;
+
+    if !status.success() {
+        eprintln!("Error: Failed to add audio - status: {:?}", status);
+        return Err(io::Error::other("Failed to add audio"));
+    }
+
+    Ok(())
+}
+
+/// 音声をまとめてミックスするフィルタを生成する
+/// 入力 `i` は ffmpeg の `i` 番目の入力。各音声を開始時刻まで遅らせて `gain_db` をかけ、
+/// 正規化せずに足し合わせる（`amix` の `normalize=0`）。`ceiling_db` を指定すると最後にリミッターをかける
+pub fn mix_filter(inputs: &[MixInput], ceiling_db: Option<f64>) -> String {
+    let mut filters: Vec<String> = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            let mut chain = Vec::new();
+            if let Some(length) = input.length {
+                chain.push(format!(
+                    "atrim=end={:.3},asetpts=PTS-STARTPTS",
+                    length.as_secs_f64()
+                ));
+                if !input.fade_out.is_zero() {
+                    chain.push(format!(
+                        "afade=t=out:st={:.3}:d={:.3}",
+                        (length - input.fade_out).as_secs_f64(),
+                        input.fade_out.as_secs_f64()
+                    ));
+                }
+            }
+            if !input.fade_in.is_zero() {
+                chain.push(format!("afade=t=in:d={:.3}", input.fade_in.as_secs_f64()));
+            }
+            chain.push(format!("adelay={}:all=1", input.start.as_millis()));
+            chain.push(format!("volume={:.2}dB", input.gain_db));
+            if let Some(ducking) = &input.ducking {
+                chain.push(format!(
+                    "volume=eval=frame:volume='{}'",
+                    ducking_expression(ducking)
+                ));
+            }
+            format!("[{}:a]{}[a{}]", i, chain.join(","), i)
+        })
+        .collect();
+
+    let labels: String = (0..inputs.len()).map(|i| format!("[a{}]", i)).collect();
+    let mut mix = format!(
+        "{}amix=inputs={}:duration=longest:dropout_transition=0:normalize=0",
+        labels,
+        inputs.len()
+    );
+    if let Some(ceiling) = ceiling_db {
+        // 自動で音量を上げる level は無効にし、ピークだけを抑える
+        mix.push_str(&format!(
+            ",alimiter=limit={:.4}:level=disabled",
+            db_to_linear(ceiling).clamp(0.0625, 1.0)
+        ));
+    }
+    filters.push(format!("{}[mixed]", mix));
+    filters.join(";")
+}
+
+/// 読み上げの区間で音量を下げる `volume` フィルタの式（`t` は動画の先頭からの秒数）
+/// 区間ごとの台形（`attack` で下がり `release` で戻る）の和を dB の下げ幅にする
+pub fn ducking_expression(ducking: &Ducking) -> String {
+    if ducking.intervals.is_empty() {
+        return "1".to_string();
+    }
+    // 0 から 1 への傾斜。長さ 0 なら段差
+    let ramp = |from: String, length: Duration| match length.is_zero() {
+        true => format!("gte({},0)", from),
+        false => format!("clip(({})/{:.3},0,1)", from, length.as_secs_f64()),
+    };
+    let terms: Vec<String> = ducking
+        .intervals
+        .iter()
+        .map(|(start, end)| {
+            let onset = start.as_secs_f64() - ducking.attack.as_secs_f64();
+            let rise = match onset < 0.0 {
+                true => ramp(format!("t+{:.3}", -onset), ducking.attack),
+                false => ramp(format!("t-{:.3}", onset), ducking.attack),
+            };
+            let fall = ramp(
+                format!("{:.3}-t", (*end + ducking.release).as_secs_f64()),
+                ducking.release,
             );
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Failed to add audio (start delay)",
-            ));
-        }
+            format!("min({},{})", rise, fall)
+        })
+        .collect();
+    format!("pow(10,-{:.2}*({})/20)", ducking.depth_db, terms.join("+"))
+}
 
-        // 一時ファイルをリネーム
-        fs::rename(&temp_output_video, output_video)?;
+/// 音声を1回でミックスし、音声ファイルに書き出す
+pub fn mix_audio(
+    inputs: &[MixInput],
+    output_audio: &str,
+    ceiling_db: Option<f64>,
+) -> Result<(), io::Error> {
+    if inputs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no audio to mix",
+        ));
+    }
+
+    let mut args = vec!["-y".to_string()]; // 中間ファイルのため常に上書き
+    for input in inputs {
+        args.extend(["-i".to_string(), input.path.clone()]);
+    }
+    args.extend([
+        "-filter_complex".to_string(),
+        mix_filter(inputs, ceiling_db),
+        "-map".to_string(),
+        "[mixed]".to_string(),
+        output_audio.to_string(),
+    ]);
+
+    let status = Command::new("ffmpeg").args(&args).status()?;
+
+    if !status.success() {
+        eprintln!("Error: Failed to mix audio - status: {:?}", status);
+        return Err(io::Error::other("Failed to mix audio"));
     }
 
     Ok(())
@@ -252,10 +312,7 @@ pub fn cut(input_video: &str, output_video: &str, duration: Duration) -> Result<
 
     if !status.success() {
         eprintln!("Error: Failed to split video - status: {:?}", status);
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to split video",
-        ));
+        return Err(io::Error::other("Failed to split video"));
     }
 
     Ok(())
@@ -292,11 +349,400 @@ pub fn crop(
 
     if !status.success() {
         eprintln!("Error: Failed to split video - status: {:?}", status);
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to split video",
+        return Err(io::Error::other("Failed to split video"));
+    }
+
+    Ok(())
+}
+
+/// loudnorm の1パス目で音声の音量を解析し、結果を含む標準エラー出力を返す
+pub fn loudnorm_analysis(input_audio: &str) -> Result<String, io::Error> {
+    let output = Command::new("ffmpeg")
+        .args([
+            "-hide_banner",
+            "-nostats",
+            "-i",
+            input_audio,
+            "-af",
+            "loudnorm=print_format=json",
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()?;
+
+    if !output.status.success() {
+        eprintln!(
+            "Error: Failed to analyze loudness - status: {:?}",
+            output.status
+        );
+        return Err(io::Error::other("Failed to analyze loudness"));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+/// 音声ファイルをプロジェクトの形式（サンプリングレート・チャンネル数・サンプル形式）の WAV に変換する
+pub fn convert_audio(
+    input_audio: &str,
+    output_audio: &str,
+    format: &AudioFormat,
+) -> Result<(), io::Error> {
+    let status = Command::new("ffmpeg")
+        .args([
+            "-y", // キャッシュへの書き出しのため常に上書き
+            "-v",
+            "error",
+            "-i",
+            input_audio,
+            "-vn",
+            "-ar",
+            &format.sample_rate.to_string(),
+            "-ac",
+            &format.channels.to_string(),
+            "-c:a",
+            format.sample_format.codec(),
+            output_audio,
+        ])
+        .status()?;
+
+    if !status.success() {
+        eprintln!("Error: Failed to convert audio - status: {:?}", status);
+        return Err(io::Error::other("Failed to convert audio"));
+    }
+
+    Ok(())
+}
+
+/// 速度・高さ・音量を変更するオーディオフィルタを生成する
+/// asetrate で高さを変え、変わった速度を atempo で打ち消したうえで指定の速度にする
+/// 速度が正でない・高さが範囲外などで加工できない値はエラーにする
+pub fn prosody_filter(sample_rate: u32, prosody: &Prosody) -> Result<String, io::Error> {
+    prosody
+        .validate()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut filters = Vec::new();
+
+    let pitch_factor = 2f64.powf(prosody.pitch() / 12.0);
+    if prosody.pitch() != 0.0 {
+        filters.push(format!(
+            "asetrate={},aresample={}",
+            (sample_rate as f64 * pitch_factor).round() as u32,
+            sample_rate
         ));
     }
 
+    // atempo は 1段あたり 0.5〜2.0 倍のため、範囲外は多段にする
+    let mut tempo = prosody.rate() / pitch_factor;
+    while tempo > 2.0 {
+        filters.push("atempo=2.0".to_string());
+        tempo /= 2.0;
+    }
+    while tempo < 0.5 {
+        filters.push("atempo=0.5".to_string());
+        tempo /= 0.5;
+    }
+    if (tempo - 1.0).abs() > 1e-6 {
+        filters.push(format!("atempo={:.6}", tempo));
+    }
+
+    if prosody.gain() != 0.0 {
+        filters.push(format!("volume={:.2}dB", prosody.gain()));
+    }
+
+    Ok(if filters.is_empty() {
+        "anull".to_string()
+    } else {
+        filters.join(",")
+    })
+}
+
+/// 音声ファイルの速度・高さ・音量を変更する
+pub fn adjust_prosody(
+    input_audio: &str,
+    output_audio: &str,
+    sample_rate: u32,
+    prosody: &Prosody,
+) -> Result<(), io::Error> {
+    let status = Command::new("ffmpeg")
+        .args([
+            "-y", // 一時ファイルからの変換のため常に上書き
+            "-i",
+            input_audio,
+            "-af",
+            &prosody_filter(sample_rate, prosody)?,
+            output_audio,
+        ])
+        .status()?;
+
+    if !status.success() {
+        eprintln!("Error: Failed to adjust prosody - status: {:?}", status);
+        return Err(io::Error::other("Failed to adjust prosody"));
+    }
+
     Ok(())
 }
+
+/// 話者ごとの音響効果のオーディオフィルタを生成する（効果がなければ None）
+/// 高さ → 帯域 → イコライザ → 残響 → 定位 の順にかける
+pub fn effects_filter(sample_rate: u32, effects: &Effects) -> Result<Option<String>, io::Error> {
+    if effects.is_empty() {
+        return Ok(None);
+    }
+    let mut filters = Vec::new();
+
+    if let Some(semitones) = effects.pitch_shift.filter(|s| *s != 0.0) {
+        let pitch = Prosody {
+            pitch: Some(semitones),
+            ..Prosody::default()
+        };
+        filters.push(prosody_filter(sample_rate, &pitch)?);
+    }
+
+    match effects.band {
+        Some(Band::Telephone) => filters.push("highpass=f=300,lowpass=f=3400".to_string()),
+        Some(Band::Radio) => filters.push(
+            "highpass=f=500,lowpass=f=4500,acompressor=threshold=0.1:ratio=6:attack=5:release=50"
+                .to_string(),
+        ),
+        None => {}
+    }
+
+    for eq in &effects.eq {
+        filters.push(format!(
+            "equalizer=f={}:t=q:w={}:g={}",
+            eq.frequency, eq.q, eq.gain_db
+        ));
+    }
+
+    if let Some(reverb) = effects.reverb {
+        let decay = reverb.decay.clamp(0.0, 1.0);
+        let delays: Vec<String> = (1..=3).map(|i| (reverb.room_ms * i).to_string()).collect();
+        let decays: Vec<String> = (1..=3).map(|i| format!("{:.3}", decay.powi(i))).collect();
+        filters.push(format!(
+            "aecho=in_gain=1:out_gain=0.8:delays={}:decays={}",
+            delays.join("|"),
+            decays.join("|")
+        ));
+    }
+
+    // モノラルにまとめてから、音量が変わらないように左右へ振り分ける
+    if let Some(pan) = effects.pan {
+        let angle = (pan.clamp(-1.0, 1.0) + 1.0) * std::f64::consts::FRAC_PI_4;
+        filters.push(format!(
+            "aformat=channel_layouts=mono,pan=stereo|c0={:.4}*c0|c1={:.4}*c0",
+            angle.cos(),
+            angle.sin()
+        ));
+    }
+
+    Ok(match filters.is_empty() {
+        true => None,
+        false => Some(filters.join(",")),
+    })
+}
+
+/// 音声ファイルに話者ごとの音響効果をかける
+pub fn apply_effects(
+    input_audio: &str,
+    output_audio: &str,
+    sample_rate: u32,
+    effects: &Effects,
+) -> Result<(), io::Error> {
+    let filter = effects_filter(sample_rate, effects)?.unwrap_or_else(|| "anull".to_string());
+    let status = Command::new("ffmpeg")
+        .args([
+            "-y", // 一時ファイルからの変換のため常に上書き
+            "-i",
+            input_audio,
+            "-af",
+            &filter,
+            output_audio,
+        ])
+        .status()?;
+
+    if !status.success() {
+        eprintln!("Error: Failed to apply effects - status: {:?}", status);
+        return Err(io::Error::other("Failed to apply effects"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{loudness, mix::Track, wav};
+    use crate::speech::effects::{EqBand, Reverb};
+
+    #[test]
+    fn test_prosody_filter() {
+        assert_eq!(prosody_filter(24000, &Prosody::default()).unwrap(), "anull");
+
+        let rate = Prosody {
+            rate: Some(1.25),
+            ..Prosody::default()
+        };
+        assert_eq!(prosody_filter(24000, &rate).unwrap(), "atempo=1.250000");
+
+        // 1オクターブ上げると 2倍速になるため、atempo=0.5 で元の速さに戻す
+        let pitch = Prosody {
+            pitch: Some(12.0),
+            ..Prosody::default()
+        };
+        assert_eq!(
+            prosody_filter(24000, &pitch).unwrap(),
+            "asetrate=48000,aresample=24000,atempo=0.500000"
+        );
+
+        let all = Prosody {
+            rate: Some(5.0),
+            pitch: None,
+            gain: Some(-3.0),
+        };
+        assert_eq!(
+            prosody_filter(24000, &all).unwrap(),
+            "atempo=2.0,atempo=2.0,atempo=1.250000,volume=-3.00dB"
+        );
+
+        // 速度が 0 や無限大では atempo を決められない
+        for rate in [0.0, -1.0, f64::INFINITY] {
+            let prosody = Prosody {
+                rate: Some(rate),
+                ..Prosody::default()
+            };
+            assert!(prosody_filter(24000, &prosody).is_err());
+        }
+        let pitch = Prosody {
+            pitch: Some(f64::INFINITY),
+            ..Prosody::default()
+        };
+        assert!(prosody_filter(24000, &pitch).is_err());
+    }
+
+    #[test]
+    fn test_effects_filter() {
+        assert_eq!(effects_filter(24000, &Effects::default()).unwrap(), None);
+
+        let effects = Effects {
+            pitch_shift: Some(12.0),
+            band: Some(Band::Telephone),
+            eq: vec![EqBand {
+                frequency: 1000.0,
+                gain_db: -3.0,
+                q: 2.0,
+            }],
+            reverb: Some(Reverb {
+                room_ms: 50,
+                decay: 0.5,
+            }),
+            pan: Some(0.0),
+        };
+        assert_eq!(
+            effects_filter(24000, &effects).unwrap().unwrap(),
+            "asetrate=48000,aresample=24000,atempo=0.500000,\
+             highpass=f=300,lowpass=f=3400,\
+             equalizer=f=1000:t=q:w=2:g=-3,\
+             aecho=in_gain=1:out_gain=0.8:delays=50|100|150:decays=0.500|0.250|0.125,\
+             aformat=channel_layouts=mono,pan=stereo|c0=0.7071*c0|c1=0.7071*c0"
+        );
+
+        // 右いっぱいなら左は無音
+        let right = Effects {
+            pan: Some(1.0),
+            ..Effects::default()
+        };
+        assert!(effects_filter(24000, &right)
+            .unwrap()
+            .unwrap()
+            .ends_with("c0=0.0000*c0|c1=1.0000*c0"));
+    }
+
+    #[test]
+    fn test_mix_filter() {
+        let inputs = [
+            MixInput::new("a.wav", Track::Narration, Duration::ZERO, 3.5),
+            MixInput::new("b.wav", Track::Narration, Duration::from_millis(2250), -6.0),
+        ];
+        assert_eq!(
+            mix_filter(&inputs, Some(-1.0)),
+            "[0:a]adelay=0:all=1,volume=3.50dB[a0];\
+             [1:a]adelay=2250:all=1,volume=-6.00dB[a1];\
+             [a0][a1]amix=inputs=2:duration=longest:dropout_transition=0:normalize=0,\
+             alimiter=limit=0.8913:level=disabled[mixed]"
+        );
+        assert!(!mix_filter(&inputs, None).contains("alimiter"));
+
+        // BGM は長さを切ってフェードし、読み上げに合わせて下げる
+        let ducking = Ducking::new(
+            12.0,
+            Duration::from_millis(300),
+            Duration::ZERO,
+            &[(Duration::from_secs(2), Duration::from_secs(4))],
+        );
+        let music = MixInput::new("bgm.mp3", Track::Music, Duration::from_secs(8), -20.0)
+            .with_length(
+                Duration::from_secs(10),
+                Duration::from_secs(2),
+                Duration::from_secs(3),
+            )
+            .with_ducking(Some(ducking));
+        assert_eq!(
+            mix_filter(&[music], None),
+            "[0:a]atrim=end=10.000,asetpts=PTS-STARTPTS,afade=t=out:st=7.000:d=3.000,\
+             afade=t=in:d=2.000,adelay=8000:all=1,volume=-20.00dB,\
+             volume=eval=frame:volume='pow(10,-12.00*(min(clip((t-1.700)/0.300,0,1),gte(4.000-t,0)))/20)'[a0];\
+             [a0]amix=inputs=1:duration=longest:dropout_transition=0:normalize=0[mixed]"
+        );
+    }
+
+    #[test]
+    #[ignore = "requires ffmpeg on PATH"]
+    fn test_mix_levels() {
+        // 1kHz・-20dBFS のモノラル音声（約 -23 LUFS）を、補正量を変えて別々の時刻に置く
+        let dir = env::temp_dir();
+        let tone = dir.join("auto-mv-mix-tone.wav");
+        let tone = tone.to_str().unwrap();
+        let samples: Vec<i16> = (0..48000)
+            .map(|i| {
+                let t = i as f64 / 48000.0;
+                wav::to_i16((0.1 * (2.0 * std::f64::consts::PI * 1000.0 * t).sin()) as f32)
+            })
+            .collect();
+        wav::write_wav(tone, 48000, 1, &samples).unwrap();
+        let source = loudness::measure_file(tone).unwrap().integrated.unwrap();
+
+        let gains = [6.0, -6.0, 0.0, -12.0];
+        let inputs: Vec<MixInput> = gains
+            .iter()
+            .enumerate()
+            .map(|(i, gain)| {
+                MixInput::new(
+                    tone,
+                    Track::Narration,
+                    Duration::from_secs(i as u64 * 2),
+                    *gain,
+                )
+            })
+            .collect();
+        let mixed = dir.join("auto-mv-mix-out.wav");
+        let mixed = mixed.to_str().unwrap();
+        mix_audio(&inputs, mixed, Some(-1.0)).unwrap();
+
+        // 後から重ねた音声も含め、どのクリップも指定した補正量だけ変わる
+        let (info, output) = wav::read_samples(mixed).unwrap();
+        for (i, gain) in gains.iter().enumerate() {
+            let clip = &output[i * 2 * 48000..(i * 2 + 1) * 48000];
+            let level = loudness::measure(clip, info.channels, info.sample_rate)
+                .integrated
+                .unwrap();
+            assert!(
+                (level - (source + gain)).abs() < 0.2,
+                "clip {}: {} LUFS, expected {}",
+                i,
+                level,
+                source + gain
+            );
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::{io, process::Command, time::Duration};
+
+use serde::Deserialize;
+
+use crate::audio::wav::{self, SampleFormat, WavError};
+
+/// ffprobe で調べたメディアファイルの情報
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaInfo {
+    /// コンテナ名（例: wav, mp3, ogg, flac, mov,mp4,m4a,3gp,3g2,mj2）
+    pub format_name: String,
+    /// コンテナの再生時間（不明なら None）
+    pub duration: Option<Duration>,
+    pub streams: Vec<StreamInfo>,
+}
+
+/// ストリームの情報
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamInfo {
+    pub index: u32,
+    pub kind: StreamKind,
+    /// コーデック名（例: pcm_s16le, mp3, opus, flac, aac）
+    pub codec: String,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub duration: Option<Duration>,
+}
+
+/// ストリームの種類（ffprobe の codec_type）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    Audio,
+    Video,
+    Subtitle,
+    Other,
+}
+
+/// 音声ファイルの情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioInfo {
+    pub codec: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration: Duration,
+}
+
+/// ffprobe の JSON 出力（数値の多くは文字列で返される）
+#[derive(Deserialize)]
+struct RawProbe {
+    #[serde(default)]
+    streams: Vec<RawStream>,
+    format: Option<RawFormat>,
+}
+
+#[derive(Deserialize)]
+struct RawStream {
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u16>,
+    duration: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawFormat {
+    format_name: Option<String>,
+    duration: Option<String>,
+}
+
+impl MediaInfo {
+    /// `ffprobe -show_format -show_streams -of json` の出力を解析する
+    pub fn parse(json: &str) -> Result<Self, io::Error> {
+        let raw: RawProbe = serde_json::from_str(json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let format = raw.format.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "ffprobe: format is missing")
+        })?;
+
+        let streams = raw
+            .streams
+            .into_iter()
+            .map(|s| StreamInfo {
+                index: s.index,
+                kind: match s.codec_type.as_deref() {
+                    Some("audio") => StreamKind::Audio,
+                    Some("video") => StreamKind::Video,
+                    Some("subtitle") => StreamKind::Subtitle,
+                    _ => StreamKind::Other,
+                },
+                codec: s.codec_name.unwrap_or_default(),
+                sample_rate: s
+                    .sample_rate
+                    .and_then(|r| r.parse().ok())
+                    .filter(|r| *r > 0),
+                channels: s.channels.filter(|c| *c > 0),
+                duration: s.duration.as_deref().and_then(parse_seconds),
+            })
+            .collect();
+
+        Ok(MediaInfo {
+            format_name: format.format_name.unwrap_or_default(),
+            duration: format.duration.as_deref().and_then(parse_seconds),
+            streams,
+        })
+    }
+
+    /// 最初の音声ストリーム
+    pub fn audio_stream(&self) -> Option<&StreamInfo> {
+        self.streams.iter().find(|s| s.kind == StreamKind::Audio)
+    }
+
+    /// 最初の音声ストリームの情報
+    /// 再生時間はストリームの値、なければコンテナの値を使う
+    pub fn audio(&self) -> Result<AudioInfo, io::Error> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let stream = self
+            .audio_stream()
+            .ok_or_else(|| invalid("no audio stream"))?;
+        Ok(AudioInfo {
+            codec: stream.codec.clone(),
+            sample_rate: stream
+                .sample_rate
+                .ok_or_else(|| invalid("unknown sample rate"))?,
+            channels: stream.channels.ok_or_else(|| invalid("unknown channels"))?,
+            duration: stream
+                .duration
+                .or(self.duration)
+                .ok_or_else(|| invalid("unknown duration"))?,
+        })
+    }
+}
+
+/// ffprobe の秒数（"1.234000" や "N/A"）を解析する
+fn parse_seconds(value: &str) -> Option<Duration> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|s| s.is_finite() && *s >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// ffprobe でメディアファイルを調べる
+pub fn probe(path: &str) -> Result<MediaInfo, io::Error> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_format",
+            "-show_streams",
+            "-of",
+            "json",
+            path,
+        ])
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("ffprobe: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "ffprobe failed for {}: {}",
+                path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    MediaInfo::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// 音声ファイルの情報を取得する
+/// WAV はヘッダを直接読み、それ以外（MP3・OGG・FLAC・M4A など）は ffprobe で調べる
+pub fn audio_info(path: &str) -> Result<AudioInfo, io::Error> {
+    match wav::WavInfo::from_file(path) {
+        Ok(info) => Ok(AudioInfo {
+            codec: wav_codec(info.format, info.bits_per_sample),
+            sample_rate: info.sample_rate,
+            channels: info.channels,
+            duration: info.duration(),
+        }),
+        Err(WavError::Io(e)) => Err(e),
+        Err(_) => probe(path)
+            .and_then(|media| media.audio())
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e))),
+    }
+}
+
+/// 音声ファイルの再生時間
+pub fn duration(path: &str) -> Result<Duration, io::Error> {
+    Ok(audio_info(path)?.duration)
+}
+
+/// WAV のフォーマットに対応する ffmpeg のコーデック名
+fn wav_codec(format: SampleFormat, bits_per_sample: u16) -> String {
+    match (format, bits_per_sample) {
+        (SampleFormat::Pcm, 8) => "pcm_u8".to_string(),
+        (SampleFormat::Pcm, bits) => format!("pcm_s{}le", bits),
+        (SampleFormat::Float, bits) => format!("pcm_f{}le", bits),
+        (SampleFormat::Alaw, _) => "pcm_alaw".to_string(),
+        (SampleFormat::Mulaw, _) => "pcm_mulaw".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MP3: &str = r#"{
+        "streams": [
+            {
+                "index": 0,
+                "codec_name": "mp3",
+                "codec_type": "audio",
+                "sample_rate": "24000",
+                "channels": 1,
+                "duration": "1.512000"
+            }
+        ],
+        "format": {
+            "filename": "voice.mp3",
+            "nb_streams": 1,
+            "format_name": "mp3",
+            "duration": "1.536000",
+            "size": "6144"
+        }
+    }"#;
+
+    const M4A: &str = r#"{
+        "streams": [
+            { "index": 0, "codec_name": "mjpeg", "codec_type": "video", "duration": "N/A" },
+            { "index": 1, "codec_name": "aac", "codec_type": "audio", "sample_rate": "44100", "channels": 2 }
+        ],
+        "format": { "format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": "3.250000" }
+    }"#;
+
+    #[test]
+    fn test_parse() {
+        let media = MediaInfo::parse(MP3).unwrap();
+        assert_eq!(media.format_name, "mp3");
+        assert_eq!(media.duration, Some(Duration::from_millis(1536)));
+        assert_eq!(
+            media.audio().unwrap(),
+            AudioInfo {
+                codec: "mp3".to_string(),
+                sample_rate: 24000,
+                channels: 1,
+                duration: Duration::from_millis(1512),
+            }
+        );
+
+        // 音声ストリームが先頭でなく、長さはコンテナの値を使う
+        let media = MediaInfo::parse(M4A).unwrap();
+        assert_eq!(media.streams[0].kind, StreamKind::Video);
+        assert_eq!(media.streams[0].duration, None);
+        let audio = media.audio().unwrap();
+        assert_eq!((audio.codec.as_str(), audio.channels), ("aac", 2));
+        assert_eq!(audio.duration, Duration::from_millis(3250));
+
+        assert!(MediaInfo::parse(r#"{"streams": []}"#).is_err());
+        let no_audio = r#"{"streams": [], "format": {"format_name": "png_pipe"}}"#;
+        assert!(MediaInfo::parse(no_audio).unwrap().audio().is_err());
+    }
+
+    #[test]
+    fn test_audio_info_wav() {
+        let path = std::env::temp_dir().join("auto-mv-probe.wav");
+        let path = path.to_str().unwrap();
+        wav::write_wav(path, 16000, 2, &[0; 16000 * 2]).unwrap();
+
+        // WAV は ffprobe を使わずに読む
+        assert_eq!(
+            audio_info(path).unwrap(),
+            AudioInfo {
+                codec: "pcm_s16le".to_string(),
+                sample_rate: 16000,
+                channels: 2,
+                duration: Duration::from_secs(1),
+            }
+        );
+        assert!(audio_info("./source/not-found.wav").is_err());
+    }
+}
@@ -0,0 +1,7 @@
+pub mod audio;
+
+pub mod ffmpeg;
+
+pub mod speech;
+
+pub mod models;
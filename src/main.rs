@@ -1,24 +1,44 @@
 use dotenv::dotenv;
-use log::{error, info};
-use std::{env, fs, io, ops::Add, time::Duration};
+use log::{error, info, warn};
+use std::{collections::HashMap, env, fs, io, ops::Add, path::Path, time::Duration};
 
-use crate::models::{
-    ass_subtitle::{create_ass_file, StyleType, Subtitle},
-    slide::SlideImage,
+use auto_mv::{
+    audio::{
+        ingest,
+        loudness::{self, Loudness},
+        mix::{MixInput, Track},
+        music::{self, Cue},
+        narration,
+    },
+    ffmpeg,
+    models::{
+        ass_subtitle::{create_ass_file, stack_rows, StyleType, Subtitle},
+        config::{AudioFormat, Config, FailurePolicy, LoudnessConfig, SpeechConfig},
+        manifest::{Clip, ClipManifest},
+        report::FailureReport,
+        script::{parse_script, ScriptError, ScriptLine, SfxCue},
+        slide::SlideImage,
+        timeline::{Anchor, Timeline},
+    },
+    speech::{
+        self,
+        lexicon::Lexicon,
+        normalize::Normalizer,
+        recording,
+        ssml::Markup,
+        timepoint::{self, Caption},
+        voice::{Voice, VoiceCatalog},
+        SpeechEngine, SynthesisRequest, Synthesized,
+    },
 };
 
-mod ffmpeg;
-
-mod speech;
-
-mod models;
-
 /// 半自動動画作成手順
 /// 0. 脚本べースで動画を作成する際の手順を自動化
 /// 1. ffmpeg::command::brank で空白の動画
 /// 2. 字幕命令書と画像挿入命令書を生成
 /// 3. speech::command::text-to-speech でセリフまたは行ごと音声を生成
-/// 4. ffmpeg::command::add_audioで動画に音声を追加
+/// 4. audio::narration::assemble で読み上げを1本のトラックにまとめ、BGM・効果音があれば
+///    ffmpeg::command::mix_audio で一度にミックスし、add_audio で動画に追加
 /// 5. 音声の長さと待機時間を[start, end]としタイムスタンプを生成
 /// 6. 字幕命令書及び画像挿入命令書にテキスト・画像・タイムスタンプを追加
 /// 7. ffmpeg::command::add_subs で字幕を動画に追加
@@ -30,48 +50,57 @@ async fn main() -> Result<(), io::Error> {
     env::set_var("RUST_LOG", "info");
     env_logger::init();
 
-    // 累積時間の初期化
-    let mut total_time = Duration::from_secs_f64(0.0);
+    // 行の開始時刻を決めるタイムライン
+    let mut timeline = Timeline::default();
     // 字幕付与命令書の生成
     let mut asss = Vec::new();
     // 画像付与命令書の生成
-    let mut slides: Vec<SlideImage> = Vec::new();
+    let slides: Vec<SlideImage> = Vec::new();
 
+    let config_file = "./source/config.json";
     let scripts_file = "./source/scripts.txt";
-    let voice_output = "./source/voice.wav";
+    let clip_dir = "./source/clips";
+    let clip_manifest = "./source/clips.json";
+    let narration_audio = "./source/narration.wav";
+    let mixed_audio = "./source/mixed.wav";
     let subtitle_output = "./source/subtitle.ass";
+    let failure_report = "./source/failed-lines.txt";
+    let take_alignment = "./source/take-alignment.json";
 
-    // 環境変数から待機時間を取得
-    let waiting_sec_after_speaking = {
-        let key = env::var("WAITING_SEC_AFTER_SPEAKING")
-            .unwrap_or_else(|_| "1".to_string())
-            .parse::<u64>()
-            .unwrap();
-        Duration::from_secs(key)
-    };
+    // 環境変数から待機時間を取得（小数で指定可能。例: 0.4）
+    let waiting_sec_after_speaking = waiting_seconds(
+        &env::var("WAITING_SEC_AFTER_SPEAKING").unwrap_or_else(|_| "1".to_string()),
+    )?;
+
+    // 設定に従い音声合成エンジンを選択
+    let config = Config::load(config_file)?;
+    let engine = speech::engine_from_config(&config.speech)?;
+    info!("speech engine: {}", engine.name());
+    let catalog = speech::catalog_from_config(&config.speech, engine.as_ref()).await?;
+    let voices = catalog.by_language(&config.speech.language)?;
+    let lexicon = speech::lexicon_from_config(&config.speech)?;
+    let normalizer = Normalizer::new(&config.speech.normalize, &config.speech.language);
 
     // 脚本を定義
     // セリフ、または行ごとに配列で定義
     // 1配列ごとに音声を生成する
     // why: 音声の長さに字幕生成・動画が依存しているため
+    // 行頭の `{話者 rate=.. pitch=.. gain=..}` で話者と読み上げ方を指定できる
     let scripts = if fs::exists(scripts_file).unwrap() {
-        let script_content = fs::read_to_string(scripts_file).unwrap();
-        // file to string
-        script_content
-            .lines()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>()
+        fs::read_to_string(scripts_file).unwrap()
     } else {
-        vec![
-            String::from("脚本を元に音声を生成"),
-            String::from("脚本のセリフまたは行ごとに"),
-            String::from("音声を生成"),
-            String::from("字幕命令書に追加"),
-            String::from("画像付与命令書に追加"),
-            String::from("累積時間を更新"),
-            String::from("字幕を動画に追加"),
+        [
+            "脚本を元に音声を生成",
+            "脚本のセリフまたは行ごとに",
+            "音声を生成",
+            "字幕命令書に追加",
+            "画像付与命令書に追加",
+            "累積時間を更新",
+            "字幕を動画に追加",
         ]
+        .join("\n")
     };
+    let scripts = parse_script(&scripts);
 
     // ffmpeg::command::brank で空白の動画を生成
     let (output, resolution, duration, frame_rate) = ("./source/0-brank.mp4", "1280x720", 60, 30);
@@ -85,94 +114,277 @@ async fn main() -> Result<(), io::Error> {
     // 累積時間を更新
     // 字幕を動画に追加
     let update_output = "./source/1-audio-overlay.mp4";
+    // 行ごとの読み上げ（ループの後で1本のトラックにまとめる）
+    let mut narration_inputs = Vec::new();
+    // 動画に重ねる BGM・効果音（ループの後でまとめてミックスする）
+    let mut mix_inputs = Vec::new();
+    // 読み上げの区間（BGM を下げる）
+    let mut speech_intervals = Vec::new();
+    // 行ごとの音声は別々のファイルに残し、一覧に記録する
+    fs::create_dir_all(clip_dir)?;
+    let mut manifest = ClipManifest::default();
+    // 通しの録音があれば、行ごとの録音がない読み上げの行に分け、区切りを書き出す
+    let mut take_clips = HashMap::new();
+    // 通しの録音で続けて読んだ行の間隔
+    let mut take_gaps = HashMap::new();
+    if let Some(take) = &config.speech.recordings.take {
+        let recordings = &config.speech.recordings;
+        let lines: Vec<&ScriptLine> = scripts
+            .iter()
+            .filter_map(|s| s.as_ref().ok())
+            .filter(|s| s.spoken.is_some() && matches!(recording::find(recordings, s), Ok(None)))
+            .collect();
+        let take = Path::new(&recordings.dir)
+            .join(take)
+            .to_string_lossy()
+            .to_string();
+        let segments = recording::split_take(
+            &take,
+            &lines,
+            &recordings.split,
+            &config.audio.format,
+            clip_dir,
+        )?;
+        fs::write(take_alignment, serde_json::to_string_pretty(&segments)?)?;
+        info!("{}: split into {} lines", take, segments.len());
+        take_gaps = recording::take_gaps(&segments, &recordings.split, &config.speech.trim);
+        take_clips.extend(segments.into_iter().map(|s| (s.line, s.path)));
+    }
+    let context = LineContext {
+        config: &config.speech,
+        format: &config.audio.format,
+        loudness: &config.audio.loudness,
+        engine: engine.as_ref(),
+        catalog: &catalog,
+        voices: &voices,
+        lexicon: &lexicon,
+        normalizer: normalizer.as_ref(),
+        clip_dir,
+        take_clips: &take_clips,
+    };
+    // 音声を生成できなかった行
+    let mut report = FailureReport::default();
     for (i, script) in scripts.iter().enumerate() {
-        // ffmpeg::command::add_audio でテキストから音声を生成
-        let code = speech::voice::Code::new();
-
-        // 有料ボイスを選択
-        let voice = speech::voice::Name::from(code, ((i + 4) % 10) as u32).unwrap();
-        info!("voice: {}", voice);
-        let duration = match speech::local::command(
-            script,
-            voice_output,
-            Some(code.to_string()),
-            Some(voice.to_string()),
-        ) {
-            Ok(duration) => {
-                // 音声の長さを取得
-                info!("voice time: {:?}", duration);
-                asss.push(Subtitle {
-                    id: i as i32,
-                    start_time: total_time,
-                    end_time: total_time + duration,
-                    text: script.to_string(),
-                    // [TODO] 適宜、スタイルを追加
-                    style_name: Some(StyleType::from(i as u32)),
-                });
-
-                // [TODO] 適宜、画像付与命令書を追加
-                // 画像出力に伴うロジックが必要
-                // if i == 0 {
-                //     ffmpeg::command::add_image_overlay(
-                //         output,
-                //         "./source/layer_image.jpg",
-                //         0,
-                //         20,
-                //         output,
-                //     )?;
-                //     // slides.push(SlideImage {
-                //     //     id: i as i32,
-                //     //     start_time: Duration::from_secs(0),
-                //     //     end_time: Duration::from_secs(20),
-                //     //     image_path: "./source/layer_image.jpg".to_string(),
-                //     //     display_options: None,
-                //     // });
-                // }
-
-                duration
-            }
+        let timing = script
+            .as_ref()
+            .map(|s| s.timing.clone())
+            .unwrap_or_default();
+        let policy = config.speech.on_failure;
+        // 行ごとに字幕の表記と音声を用意する
+        let result = match script {
+            Ok(script) => synthesize_line(&context, i, script).await,
+            Err(e) => Err(io::Error::new(e.error.kind(), e.to_string())),
+        };
+        let SynthesizedLine {
+            display: display_text,
+            duration,
+            audio,
+            captions,
+        } = match result {
+            Ok(line) => line,
             Err(e) => {
-                error!("Error: {}", e);
-                continue;
+                let (number, text) = line_text(script);
+                // 設定に従い、中止・無音と警告字幕の挿入・行の省略のいずれかを行う
+                report_failure(&mut report, policy, failure_report, number, &text, e)?;
+                if policy == FailurePolicy::Skip {
+                    // 飛ばした行も長さ 0 で配置し、後の行から id で参照できるようにする
+                    if let Err(e) = timeline.place(&timing, 0.0, Duration::ZERO) {
+                        warn!("line {}: {}", number, e);
+                        timeline.place_fallback(&timing, 0.0, Duration::ZERO)?;
+                    }
+                    continue;
+                }
+                SynthesizedLine {
+                    display: Some(format!("[音声生成失敗] {}", text)),
+                    duration: Duration::from_secs_f64(config.speech.placeholder_seconds),
+                    audio: None,
+                    captions: Vec::new(),
+                }
             }
         };
 
-        // ffmpeg::command::add_audio で音声を動画に追加
-        // 動画ファイルを更新・追記していく
-        let (input, audio, update_output) = if i == 0 {
-            // ブランク動画に対して、音声を追加し、音声を重ねる動画を更新
-            (output, voice_output, update_output)
-        } else {
-            (update_output, voice_output, update_output)
+        // 脚本の指定（間隔・同時・時刻）に従って開始時刻を決める
+        // 指定が誤っていれば報告し、中止しない設定なら指定を無視して前の行に続けて置く
+        let gap = waiting_sec_after_speaking.as_secs_f64();
+        let gap = script.as_ref().map_or(gap, |s| s.default_gap(gap));
+        // 通しの録音で直前の行に続けて読んだ行は、録音上の間隔で並べる
+        let previous = i.checked_sub(1).map(|j| line_text(&scripts[j]).0);
+        let gap = match script.as_ref().ok().and_then(|s| take_gaps.get(&s.number)) {
+            Some((line, take_gap)) if previous == Some(*line) => *take_gap,
+            _ => gap,
+        };
+        let start = match timeline.place(&timing, gap, duration) {
+            Ok(start) => start,
+            Err(e) => {
+                let (number, text) = line_text(script);
+                report_failure(&mut report, policy, failure_report, number, &text, e)?;
+                timeline.place_fallback(&timing, gap, duration)?
+            }
         };
-        let volume_waight = i + 1;
-        ffmpeg::command::add_audio(
-            input,
-            audio,
-            update_output,
-            total_time,
-            volume_waight as i32,
-        )?;
 
-        // 累積時間を更新
-        total_time += duration + waiting_sec_after_speaking;
+        if let Some(audio) = &audio {
+            // 行ごとの音量の補正に読み上げ全体の補正を重ねる
+            narration_inputs.push(MixInput::new(
+                &audio.path,
+                Track::Narration,
+                start,
+                audio.gain_db + config.audio.mix.narration_gain_db,
+            ));
+            speech_intervals.push((start, start + duration));
+            manifest.push(Clip {
+                line: script.as_ref().map(|s| s.number).unwrap_or_default(),
+                track: Track::Narration,
+                path: audio.path.clone(),
+                start: start.as_secs_f64(),
+                duration: duration.as_secs_f64(),
+                loudness: audio.loudness.integrated,
+                true_peak: audio.loudness.true_peak,
+                gain_db: audio.gain_db,
+            });
+        }
+
+        // 効果音は行の開始・終了または指定の時刻に、読み上げと同じミックスで重ねる
+        // 用意できなかった効果音は行と同じく報告し、中止しない設定なら鳴らさずに続ける
+        for cue in script.iter().flat_map(|s| &s.sfx) {
+            let (path, sfx_duration, time) = match prepare_sfx(&config, cue, start, duration) {
+                Ok(sfx) => sfx,
+                Err(e) => {
+                    let (number, text) = line_text(script);
+                    let policy = config.speech.on_failure;
+                    report_failure(&mut report, policy, failure_report, number, &text, e)?;
+                    continue;
+                }
+            };
+            mix_inputs.push(MixInput::new(
+                &path,
+                Track::Effect,
+                time,
+                cue.gain_db + config.audio.mix.effects_gain_db,
+            ));
+            manifest.push(Clip {
+                line: script.as_ref().map(|s| s.number).unwrap_or_default(),
+                track: Track::Effect,
+                path,
+                start: time.as_secs_f64(),
+                duration: sfx_duration.as_secs_f64(),
+                loudness: None,
+                true_peak: None,
+                gain_db: cue.gain_db,
+            });
+        }
+
+        if !captions.is_empty() {
+            // 読み上げの時刻に合わせて区切った字幕
+            for caption in &captions {
+                asss.push(Subtitle {
+                    id: i as i32,
+                    start_time: start + caption.start,
+                    end_time: start + caption.end,
+                    text: caption.text.clone(),
+                    style_name: Some(StyleType::from(i as u32)),
+                    row: 0,
+                });
+            }
+        } else if let Some(text) = &display_text {
+            asss.push(Subtitle {
+                id: i as i32,
+                start_time: start,
+                end_time: start + duration,
+                text: text.clone(),
+                // [TODO] 適宜、スタイルを追加
+                style_name: Some(StyleType::from(i as u32)),
+                row: 0,
+            });
+        }
+
+        // [TODO] 適宜、画像付与命令書を追加
+        // 画像出力に伴うロジックが必要
+        // if i == 0 {
+        //     ffmpeg::command::add_image_overlay(
+        //         output,
+        //         "./source/layer_image.jpg",
+        //         0,
+        //         20,
+        //         output,
+        //     )?;
+        //     // slides.push(SlideImage {
+        //     //     id: i as i32,
+        //     //     start_time: Duration::from_secs(0),
+        //     //     end_time: Duration::from_secs(20),
+        //     //     image_path: "./source/layer_image.jpg".to_string(),
+        //     //     display_options: None,
+        //     // });
+        // }
 
         info!(
-            "[{}: {}] 音声ファイルを動画に追加しました。",
+            "[{}: {:?}: {}] 行を追加しました。",
             i,
-            script // format_duration_as_ass_time(total_time)
+            start,
+            display_text.as_deref().unwrap_or_default()
         );
     }
 
-    // 音声を追加した動画を更新
-    let output = update_output;
+    // すべての行が終わり、待機時間を空けた時刻
+    let total_time = timeline.end().unwrap_or_default() + waiting_sec_after_speaking;
+    // 完成した動画の長さ
+    let video_end = total_time.add(Duration::new(2, 0));
+
+    // BGM を動画の終わりまで敷き、読み上げ中は下げる
+    let mut cues = Vec::new();
+    for cue in config.audio.music.cues() {
+        let path = ingest::ingest(&cue.file, &config.audio.format)?;
+        cues.push(Cue {
+            start: timeline.resolve(&Anchor::parse(&cue.at)?)?,
+            length: ffmpeg::probe::duration(&path)?,
+            path,
+        });
+    }
+    mix_inputs.extend(music::music_inputs(
+        &cues,
+        video_end,
+        &speech_intervals,
+        &config.audio.music,
+    ));
+
+    manifest.write(clip_manifest)?;
+
+    // 失敗した行をまとめて報告する
+    if !report.is_empty() {
+        error!("{}", report.summary());
+        report.write(failure_report)?;
+    }
+
+    // 読み上げはサンプル単位で並べて1本の WAV にし、ミックスでは1つの音声として扱う
+    if !narration_inputs.is_empty() {
+        narration::assemble(&narration_inputs, &config.audio.format, narration_audio)?;
+        mix_inputs.insert(
+            0,
+            MixInput::new(narration_audio, Track::Narration, Duration::ZERO, 0.0),
+        );
+    }
+
+    // すべての音声を1回でミックスし、ブランク動画に付ける
+    // 読み上げだけでリミッターもかけなければ、ミックスせずにそのまま付ける
+    let limiter = config.audio.mix.limiter_ceiling_db;
+    let output = match mix_inputs.as_slice() {
+        [] => output,
+        [only] if only.track == Track::Narration && limiter.is_none() => {
+            ffmpeg::command::add_audio(output, narration_audio, update_output)?;
+            update_output
+        }
+        _ => {
+            ffmpeg::command::mix_audio(&mix_inputs, mixed_audio, limiter)?;
+            ffmpeg::command::add_audio(output, mixed_audio, update_output)?;
+            update_output
+        }
+    };
 
     // 音声が追加された動画が完成
     // 画像付与命令書に従い画像を追加
 
     // [TODO] 画像付与命令書を元に動画に画像を追加
     if !slides.is_empty() {
-        for (i, script) in slides.iter().enumerate() {
+        for _slide in slides.iter() {
             // ffmpeg::command::add_image_overlay で画像を動画にオーバーレイ
             let (start, end) = (0, 5);
             let (input, layer_image, output) = (
@@ -189,6 +401,8 @@ async fn main() -> Result<(), io::Error> {
 
     // ass: 字幕命令書を元にass形式の字幕を生成
     if !asss.is_empty() {
+        // 同時に表示する字幕は段をずらす
+        stack_rows(&mut asss);
         let str_for_ass_file = create_ass_file(&asss);
 
         // ass形式の字幕ファイルを生成
@@ -202,12 +416,271 @@ async fn main() -> Result<(), io::Error> {
     ffmpeg::command::add_subs(input, subs_file, output)?;
 
     // リザルトファイルを用意する
-    let (input, cut_to, output) = (
-        output,
-        total_time.add(Duration::new(2, 0)),
-        "./source/result.mp4",
-    );
+    let (input, cut_to, output) = (output, video_end, "./source/result.mp4");
     ffmpeg::command::cut(input, output, cut_to).unwrap();
 
     Ok(())
 }
+
+/// 行ごとの音声合成に使う設定やエンジン
+struct LineContext<'a> {
+    config: &'a SpeechConfig,
+    /// 行ごとの音声をそろえる形式
+    format: &'a AudioFormat,
+    loudness: &'a LoudnessConfig,
+    engine: &'a dyn SpeechEngine,
+    catalog: &'a VoiceCatalog,
+    voices: &'a [&'a Voice],
+    lexicon: &'a Lexicon,
+    normalizer: Option<&'a Normalizer>,
+    /// 行ごとの音声の保存先
+    clip_dir: &'a str,
+    /// 通しの録音から切り出した行ごとの音声（キーは行番号）
+    take_clips: &'a HashMap<usize, String>,
+}
+
+/// 脚本の1行から用意した字幕と音声
+struct SynthesizedLine {
+    /// 字幕の表記
+    display: Option<String>,
+    /// 行の長さ
+    duration: Duration,
+    /// 生成した音声（読み上げがなければ None）
+    audio: Option<LineAudio>,
+    /// 読み上げの時刻で区切った字幕（区切らない場合は空）
+    captions: Vec<Caption>,
+}
+
+/// 行の音声と、測った音量
+struct LineAudio {
+    path: String,
+    loudness: Loudness,
+    /// 目標の音量に揃えるための補正 (dB)
+    gain_db: f64,
+}
+
+/// 報告に使う行番号と行のテキスト
+fn line_text(script: &Result<ScriptLine, ScriptError>) -> (usize, String) {
+    match script {
+        Ok(script) => (
+            script.number,
+            script
+                .display
+                .clone()
+                .or(script.spoken.clone())
+                .unwrap_or_default(),
+        ),
+        Err(e) => (e.number, e.text.clone()),
+    }
+}
+
+/// 失敗を記録する。`on_failure: fail` なら報告を書き出してエラーを返す
+fn report_failure(
+    report: &mut FailureReport,
+    policy: FailurePolicy,
+    path: &str,
+    number: usize,
+    text: &str,
+    e: io::Error,
+) -> Result<(), io::Error> {
+    error!("Error: line {}: {}", number, e);
+    report.push(number, text, &e);
+    if policy == FailurePolicy::Fail {
+        report.write(path)?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// 効果音をプロジェクトの形式で取り込み、パス・長さ・鳴らす時刻を返す
+fn prepare_sfx(
+    config: &Config,
+    cue: &SfxCue,
+    start: Duration,
+    duration: Duration,
+) -> Result<(String, Duration, Duration), io::Error> {
+    let source = Path::new(&config.audio.sfx_dir)
+        .join(&cue.file)
+        .to_string_lossy()
+        .to_string();
+    let sfx_error = |e: io::Error| io::Error::new(e.kind(), format!("sfx {}: {}", source, e));
+    let path = ingest::ingest(&source, &config.audio.format).map_err(sfx_error)?;
+    let sfx_duration = ffmpeg::probe::duration(&path).map_err(sfx_error)?;
+    Ok((path, sfx_duration, cue.time(start, duration)?))
+}
+
+/// 行の後の待機時間（秒）を解析する。数値でないものや負の値はエラーにする
+fn waiting_seconds(value: &str) -> Result<Duration, io::Error> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "WAITING_SEC_AFTER_SPEAKING must be a non-negative number of seconds, got `{}`",
+                    value
+                ),
+            )
+        })
+}
+
+/// 脚本の1行から字幕の表記と音声を用意する
+async fn synthesize_line(
+    context: &LineContext<'_>,
+    i: usize,
+    script: &ScriptLine,
+) -> Result<SynthesizedLine, io::Error> {
+    // 話者の設定があれば、その音声と読み上げ方を使う
+    let speaker = match &script.speaker {
+        Some(name) => Some(context.config.speakers.get(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("unknown speaker `{}`", name),
+            )
+        })?),
+        None => None,
+    };
+    let prosody = speaker
+        .map(|s| s.prosody)
+        .unwrap_or_default()
+        .merge(&script.prosody);
+
+    // 字幕の表記を用意する（タグを除き、元の表記を残す）
+    let display_text = script
+        .display
+        .as_deref()
+        .map(Markup::parse)
+        .transpose()?
+        .map(|m| m.display_text());
+
+    // 録音があれば合成の代わりに使い、合成した音声と同じように仕上げる
+    let output = format!("{}/line-{}.wav", context.clip_dir, script.number);
+    let recorded = match context.take_clips.get(&script.number) {
+        Some(path) => Some(path.clone()),
+        None => recording::find(&context.config.recordings, script)?,
+    };
+    if let Some(path) = recorded {
+        info!("line {}: recording: {}", script.number, path);
+        let recorded = recording::import(&path, &output, context.format, &context.config.trim)?;
+        return finish_line(context, script, display_text, recorded, Vec::new());
+    }
+
+    // 読み上げがなければ字幕の表示時間だけ進める
+    let Some(spoken) = &script.spoken else {
+        return Ok(SynthesizedLine {
+            display: display_text,
+            duration: script.display_duration(),
+            audio: None,
+            captions: Vec::new(),
+        });
+    };
+    // 音声は合成する行でだけ選ぶ（録音を使う行はカタログにない音声でもよい）
+    // 話者に音声の指定がなければ音声カタログから行ごとに選ぶ
+    let voice = match speaker.and_then(|s| s.voice.as_deref()) {
+        Some(id) => context.catalog.get(id)?,
+        None => context.voices[(i + 4) % context.voices.len()],
+    };
+    info!("voice: {}", voice.id);
+
+    // 脚本のマークアップを解析し、SSML と読み上げ用のテキストに分ける
+    let mut markup = Markup::parse(spoken)?;
+    // 発音辞書で読みを差し替える（字幕は元の表記のまま）
+    for substitution in markup.substitute(context.lexicon) {
+        info!(
+            "line {}: lexicon: {} -> {}",
+            script.number, substitution.surface, substitution.reading
+        );
+    }
+    // 数値・単位・日付などを読みやすい形に展開する
+    if let Some(normalizer) = context.normalizer {
+        for substitution in markup.substitute(normalizer) {
+            info!(
+                "line {}: normalize: {} -> {}",
+                script.number, substitution.surface, substitution.reading
+            );
+        }
+    }
+    // 字幕と読み上げが同じ表記なら、句読点の区切りの時刻を受け取って字幕を区切る
+    let split = context.config.split_subtitles && script.display == script.spoken;
+    let clauses = if split { markup.mark_clauses() } else { 0 };
+    let ssml = (context.engine.supports_ssml() && markup.has_markup()).then(|| markup.to_ssml());
+    let request = SynthesisRequest::new(&markup.spoken_text(), &output, &voice.language, &voice.id)
+        .with_ssml(ssml)
+        .with_prosody(prosody)
+        .with_effects(speaker.map(|s| s.effects.clone()).unwrap_or_default())
+        .with_clauses(clauses);
+
+    // 音声の長さを取得
+    let synthesized = context.engine.synthesize(&request).await?;
+
+    let captions = match &display_text {
+        Some(text) if split => timepoint::split_text(
+            text,
+            &markup.marks(),
+            &synthesized.timepoints,
+            synthesized.duration,
+        ),
+        _ => Vec::new(),
+    };
+    finish_line(context, script, display_text, synthesized, captions)
+}
+
+/// 合成・録音した行の音声をプロジェクトの形式にそろえ、音量を測る
+fn finish_line(
+    context: &LineContext<'_>,
+    script: &ScriptLine,
+    display_text: Option<String>,
+    synthesized: Synthesized,
+    captions: Vec<Caption>,
+) -> Result<SynthesizedLine, io::Error> {
+    let duration = synthesized.duration;
+    info!("voice time: {:?}", duration);
+
+    // プロジェクトの形式に変換してから（チャンネル数で音量が変わるため）音量を一度だけ測り、
+    // 行ごとの補正量を決める
+    let path = ingest::ingest(&synthesized.path, context.format)?;
+    let (loudness, gain_db) = if context.loudness.enabled {
+        let loudness = loudness::measure_file(&path).unwrap_or_else(|e| {
+            warn!("line {}: loudness not measured: {}", script.number, e);
+            Loudness::default()
+        });
+        (loudness, loudness.gain_to(context.loudness))
+    } else {
+        (Loudness::default(), 0.0)
+    };
+    info!(
+        "loudness: {:?} LUFS, {:?} dBTP, gain {:.2} dB",
+        loudness.integrated, loudness.true_peak, gain_db
+    );
+
+    Ok(SynthesizedLine {
+        display: display_text,
+        duration,
+        audio: Some(LineAudio {
+            path,
+            loudness,
+            gain_db,
+        }),
+        captions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_waiting_seconds() {
+        assert_eq!(waiting_seconds("1").unwrap(), Duration::from_secs(1));
+        assert_eq!(waiting_seconds("0.4").unwrap(), Duration::from_millis(400));
+        assert_eq!(waiting_seconds("0").unwrap(), Duration::ZERO);
+        for value in ["", "abc", "-0.5", "inf", "NaN"] {
+            let e = waiting_seconds(value).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput, "{}", value);
+        }
+    }
+}
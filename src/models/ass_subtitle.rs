@@ -8,6 +8,40 @@ pub struct Subtitle {
     pub end_time: Duration,
     pub text: String,
     pub style_name: Option<StyleType>,
+    /// 同時に表示する字幕と重ならないよう、下から何段目に表示するか（0 ならスタイルの位置）
+    pub row: u32,
+}
+
+/// 字幕1段分の高さ（フォントサイズ 48 の行間を含む）
+const ROW_HEIGHT: u32 = 60;
+
+impl Subtitle {
+    /// 下端からの余白。0 ならスタイルの MarginV を使う
+    fn margin_v(&self) -> u32 {
+        match self.row {
+            0 => 0,
+            row => Style::from(0).margin_v + row * ROW_HEIGHT,
+        }
+    }
+}
+
+/// 表示時間が重なる字幕を別の段に振り分ける
+/// 開始の早い順に、重なる字幕のない最も下の段を使う
+pub fn stack_rows(subtitles: &mut [Subtitle]) {
+    let mut order: Vec<usize> = (0..subtitles.len()).collect();
+    order.sort_by_key(|i| (subtitles[*i].start_time, *i));
+
+    let mut placed: Vec<usize> = Vec::new();
+    for i in order {
+        let (start, end) = (subtitles[i].start_time, subtitles[i].end_time);
+        let used: Vec<u32> = placed
+            .iter()
+            .filter(|j| subtitles[**j].start_time < end && start < subtitles[**j].end_time)
+            .map(|j| subtitles[*j].row)
+            .collect();
+        subtitles[i].row = (0..).find(|row| !used.contains(row)).unwrap();
+        placed.push(i);
+    }
 }
 
 /// `Duration` を SRT形式の時間フォーマットに変換する補助関数
@@ -28,7 +62,7 @@ impl fmt::Display for Subtitle {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Dialogue:0,{},{},{},,0,0,0,,{}",
+            "Dialogue:0,{},{},{},,0,0,{},,{}",
             format_duration_as_time(self.start_time),
             format_duration_as_time(self.end_time),
             if let Some(t) = self.style_name {
@@ -36,6 +70,7 @@ impl fmt::Display for Subtitle {
             } else {
                 StyleType::Default.to_string()
             }, // スタイル名を使用
+            self.margin_v(),
             escape_ass_text(&self.text)
         )
     }
@@ -81,8 +116,8 @@ pub struct Style {
     pub encoding: u32,
 }
 
-impl Style {
-    pub fn default() -> Self {
+impl Default for Style {
+    fn default() -> Self {
         Style {
             name: StyleType::Default,
             font_family: "Arial".to_string(),
@@ -108,7 +143,9 @@ impl Style {
             encoding: 1,
         }
     }
+}
 
+impl Style {
     pub fn from(n: u32) -> Self {
         Style {
             name: StyleType::from(n),
@@ -200,6 +237,12 @@ pub enum StyleType {
     Gray,
 }
 
+impl Default for StyleType {
+    fn default() -> Self {
+        StyleType::new()
+    }
+}
+
 impl StyleType {
     pub fn new() -> Self {
         StyleType::Default
@@ -336,4 +379,33 @@ mod tests {
         let modified_text = escape_ass_text(text);
         println!("{}", modified_text);
     }
+
+    fn subtitle(start: u64, end: u64) -> Subtitle {
+        Subtitle {
+            id: 0,
+            start_time: Duration::from_secs(start),
+            end_time: Duration::from_secs(end),
+            text: "a".to_string(),
+            style_name: None,
+            row: 0,
+        }
+    }
+
+    #[test]
+    fn test_stack_rows() {
+        let mut subtitles = vec![
+            subtitle(0, 4),
+            subtitle(1, 2),
+            subtitle(2, 3),
+            subtitle(1, 3),
+            subtitle(4, 5),
+        ];
+        stack_rows(&mut subtitles);
+        let rows: Vec<u32> = subtitles.iter().map(|s| s.row).collect();
+        // 終了と開始が同じ時刻なら重ならない
+        assert_eq!(rows, vec![0, 1, 1, 2, 0]);
+
+        assert!(subtitles[0].to_string().contains(",,0,0,0,,"));
+        assert!(subtitles[3].to_string().contains(",,0,0,140,,"));
+    }
 }
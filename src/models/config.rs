@@ -0,0 +1,988 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs, io,
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::speech::{
+    effects::Effects,
+    prosody::{validate_pitch, Prosody},
+};
+
+/// プロジェクト設定
+/// ./source/config.json から読み込む。ファイルがなければ既定値を使う
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub speech: SpeechConfig,
+    pub audio: AudioConfig,
+}
+
+impl Config {
+    /// 設定ファイルを読み込む
+    /// 環境変数 `SPEECH_ENGINE` が指定されていれば、エンジン種別を上書きする
+    pub fn load(path: &str) -> Result<Self, io::Error> {
+        let mut config = if fs::exists(path)? {
+            let content = fs::read_to_string(path)?;
+            serde_json::from_str::<Config>(&content).map_err(io::Error::other)?
+        } else {
+            Config::default()
+        };
+
+        if let Ok(engine) = env::var("SPEECH_ENGINE") {
+            config.speech.engine = serde_json::from_value(serde_json::Value::String(engine))
+                .map_err(io::Error::other)?;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 処理の途中で止まる・終わらない値がないかを確かめる
+    pub fn validate(&self) -> Result<(), io::Error> {
+        let invalid = |message: String| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("config: {}", message))
+        };
+        let placeholder = self.speech.placeholder_seconds;
+        if !(placeholder.is_finite() && placeholder >= 0.0) {
+            return Err(invalid(format!(
+                "speech.placeholder_seconds must not be negative: {}",
+                placeholder
+            )));
+        }
+        let format = &self.audio.format;
+        if format.sample_rate == 0 || format.channels == 0 {
+            return Err(invalid(format!(
+                "audio.format.sample_rate and channels must be positive: {} Hz, {} ch",
+                format.sample_rate, format.channels
+            )));
+        }
+        for (name, speaker) in &self.speech.speakers {
+            speaker
+                .prosody
+                .validate()
+                .map_err(|e| invalid(format!("speaker `{}`: {}", name, e)))?;
+            if let Some(pitch) = speaker.effects.pitch_shift {
+                validate_pitch(pitch)
+                    .map_err(|e| invalid(format!("speaker `{}` effects: {}", name, e)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 音声の仕上げの設定
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// ミックスに使うすべての音声をそろえる形式
+    pub format: AudioFormat,
+    /// 行ごとの音量の揃え方
+    pub loudness: LoudnessConfig,
+    /// ミックスの設定
+    pub mix: MixConfig,
+    /// BGM
+    pub music: MusicConfig,
+    /// 脚本の `[sfx ...]` のファイルを探すディレクトリ
+    pub sfx_dir: String,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig {
+            format: AudioFormat::default(),
+            loudness: LoudnessConfig::default(),
+            mix: MixConfig::default(),
+            music: MusicConfig::default(),
+            sfx_dir: "./source/sfx".to_string(),
+        }
+    }
+}
+
+/// プロジェクトの音声形式
+/// 読み上げ・BGM・効果音は取り込むときにこの形式の WAV に変換し、`cache_dir` に残す
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AudioFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: SampleType,
+    /// 変換した音声の保存先
+    pub cache_dir: String,
+}
+
+impl Default for AudioFormat {
+    fn default() -> Self {
+        AudioFormat {
+            sample_rate: 48000,
+            channels: 2,
+            sample_format: SampleType::S16,
+            cache_dir: "./source/converted".to_string(),
+        }
+    }
+}
+
+impl AudioFormat {
+    /// キャッシュキー用の文字列表現
+    pub fn key(&self) -> String {
+        format!(
+            "{}Hz/{}ch/{:?}",
+            self.sample_rate, self.channels, self.sample_format
+        )
+    }
+}
+
+/// サンプルの形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SampleType {
+    /// 16bit 整数
+    #[default]
+    S16,
+    /// 32bit 浮動小数点
+    F32,
+}
+
+impl SampleType {
+    /// ffmpeg のコーデック名
+    pub fn codec(&self) -> &'static str {
+        match self {
+            SampleType::S16 => "pcm_s16le",
+            SampleType::F32 => "pcm_f32le",
+        }
+    }
+}
+
+/// BGM の設定
+/// 1曲（`file`）またはプレイリスト（`playlist`）を、動画の終わりまでループして敷く
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MusicConfig {
+    /// 動画全体に流す曲
+    pub file: Option<String>,
+    /// 場面ごとの曲。`file` より優先する
+    pub playlist: Vec<MusicCue>,
+    /// BGM の音量 (dB)
+    pub gain_db: f64,
+    /// 曲の長さが足りなければ繰り返す
+    #[serde(rename = "loop")]
+    pub looped: bool,
+    /// 繰り返しと曲の切り替えで重ねる長さ（秒）。0 なら続けてつなぐ
+    pub crossfade_seconds: f64,
+    /// 動画の先頭のフェードイン（秒）
+    pub fade_in_seconds: f64,
+    /// 動画の終わりのフェードアウト（秒）
+    pub fade_out_seconds: f64,
+    /// 読み上げ中に BGM を下げる
+    pub ducking: DuckingConfig,
+}
+
+impl Default for MusicConfig {
+    fn default() -> Self {
+        MusicConfig {
+            file: None,
+            playlist: Vec::new(),
+            gain_db: -20.0,
+            looped: true,
+            crossfade_seconds: 2.0,
+            fade_in_seconds: 1.0,
+            fade_out_seconds: 3.0,
+            ducking: DuckingConfig::default(),
+        }
+    }
+}
+
+impl MusicConfig {
+    /// 再生する曲の一覧（`file` は先頭から流す1曲のプレイリストとみなす）
+    pub fn cues(&self) -> Vec<MusicCue> {
+        match (&self.file, self.playlist.is_empty()) {
+            (Some(file), true) => vec![MusicCue {
+                file: file.clone(),
+                at: "0".to_string(),
+            }],
+            _ => self.playlist.clone(),
+        }
+    }
+}
+
+/// プレイリストの1曲
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MusicCue {
+    pub file: String,
+    /// 曲を始める時刻。脚本の `at=` と同じ形式（`0`、`intro.start`、`q1.end+2` など）
+    pub at: String,
+}
+
+/// 読み上げ中に BGM を下げる設定
+/// 行の開始前に `attack_ms` かけて `depth_db` だけ下げ、終了後に `release_ms` かけて戻す
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DuckingConfig {
+    pub enabled: bool,
+    pub depth_db: f64,
+    pub attack_ms: u64,
+    pub release_ms: u64,
+}
+
+impl Default for DuckingConfig {
+    fn default() -> Self {
+        DuckingConfig {
+            enabled: true,
+            depth_db: 12.0,
+            attack_ms: 300,
+            release_ms: 800,
+        }
+    }
+}
+
+/// ミックスの設定
+/// 読み上げ・BGM・効果音は正規化せずに1回で足し合わせ、音量は dB の補正だけで決める
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MixConfig {
+    /// 読み上げ全体にかける補正 (dB)
+    pub narration_gain_db: f64,
+    /// 効果音全体にかける補正 (dB)
+    pub effects_gain_db: f64,
+    /// 最後にかけるリミッターの上限 (dBFS)。null ならリミッターをかけない
+    pub limiter_ceiling_db: Option<f64>,
+}
+
+impl Default for MixConfig {
+    fn default() -> Self {
+        MixConfig {
+            narration_gain_db: 0.0,
+            effects_gain_db: 0.0,
+            limiter_ceiling_db: Some(-1.0),
+        }
+    }
+}
+
+/// 音量の揃え方の設定
+/// 合成した音声ごとに EBU R128 の統合ラウドネスを測り、目標との差を補正量とする
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct LoudnessConfig {
+    pub enabled: bool,
+    /// 目標の統合ラウドネス (LUFS)
+    pub target_lufs: f64,
+    /// 補正後のトゥルーピークの上限 (dBTP)
+    pub max_true_peak: f64,
+}
+
+impl Default for LoudnessConfig {
+    fn default() -> Self {
+        LoudnessConfig {
+            enabled: true,
+            target_lufs: -16.0,
+            max_true_peak: -1.5,
+        }
+    }
+}
+
+/// 音声合成の設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SpeechConfig {
+    pub engine: EngineKind,
+    /// 読み上げる言語
+    pub language: String,
+    /// 音声カタログの JSON ファイル。未指定なら組み込みの ja-JP 音声を使う
+    pub voices: Option<String>,
+    /// true ならエンジンの list_voices から音声カタログを作る
+    pub discover_voices: bool,
+    /// 発音辞書の JSON ファイル。読み上げ前に表記を読みに差し替える
+    pub lexicon: Option<String>,
+    /// 読み上げ前のテキスト正規化
+    pub normalize: NormalizeConfig,
+    /// 合成した音声の前後の無音の調整
+    pub trim: TrimConfig,
+    /// 合成の代わりに使う録音
+    pub recordings: RecordingConfig,
+    /// 合成に失敗した場合の再試行
+    pub retry: RetryConfig,
+    /// 1分あたりの合成リクエストの上限（クラウドの API 向け）。未指定なら制限しない
+    pub requests_per_minute: Option<u32>,
+    /// 再試行しても合成できなかった行の扱い
+    pub on_failure: FailurePolicy,
+    /// `placeholder` の場合に空ける時間（秒）
+    pub placeholder_seconds: f64,
+    /// 合成結果のキャッシュ先。null ならキャッシュしない
+    pub cache_dir: Option<String>,
+    /// true なら句読点や `[mark]` の位置で、読み上げられた時刻に合わせて字幕を区切る
+    pub split_subtitles: bool,
+    /// 話者ごとの設定。脚本の `{話者名}` で参照する
+    pub speakers: HashMap<String, SpeakerConfig>,
+    pub local: LocalConfig,
+    pub fake: FakeConfig,
+    pub google: GoogleConfig,
+    pub voicevox: VoicevoxConfig,
+    pub command: CommandConfig,
+}
+
+impl Default for SpeechConfig {
+    fn default() -> Self {
+        SpeechConfig {
+            engine: EngineKind::default(),
+            language: "ja-JP".to_string(),
+            voices: None,
+            discover_voices: false,
+            lexicon: None,
+            normalize: NormalizeConfig::default(),
+            trim: TrimConfig::default(),
+            recordings: RecordingConfig::default(),
+            retry: RetryConfig::default(),
+            requests_per_minute: None,
+            on_failure: FailurePolicy::default(),
+            placeholder_seconds: 2.0,
+            cache_dir: Some("./source/cache/tts".to_string()),
+            split_subtitles: false,
+            speakers: HashMap::new(),
+            local: LocalConfig::default(),
+            fake: FakeConfig::default(),
+            google: GoogleConfig::default(),
+            voicevox: VoicevoxConfig::default(),
+            command: CommandConfig::default(),
+        }
+    }
+}
+
+impl SpeechConfig {
+    /// キャッシュキー用の、使うエンジンの設定の文字列表現
+    /// 音声の出力に関わる設定が変われば別のキーになる
+    pub fn engine_key(&self) -> String {
+        match self.engine {
+            EngineKind::Local => self.local.key(),
+            EngineKind::Fake => self.fake.key(),
+            EngineKind::Google => self.google.key(),
+            EngineKind::Voicevox => self.voicevox.key(),
+            EngineKind::Command => self.command.key(),
+        }
+    }
+}
+
+/// テキスト正規化の設定（読み上げる言語が ja / en の場合に有効）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct NormalizeConfig {
+    pub enabled: bool,
+    /// 数値（桁区切り・小数）
+    pub numbers: bool,
+    /// 通貨記号付きの金額
+    pub currency: bool,
+    /// 単位付きの数値（km, GB, Mbps, % など）
+    pub units: bool,
+    /// 日付 (2024/10/13)
+    pub dates: bool,
+    /// 時刻 (12:30)
+    pub times: bool,
+    /// バージョン (v0.1.0)
+    pub versions: bool,
+    pub emails: bool,
+    pub urls: bool,
+}
+
+impl Default for NormalizeConfig {
+    fn default() -> Self {
+        NormalizeConfig {
+            enabled: true,
+            numbers: true,
+            currency: true,
+            units: true,
+            dates: true,
+            times: true,
+            versions: true,
+            emails: true,
+            urls: true,
+        }
+    }
+}
+
+/// 人が録音した読み上げの設定
+/// 脚本の `{rec=ファイル}` と、`dir` 内の `{id}.wav`・`line-{行番号}.wav` を合成の代わりに使う
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RecordingConfig {
+    /// 録音を置くディレクトリ。`rec=` の相対パスもここから探す
+    pub dir: String,
+    /// 行の id・行番号で探すときの拡張子（先にあるものを優先する）
+    pub extensions: Vec<String>,
+    /// 脚本全体を通して読んだ1本の録音（`dir` からの相対パス）。行ごとの録音がない行に分けて使う
+    pub take: Option<String>,
+    /// 通しの録音の分け方
+    pub split: SplitConfig,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        RecordingConfig {
+            dir: "./source/recordings".to_string(),
+            extensions: ["wav", "flac", "mp3", "m4a", "ogg"]
+                .map(String::from)
+                .to_vec(),
+            take: None,
+            split: SplitConfig::default(),
+        }
+    }
+}
+
+/// 通しの録音を無音の位置で行に分ける設定
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SplitConfig {
+    /// 無音とみなす音量 (dBFS)
+    pub threshold_db: f64,
+    /// 行の区切りとみなす無音の長さ (ms)
+    pub min_silence_ms: u64,
+    /// 切り出すときに前後へ残す余白 (ms)
+    pub padding_ms: u64,
+    /// 行の開始位置の指定（キーは行の id または行番号、値は録音の先頭からの秒数）
+    pub pins: HashMap<String, f64>,
+}
+
+impl Default for SplitConfig {
+    fn default() -> Self {
+        SplitConfig {
+            threshold_db: -40.0,
+            min_silence_ms: 350,
+            padding_ms: 100,
+            pins: HashMap::new(),
+        }
+    }
+}
+
+/// 無音の調整の設定
+/// 閾値以下の前後の無音を取り除き、指定の長さの無音を付け足す（WAV 出力のみ）
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TrimConfig {
+    pub enabled: bool,
+    /// 無音とみなす音量 (dBFS)
+    pub threshold_db: f64,
+    /// 先頭に付け足す無音 (ms)
+    pub head_padding_ms: u64,
+    /// 末尾に付け足す無音 (ms)
+    pub tail_padding_ms: u64,
+}
+
+impl Default for TrimConfig {
+    fn default() -> Self {
+        TrimConfig {
+            enabled: true,
+            threshold_db: -50.0,
+            head_padding_ms: 50,
+            tail_padding_ms: 100,
+        }
+    }
+}
+
+impl TrimConfig {
+    /// キャッシュキー用の文字列表現
+    pub fn key(&self) -> String {
+        if !self.enabled {
+            return "off".to_string();
+        }
+        format!(
+            "threshold={:.1},head={},tail={}",
+            self.threshold_db, self.head_padding_ms, self.tail_padding_ms
+        )
+    }
+}
+
+/// 再試行の設定（指数バックオフ）
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// 最初の試行を含む最大の試行回数
+    pub max_attempts: u32,
+    /// 最初の再試行までの待ち時間 (ms)
+    pub initial_backoff_ms: u64,
+    /// 待ち時間の上限 (ms)
+    pub max_backoff_ms: u64,
+    /// 再試行ごとに待ち時間に掛ける倍率
+    pub multiplier: f64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 8000,
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// `attempt` 回目の失敗の後に待つ時間
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ms = self.initial_backoff_ms as f64 * self.multiplier.powi(attempt as i32 - 1);
+        Duration::from_millis(ms.min(self.max_backoff_ms as f64) as u64)
+    }
+}
+
+/// 合成できなかった行の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// 動画の生成を中止する
+    Fail,
+    /// 無音の間を空け、警告の字幕を表示する
+    #[default]
+    Placeholder,
+    /// 行を飛ばす
+    Skip,
+}
+
+/// 話者の設定
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SpeakerConfig {
+    /// 音声名（音声カタログの id）
+    pub voice: Option<String>,
+    /// 話者の速度・高さ・音量。脚本の行ごとの指定で上書きできる
+    #[serde(flatten)]
+    pub prosody: Prosody,
+    /// 話者の声にかける音響効果（定位・イコライザ・残響・帯域・高さ）
+    pub effects: Effects,
+}
+
+/// 音声合成エンジンの種類
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineKind {
+    /// speech.exe を呼び出す
+    #[default]
+    Local,
+    /// テスト用の決定的なエンジン
+    Fake,
+    /// Google Cloud Text-to-Speech (gRPC)
+    Google,
+    /// ローカルで動作する VOICEVOX 互換の HTTP エンジン
+    Voicevox,
+    /// 引数のテンプレートから任意のコマンドを呼び出す
+    Command,
+}
+
+/// speech.exe の設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LocalConfig {
+    /// 実行するバイナリ名またはパス
+    pub binary: String,
+}
+
+impl Default for LocalConfig {
+    fn default() -> Self {
+        LocalConfig {
+            binary: "speech".to_string(),
+        }
+    }
+}
+
+impl LocalConfig {
+    /// キャッシュキー用の文字列表現
+    pub fn key(&self) -> String {
+        format!("binary={}", self.binary)
+    }
+}
+
+/// Google Cloud Text-to-Speech の設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GoogleConfig {
+    /// gRPC エンドポイント
+    pub endpoint: String,
+    pub auth: GoogleAuth,
+    /// サービスアカウントの鍵ファイル（`auth` が `service_account` の場合）
+    pub credentials: Option<String>,
+    pub audio_encoding: AudioEncoding,
+    /// 読み上げ速度 [0.25, 4.0]。0.0 はエンジンの既定値
+    pub speaking_rate: f64,
+    /// ピッチ（半音） [-20.0, 20.0]
+    pub pitch: f64,
+    /// 音量（dB） [-96.0, 16.0]
+    pub volume_gain_db: f64,
+    /// サンプリングレート。未指定なら音声本来のレート
+    pub sample_rate_hertz: Option<i32>,
+}
+
+impl Default for GoogleConfig {
+    fn default() -> Self {
+        GoogleConfig {
+            endpoint: "https://texttospeech.googleapis.com".to_string(),
+            auth: GoogleAuth::Adc,
+            credentials: None,
+            audio_encoding: AudioEncoding::Linear16,
+            speaking_rate: 1.0,
+            pitch: 0.0,
+            volume_gain_db: 0.0,
+            sample_rate_hertz: None,
+        }
+    }
+}
+
+impl GoogleConfig {
+    /// キャッシュキー用の文字列表現（認証の設定は音声に関わらないため含めない）
+    pub fn key(&self) -> String {
+        format!(
+            "endpoint={},encoding={:?},rate={:.3},pitch={:.3},gain={:.3},sample_rate={:?}",
+            self.endpoint,
+            self.audio_encoding,
+            self.speaking_rate,
+            self.pitch,
+            self.volume_gain_db,
+            self.sample_rate_hertz
+        )
+    }
+}
+
+/// VOICEVOX 互換エンジンの設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct VoicevoxConfig {
+    /// エンジンの URL
+    pub endpoint: String,
+    /// 出力のサンプリングレート。未指定ならエンジンの既定値
+    pub output_sampling_rate: Option<u32>,
+}
+
+impl Default for VoicevoxConfig {
+    fn default() -> Self {
+        VoicevoxConfig {
+            endpoint: "http://127.0.0.1:50021".to_string(),
+            output_sampling_rate: None,
+        }
+    }
+}
+
+impl VoicevoxConfig {
+    /// キャッシュキー用の文字列表現
+    pub fn key(&self) -> String {
+        format!(
+            "endpoint={},sample_rate={:?}",
+            self.endpoint, self.output_sampling_rate
+        )
+    }
+}
+
+/// コマンドテンプレートのエンジンの設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CommandConfig {
+    /// エンジン名（ログやキャッシュキーに使う）
+    pub name: String,
+    /// 実行するコマンドと引数。`{text}` `{output}` などを置き換える
+    pub argv: Vec<String>,
+    /// テキストの渡し方
+    pub text_input: TextInput,
+    /// テンプレートで使う任意の値
+    pub vars: HashMap<String, String>,
+    /// 利用できる音声名
+    pub voices: Vec<String>,
+    /// 音声の言語
+    pub language: String,
+}
+
+impl Default for CommandConfig {
+    fn default() -> Self {
+        CommandConfig {
+            name: "command".to_string(),
+            argv: Vec::new(),
+            text_input: TextInput::Argument,
+            vars: HashMap::new(),
+            voices: Vec::new(),
+            language: "ja-JP".to_string(),
+        }
+    }
+}
+
+impl CommandConfig {
+    /// キャッシュキー用の文字列表現（`vars` は順序によらない）
+    pub fn key(&self) -> String {
+        let vars: BTreeMap<_, _> = self.vars.iter().collect();
+        format!(
+            "argv={:?},input={:?},vars={:?}",
+            self.argv, self.text_input, vars
+        )
+    }
+}
+
+/// コマンドへのテキストの渡し方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextInput {
+    /// `{text}` として引数に埋め込む
+    #[default]
+    Argument,
+    /// 標準入力に書き込む
+    Stdin,
+    /// 一時ファイルに書き出し、そのパスを `{textfile}` に埋め込む
+    File,
+}
+
+/// Google API の認証方法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoogleAuth {
+    /// Application Default Credentials
+    /// GOOGLE_APPLICATION_CREDENTIALS があればその鍵を、なければ gcloud のトークンを使う
+    #[default]
+    Adc,
+    /// `credentials` に指定したサービスアカウントの鍵を使う
+    ServiceAccount,
+    /// 認証しない（ローカルのモックサーバ向け）
+    None,
+}
+
+/// 出力する音声の形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioEncoding {
+    /// 16bit PCM（WAV ヘッダ付き）
+    #[default]
+    Linear16,
+    Mp3,
+    OggOpus,
+    Mulaw,
+    Alaw,
+}
+
+/// テスト用エンジンの設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FakeConfig {
+    pub signal: FakeSignal,
+    pub sample_rate: u32,
+    /// サイン波の周波数 (Hz)
+    pub frequency: f64,
+    /// 1文字あたりの長さ（秒）
+    pub seconds_per_char: f64,
+    /// 最短の長さ（秒）
+    pub min_seconds: f64,
+}
+
+impl Default for FakeConfig {
+    fn default() -> Self {
+        FakeConfig {
+            signal: FakeSignal::Tone,
+            sample_rate: 24000,
+            frequency: 440.0,
+            seconds_per_char: 0.15,
+            min_seconds: 0.5,
+        }
+    }
+}
+
+impl FakeConfig {
+    /// キャッシュキー用の文字列表現
+    pub fn key(&self) -> String {
+        format!(
+            "signal={:?},sample_rate={},frequency={:.1},per_char={:.3},min={:.3}",
+            self.signal, self.sample_rate, self.frequency, self.seconds_per_char, self.min_seconds
+        )
+    }
+}
+
+/// テスト用エンジンが出力する信号
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FakeSignal {
+    #[default]
+    Tone,
+    Silence,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = serde_json::from_str(
+            r#"{"speech": {"engine": "fake", "fake": {"signal": "silence", "sample_rate": 16000}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.speech.engine, EngineKind::Fake);
+        assert_eq!(config.speech.fake.signal, FakeSignal::Silence);
+        assert_eq!(config.speech.fake.sample_rate, 16000);
+        assert_eq!(config.speech.local.binary, "speech");
+        assert_eq!(config.speech.language, "ja-JP");
+        assert_eq!(config.audio.loudness, LoudnessConfig::default());
+        assert_eq!(config.audio.mix.limiter_ceiling_db, Some(-1.0));
+        assert!(config.audio.music.cues().is_empty());
+        assert_eq!(config.audio.format.key(), "48000Hz/2ch/S16");
+
+        let config: Config = serde_json::from_str(
+            r#"{"audio": {"format": {"sample_rate": 44100, "channels": 1, "sample_format": "f32"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.audio.format.sample_format, SampleType::F32);
+        assert_eq!(config.audio.format.sample_format.codec(), "pcm_f32le");
+        assert_eq!(config.audio.format.cache_dir, "./source/converted");
+    }
+
+    #[test]
+    fn test_parse_music_config() {
+        let config: Config = serde_json::from_str(
+            r#"{"audio": {"music": {"file": "./source/bgm.mp3", "loop": false, "ducking": {"depth_db": 6.0}}}}"#,
+        )
+        .unwrap();
+
+        let music = config.audio.music;
+        assert!(!music.looped);
+        assert_eq!(music.ducking.depth_db, 6.0);
+        assert_eq!(music.ducking.attack_ms, 300);
+        assert_eq!(
+            music.cues(),
+            vec![MusicCue {
+                file: "./source/bgm.mp3".to_string(),
+                at: "0".to_string(),
+            }]
+        );
+
+        let config: Config = serde_json::from_str(
+            r#"{"audio": {"music": {"file": "./a.mp3", "playlist": [{"file": "./b.mp3", "at": "outro.start"}]}}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.audio.music.cues()[0].file, "./b.mp3");
+    }
+
+    #[test]
+    fn test_parse_google_config() {
+        let config: Config = serde_json::from_str(
+            r#"{"speech": {"engine": "google", "google": {"auth": "service_account", "credentials": "./key.json", "audio_encoding": "mp3", "speaking_rate": 1.25}}}"#,
+        )
+        .unwrap();
+
+        let google = config.speech.google;
+        assert_eq!(config.speech.engine, EngineKind::Google);
+        assert_eq!(google.auth, GoogleAuth::ServiceAccount);
+        assert_eq!(google.credentials.as_deref(), Some("./key.json"));
+        assert_eq!(google.audio_encoding, AudioEncoding::Mp3);
+        assert_eq!(google.speaking_rate, 1.25);
+        assert_eq!(google.endpoint, "https://texttospeech.googleapis.com");
+    }
+
+    #[test]
+    fn test_parse_recordings() {
+        let config: Config = serde_json::from_str(
+            r#"{"speech": {"recordings": {"take": "take.wav", "split": {"threshold_db": -35, "pins": {"q1": 12.5, "7": 30}}}}}"#,
+        )
+        .unwrap();
+
+        let recordings = config.speech.recordings;
+        assert_eq!(recordings.dir, "./source/recordings");
+        assert_eq!(recordings.extensions[0], "wav");
+        assert_eq!(recordings.take.as_deref(), Some("take.wav"));
+        assert_eq!(recordings.split.threshold_db, -35.0);
+        assert_eq!(recordings.split.min_silence_ms, 350);
+        assert_eq!(recordings.split.pins["7"], 30.0);
+    }
+
+    #[test]
+    fn test_parse_speakers() {
+        let config: Config = serde_json::from_str(
+            r#"{"speech": {"cache_dir": null, "speakers": {"司会": {"voice": "ja-JP-Standard-B", "rate": 1.1, "gain": -3.0, "effects": {"pan": -0.3}}}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.speech.cache_dir, None);
+        assert!(config.speech.normalize.enabled);
+        assert_eq!(config.speech.on_failure, FailurePolicy::Placeholder);
+        let speaker = &config.speech.speakers["司会"];
+        assert_eq!(speaker.voice.as_deref(), Some("ja-JP-Standard-B"));
+        assert_eq!(speaker.prosody.rate, Some(1.1));
+        assert_eq!(speaker.prosody.pitch, None);
+        assert_eq!(speaker.prosody.gain, Some(-3.0));
+        assert_eq!(speaker.effects.pan, Some(-0.3));
+        assert!(speaker.effects.eq.is_empty());
+        assert!(config.validate().is_ok());
+
+        // 速度 0 や範囲外の高さは読み込み時に弾く
+        for speaker in [
+            r#"{"rate": 0}"#,
+            r#"{"rate": -1.5}"#,
+            r#"{"pitch": 100}"#,
+            r#"{"effects": {"pitch_shift": -40}}"#,
+        ] {
+            let config: Config = serde_json::from_str(&format!(
+                r#"{{"speech": {{"speakers": {{"司会": {}}}}}}}"#,
+                speaker
+            ))
+            .unwrap();
+            assert!(config.validate().is_err(), "{}", speaker);
+        }
+
+        let config: Config =
+            serde_json::from_str(r#"{"speech": {"placeholder_seconds": -1}}"#).unwrap();
+        assert!(config.validate().is_err());
+        for format in [r#"{"sample_rate": 0}"#, r#"{"channels": 0}"#] {
+            let config: Config =
+                serde_json::from_str(&format!(r#"{{"audio": {{"format": {}}}}}"#, format)).unwrap();
+            assert!(config.validate().is_err(), "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_engine_key() {
+        // 音声の出力に関わる設定が変われば、使うエンジンのキーが変わる
+        let parse = |json: &str| serde_json::from_str::<Config>(json).unwrap().speech;
+        let google = parse(r#"{"speech": {"engine": "google"}}"#);
+        for other in [
+            r#"{"speech": {"engine": "google", "google": {"audio_encoding": "mp3"}}}"#,
+            r#"{"speech": {"engine": "google", "google": {"speaking_rate": 1.2}}}"#,
+            r#"{"speech": {"engine": "voicevox"}}"#,
+        ] {
+            assert_ne!(google.engine_key(), parse(other).engine_key(), "{}", other);
+        }
+        // 使わないエンジンの設定や認証は関係しない
+        let unused = parse(r#"{"speech": {"engine": "google", "voicevox": {"endpoint": "x"}}}"#);
+        assert_eq!(google.engine_key(), unused.engine_key());
+        let auth = parse(r#"{"speech": {"engine": "google", "google": {"auth": "none"}}}"#);
+        assert_eq!(google.engine_key(), auth.engine_key());
+
+        let voicevox = |endpoint: &str| {
+            parse(&format!(
+                r#"{{"speech": {{"engine": "voicevox", "voicevox": {{"endpoint": "{}"}}}}}}"#,
+                endpoint
+            ))
+            .engine_key()
+        };
+        assert_ne!(voicevox("http://a:50021"), voicevox("http://b:50021"));
+
+        let command = |argv: &str, vars: &str| {
+            parse(&format!(
+                r#"{{"speech": {{"engine": "command", "command": {{"argv": {}, "vars": {}}}}}}}"#,
+                argv, vars
+            ))
+            .engine_key()
+        };
+        let base = command(r#"["tts", "{model}"]"#, r#"{"model": "a", "speed": "1"}"#);
+        assert_eq!(
+            base,
+            command(r#"["tts", "{model}"]"#, r#"{"speed": "1", "model": "a"}"#)
+        );
+        assert_ne!(
+            base,
+            command(r#"["tts", "{model}"]"#, r#"{"model": "b", "speed": "1"}"#)
+        );
+        assert_ne!(
+            base,
+            command(
+                r#"["tts", "-q", "{model}"]"#,
+                r#"{"model": "a", "speed": "1"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let config = Config::load("./source/not-found.json").unwrap();
+        assert_eq!(config.speech.local.binary, "speech");
+    }
+}
@@ -0,0 +1,94 @@
+use std::{fs, io, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::mix::Track;
+
+/// 動画に配置した音声クリップ（読み上げ・効果音）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Clip {
+    /// 脚本ファイル上の行番号（1始まり）
+    pub line: usize,
+    /// 読み上げ（narration）か効果音（effect）か
+    #[serde(default)]
+    pub track: Track,
+    /// 音声ファイルのパス
+    pub path: String,
+    /// 動画の先頭からの開始時刻（秒）
+    pub start: f64,
+    /// 再生時間（秒）
+    pub duration: f64,
+    /// 統合ラウドネス (LUFS)。無音や未測定なら null
+    pub loudness: Option<f64>,
+    /// トゥルーピーク (dBTP)。無音や未測定なら null
+    pub true_peak: Option<f64>,
+    /// ミックスの前にかける音量の補正 (dB)
+    pub gain_db: f64,
+}
+
+impl Clip {
+    pub fn start_time(&self) -> Duration {
+        Duration::from_secs_f64(self.start)
+    }
+}
+
+/// 音声クリップの一覧。動画の生成後に JSON で書き出す
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClipManifest {
+    pub clips: Vec<Clip>,
+}
+
+impl ClipManifest {
+    pub fn push(&mut self, clip: Clip) {
+        self.clips.push(clip);
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, io::Error> {
+        serde_json::from_str(json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// 一覧をファイルに書き出す
+    pub fn write(&self, path: &str) -> Result<(), io::Error> {
+        fs::write(path, self.to_json())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_roundtrip() {
+        let mut manifest = ClipManifest::default();
+        manifest.push(Clip {
+            line: 3,
+            track: Track::Narration,
+            path: "./source/clips/line-3.wav".to_string(),
+            start: 1.5,
+            duration: 2.25,
+            loudness: Some(-21.3),
+            true_peak: Some(-4.0),
+            gain_db: 5.3,
+        });
+        manifest.push(Clip {
+            line: 4,
+            track: Track::Effect,
+            path: "./source/sfx/chime.wav".to_string(),
+            start: 4.75,
+            duration: 0.5,
+            loudness: None,
+            true_peak: None,
+            gain_db: 0.0,
+        });
+
+        let json = manifest.to_json();
+        assert!(json.contains("\"loudness\": null"));
+        assert!(json.contains("\"track\": \"effect\""));
+        assert_eq!(ClipManifest::from_json(&json).unwrap(), manifest);
+        assert_eq!(manifest.clips[0].start_time(), Duration::from_millis(1500));
+    }
+}
@@ -1,3 +1,8 @@
 pub mod ass_subtitle;
+pub mod config;
+pub mod manifest;
+pub mod report;
+pub mod script;
 pub mod slide;
 pub mod subtitle;
+pub mod timeline;
@@ -0,0 +1,63 @@
+use std::{fmt::Write, fs, io};
+
+/// 音声を生成できなかった行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedLine {
+    /// 脚本ファイル上の行番号（1始まり）
+    pub number: usize,
+    /// 脚本の行
+    pub text: String,
+    pub error: String,
+}
+
+/// 失敗した行の一覧。動画の生成後にまとめて報告する
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FailureReport {
+    pub lines: Vec<FailedLine>,
+}
+
+impl FailureReport {
+    pub fn push(&mut self, number: usize, text: &str, error: &dyn std::error::Error) {
+        self.lines.push(FailedLine {
+            number,
+            text: text.to_string(),
+            error: error.to_string(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// 1行1件の報告文
+    pub fn summary(&self) -> String {
+        let mut out = format!("{} line(s) failed\n", self.lines.len());
+        for line in &self.lines {
+            let _ = writeln!(out, "line {}: {}: {}", line.number, line.text, line.error);
+        }
+        out
+    }
+
+    /// 報告をファイルに書き出す
+    pub fn write(&self, path: &str) -> Result<(), io::Error> {
+        fs::write(path, self.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut report = FailureReport::default();
+        assert!(report.is_empty());
+
+        report.push(3, "こんにちは", &io::Error::other("timeout"));
+        report.push(7, "さようなら", &io::Error::other("quota exceeded"));
+        assert_eq!(
+            report.summary(),
+            "2 line(s) failed\nline 3: こんにちは: timeout\nline 7: さようなら: quota exceeded\n"
+        );
+    }
+}
@@ -0,0 +1,532 @@
+use std::{fmt, io, time::Duration};
+
+use super::timeline::{is_valid_offset, Anchor, Timing};
+use crate::speech::prosody::Prosody;
+
+/// 脚本の1行（1回の音声合成の単位）
+///
+/// 記法
+/// - `{話者 rate=1.2 pitch=-2 gain=3}`: 行頭で話者と速度・高さ・音量を指定する
+/// - `字幕の表記 || 読み上げるテキスト`: 字幕と読み上げを別々に書く
+/// - `{only=spoken}`: 読み上げのみ（字幕なし）
+/// - `{only=display duration=2.5}`: 字幕のみ（音声なし）。表示時間は秒で指定する
+/// - `{id=intro gap=-0.5 at=prev.start}`: 行の名前・前の行との間隔・開始時刻（`timeline::Anchor`）
+/// - `[sfx chime.wav at=end offset=-0.2 gain=-6]`: 効果音（`SfxCue`）。本文のどこに書いてもよい
+/// - `{rec=intro.wav}`: 合成の代わりに録音を使う（`speech.recordings.dir` からの相対パス）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptLine {
+    /// 脚本ファイル上の行番号（1始まり）
+    pub number: usize,
+    /// 話者名。config.json の speech.speakers を参照する
+    pub speaker: Option<String>,
+    /// 行ごとの速度・高さ・音量。話者の設定を上書きする
+    pub prosody: Prosody,
+    /// 字幕に表示するテキスト（マークアップを含む）。None なら字幕を出さない
+    pub display: Option<String>,
+    /// 読み上げるテキスト（マークアップを含む）。None なら音声を出さない
+    pub spoken: Option<String>,
+    /// 字幕のみの行の表示時間
+    pub duration: Option<Duration>,
+    /// 行の配置（重なり・同時・時刻の指定）
+    pub timing: Timing,
+    /// 行に合わせて鳴らす効果音
+    pub sfx: Vec<SfxCue>,
+    /// 合成の代わりに使う録音のファイル
+    pub recording: Option<String>,
+}
+
+/// 効果音を鳴らす位置
+#[derive(Debug, Clone, PartialEq)]
+pub enum SfxAt {
+    /// 行の開始（既定）
+    Start,
+    /// 行の終了
+    End,
+    /// 動画の先頭からの時刻
+    Absolute(Duration),
+}
+
+/// 効果音の指定 `[sfx ファイル at=start|end|秒 offset=秒 gain=dB]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SfxCue {
+    /// 効果音のファイル（audio.sfx_dir からの相対パス）
+    pub file: String,
+    pub at: SfxAt,
+    /// 位置からずらす時間（秒）。負の値なら前にずらす
+    pub offset: f64,
+    /// 音量の補正 (dB)
+    pub gain_db: f64,
+}
+
+impl SfxCue {
+    /// タグの中身（`sfx` に続く部分）を解析する
+    fn parse(number: usize, args: &str) -> Result<Self, io::Error> {
+        let mut tokens = args.split_whitespace();
+        let file = tokens
+            .next()
+            .ok_or_else(|| invalid(number, "[sfx] needs a file"))?;
+        let mut cue = SfxCue {
+            file: file.to_string(),
+            at: SfxAt::Start,
+            offset: 0.0,
+            gain_db: 0.0,
+        };
+        for token in tokens {
+            let (key, value) = token
+                .split_once('=')
+                .ok_or_else(|| invalid(number, &format!("unexpected `{}` in [sfx]", token)))?;
+            let number_value = || {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|v| v.is_finite())
+                    .ok_or_else(|| {
+                        invalid(number, &format!("invalid value for `{}`: {}", key, value))
+                    })
+            };
+            match key {
+                "at" => {
+                    cue.at = match value {
+                        "start" => SfxAt::Start,
+                        "end" => SfxAt::End,
+                        _ => match number_value()? {
+                            seconds if seconds < 0.0 => {
+                                return Err(invalid(number, "sfx time must not be negative"))
+                            }
+                            seconds => SfxAt::Absolute(
+                                Duration::try_from_secs_f64(seconds)
+                                    .map_err(|_| invalid(number, "sfx time is out of range"))?,
+                            ),
+                        },
+                    }
+                }
+                "offset" => match number_value()? {
+                    offset if is_valid_offset(offset) => cue.offset = offset,
+                    _ => return Err(invalid(number, "sfx offset is out of range")),
+                },
+                "gain" => cue.gain_db = number_value()?,
+                _ => return Err(invalid(number, &format!("unknown key `{}` in [sfx]", key))),
+            }
+        }
+        Ok(cue)
+    }
+
+    /// 鳴らす時刻（行の開始 `start` と長さ `duration` から求める。先頭より前は 0）
+    /// Duration に収まらない時刻はエラー
+    pub fn time(&self, start: Duration, duration: Duration) -> Result<Duration, io::Error> {
+        let base = match self.at {
+            SfxAt::Start => start,
+            SfxAt::End => start.saturating_add(duration),
+            SfxAt::Absolute(time) => time,
+        };
+        let seconds = base.as_secs_f64() + self.offset;
+        Duration::try_from_secs_f64(seconds.max(0.0)).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("sfx {}: time out of range: {} s", self.file, seconds),
+            )
+        })
+    }
+}
+
+/// 本文から `[sfx ...]` を取り除き、効果音の指定として返す
+fn take_sfx(number: usize, body: &str) -> Result<(String, Vec<SfxCue>), io::Error> {
+    let mut text = String::new();
+    let mut cues = Vec::new();
+    let mut rest = body;
+    while let Some(open) = rest.find("[sfx") {
+        let after = &rest[open + "[sfx".len()..];
+        // `[sfxx]` のような別の文字列は残す
+        if !after.starts_with(|c: char| c.is_whitespace() || c == ']') {
+            text.push_str(&rest[..open + "[sfx".len()]);
+            rest = after;
+            continue;
+        }
+        let close = after
+            .find(']')
+            .ok_or_else(|| invalid(number, "unclosed [sfx]"))?;
+        cues.push(SfxCue::parse(number, &after[..close])?);
+        text.push_str(&rest[..open]);
+        rest = after[close + 1..].trim_start();
+    }
+    text.push_str(rest);
+    Ok((text, cues))
+}
+
+/// 字幕のみの行の既定の表示時間（秒）
+pub const DISPLAY_ONLY_SECONDS: f64 = 3.0;
+
+impl ScriptLine {
+    pub fn parse(number: usize, line: &str) -> Result<Self, io::Error> {
+        let line = line.trim();
+        let mut script = ScriptLine {
+            number,
+            speaker: None,
+            prosody: Prosody::default(),
+            display: None,
+            spoken: None,
+            duration: None,
+            timing: Timing::default(),
+            sfx: Vec::new(),
+            recording: None,
+        };
+
+        let (header, body) = match line.strip_prefix('{') {
+            Some(rest) => rest
+                .split_once('}')
+                .ok_or_else(|| invalid(number, "unclosed `{` header"))?,
+            None => ("", line),
+        };
+
+        let mut only = None;
+        for token in header.split_whitespace() {
+            let Some((key, value)) = token.split_once('=') else {
+                if script.speaker.is_some() {
+                    return Err(invalid(number, &format!("unexpected `{}`", token)));
+                }
+                script.speaker = Some(token.to_string());
+                continue;
+            };
+            if key == "only" {
+                match value {
+                    "display" | "spoken" => only = Some(value),
+                    _ => {
+                        return Err(invalid(
+                            number,
+                            &format!("invalid value for `only`: {}", value),
+                        ))
+                    }
+                }
+                continue;
+            }
+            match key {
+                "id" if !value.is_empty() => {
+                    script.timing.id = Some(value.to_string());
+                    continue;
+                }
+                "rec" if !value.is_empty() => {
+                    script.recording = Some(value.to_string());
+                    continue;
+                }
+                "at" => {
+                    script.timing.at =
+                        Some(Anchor::parse(value).map_err(|e| invalid(number, &e.to_string()))?);
+                    continue;
+                }
+                _ => {}
+            }
+            // "inf" や "NaN" も f64 として読めるため、有限値に限る
+            let value = value
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| {
+                    invalid(number, &format!("invalid value for `{}`: {}", key, value))
+                })?;
+            match key {
+                "rate" if value > 0.0 => script.prosody.rate = Some(value),
+                "rate" => return Err(invalid(number, "rate must be positive")),
+                "pitch" => script.prosody.pitch = Some(value),
+                "gain" => script.prosody.gain = Some(value),
+                "duration" if value > 0.0 => {
+                    script.duration = Some(
+                        Duration::try_from_secs_f64(value)
+                            .map_err(|_| invalid(number, "duration is out of range"))?,
+                    )
+                }
+                "duration" => return Err(invalid(number, "duration must be positive")),
+                "gap" if is_valid_offset(value) => script.timing.gap = Some(value),
+                "gap" => return Err(invalid(number, "gap is out of range")),
+                _ => return Err(invalid(number, &format!("unknown key `{}`", key))),
+            }
+        }
+
+        script.prosody.validate().map_err(|e| invalid(number, &e))?;
+
+        let (body, sfx) = take_sfx(number, body)?;
+        script.sfx = sfx;
+        let (display, spoken) = match body.split_once("||") {
+            Some((display, spoken)) => (display.trim(), spoken.trim()),
+            None => (body.trim(), body.trim()),
+        };
+        script.display =
+            (only != Some("spoken") && !display.is_empty()).then(|| display.to_string());
+        script.spoken = (only != Some("display") && !spoken.is_empty()).then(|| spoken.to_string());
+        if script.timing.gap.is_some() && script.timing.at.is_some() {
+            return Err(invalid(number, "gap cannot be combined with at"));
+        }
+        if script.recording.is_some() && only == Some("display") {
+            return Err(invalid(number, "rec cannot be combined with only=display"));
+        }
+        if script.duration.is_some() && script.spoken.is_some() {
+            return Err(invalid(number, "duration is only for display-only lines"));
+        }
+
+        Ok(script)
+    }
+
+    /// 字幕のみの行の表示時間
+    /// 効果音だけの行は、指定がなければ時間を取らない
+    pub fn display_duration(&self) -> Duration {
+        match (self.duration, &self.display) {
+            (Some(duration), _) => duration,
+            (None, Some(_)) => Duration::from_secs_f64(DISPLAY_ONLY_SECONDS),
+            (None, None) => Duration::ZERO,
+        }
+    }
+
+    /// `gap` を指定しない行の前の行との間隔（秒）
+    /// 字幕も読み上げもない効果音だけの行は間隔を空けず、次の行との間隔が二重にならないようにする
+    pub fn default_gap(&self, gap: f64) -> f64 {
+        if self.display.is_none() && self.spoken.is_none() {
+            0.0
+        } else {
+            gap
+        }
+    }
+}
+
+fn invalid(number: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("script line {}: {}", number, message),
+    )
+}
+
+/// 解析できなかった脚本の行
+#[derive(Debug)]
+pub struct ScriptError {
+    /// 脚本ファイル上の行番号（1始まり）
+    pub number: usize,
+    /// 元の行
+    pub text: String,
+    pub error: io::Error,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::error::Error for ScriptError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<ScriptError> for io::Error {
+    fn from(e: ScriptError) -> Self {
+        e.error
+    }
+}
+
+/// 脚本を行ごとに解析する。空行は読み飛ばす
+pub fn parse_script(content: &str) -> Vec<Result<ScriptLine, ScriptError>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            ScriptLine::parse(i + 1, line).map_err(|error| ScriptError {
+                number: i + 1,
+                text: line.trim().to_string(),
+                error,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::timeline::{LineRef, Timeline};
+
+    #[test]
+    fn test_parse_plain_line() {
+        let line = ScriptLine::parse(1, "こんにちは").unwrap();
+        assert_eq!(line.speaker, None);
+        assert!(line.prosody.is_neutral());
+        assert_eq!(line.display.as_deref(), Some("こんにちは"));
+        assert_eq!(line.spoken.as_deref(), Some("こんにちは"));
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let line = ScriptLine::parse(
+            3,
+            "{司会 rate=1.2 pitch=-2 gain=3.5} ようこそ [pause 500ms]",
+        )
+        .unwrap();
+        assert_eq!(line.number, 3);
+        assert_eq!(line.speaker.as_deref(), Some("司会"));
+        assert_eq!(line.prosody.rate, Some(1.2));
+        assert_eq!(line.prosody.pitch, Some(-2.0));
+        assert_eq!(line.prosody.gain, Some(3.5));
+        assert_eq!(line.display.as_deref(), Some("ようこそ [pause 500ms]"));
+
+        let line = ScriptLine::parse(4, "{rate=0.8}ゆっくり").unwrap();
+        assert_eq!(line.speaker, None);
+        assert_eq!(line.prosody.rate, Some(0.8));
+        assert_eq!(line.spoken.as_deref(), Some("ゆっくり"));
+
+        // 録音を使う行。本文は字幕にだけ使う
+        let line = ScriptLine::parse(5, "{司会 rec=take2/intro.wav} ようこそ").unwrap();
+        assert_eq!(line.recording.as_deref(), Some("take2/intro.wav"));
+        assert_eq!(line.display.as_deref(), Some("ようこそ"));
+        let line = ScriptLine::parse(6, "{rec=jingle.wav}").unwrap();
+        assert_eq!((line.display, line.spoken), (None, None));
+    }
+
+    #[test]
+    fn test_parse_display_and_spoken() {
+        let line = ScriptLine::parse(1, "{司会} 10km || じゅっキロメートル").unwrap();
+        assert_eq!(line.display.as_deref(), Some("10km"));
+        assert_eq!(line.spoken.as_deref(), Some("じゅっキロメートル"));
+
+        let line = ScriptLine::parse(2, "{only=spoken} (効果音の説明)").unwrap();
+        assert_eq!(line.display, None);
+        assert_eq!(line.spoken.as_deref(), Some("(効果音の説明)"));
+
+        let line = ScriptLine::parse(3, "{only=display duration=2.5} 第1章").unwrap();
+        assert_eq!(line.display.as_deref(), Some("第1章"));
+        assert_eq!(line.spoken, None);
+        assert_eq!(line.display_duration(), Duration::from_millis(2500));
+
+        // 片方を空にしても同じ意味になる
+        let line = ScriptLine::parse(4, "|| 読み上げのみ").unwrap();
+        assert_eq!(line.display, None);
+        let line = ScriptLine::parse(5, "字幕のみ ||").unwrap();
+        assert_eq!(line.spoken, None);
+        assert_eq!(
+            line.display_duration(),
+            Duration::from_secs_f64(DISPLAY_ONLY_SECONDS)
+        );
+    }
+
+    #[test]
+    fn test_parse_timing() {
+        let line = ScriptLine::parse(1, "{司会 id=q1} 質問です").unwrap();
+        assert_eq!(line.timing.id.as_deref(), Some("q1"));
+        assert_eq!(line.timing.at, None);
+
+        let line = ScriptLine::parse(2, "{ゲスト gap=-0.4} えっ").unwrap();
+        assert_eq!(line.timing.gap, Some(-0.4));
+
+        let line = ScriptLine::parse(3, "{ゲスト at=q1.end-0.2} はい").unwrap();
+        assert_eq!(
+            line.timing.at,
+            Some(Anchor::End(LineRef::Id("q1".to_string()), -0.2))
+        );
+
+        assert!(ScriptLine::parse(4, "{at=soon} a").is_err());
+        assert!(ScriptLine::parse(4, "{gap=x} a").is_err());
+        assert!(ScriptLine::parse(4, "{id=} a").is_err());
+        assert!(ScriptLine::parse(4, "{gap=1 at=prev.start} a").is_err());
+        assert!(ScriptLine::parse(4, "{gap=1e300} a").is_err());
+        assert!(ScriptLine::parse(4, "{at=1e300} a").is_err());
+        assert!(ScriptLine::parse(4, "{at=q1.end+inf} a").is_err());
+    }
+
+    #[test]
+    fn test_parse_sfx() {
+        let line = ScriptLine::parse(
+            1,
+            "{司会} [sfx chime.wav] 正解です！ [sfx drum.wav at=end offset=-0.2 gain=-6]",
+        )
+        .unwrap();
+        assert_eq!(line.display.as_deref(), Some("正解です！"));
+        assert_eq!(line.spoken.as_deref(), Some("正解です！"));
+        assert_eq!(line.sfx.len(), 2);
+        assert_eq!(
+            line.sfx[1],
+            SfxCue {
+                file: "drum.wav".to_string(),
+                at: SfxAt::End,
+                offset: -0.2,
+                gain_db: -6.0,
+            }
+        );
+
+        let start = Duration::from_secs(10);
+        let duration = Duration::from_secs(2);
+        assert_eq!(line.sfx[0].time(start, duration).unwrap(), start);
+        assert_eq!(
+            line.sfx[1].time(start, duration).unwrap(),
+            Duration::from_millis(11800)
+        );
+
+        // 効果音だけの行は時間を取らない
+        let line = ScriptLine::parse(2, "[sfx whoosh.mp3 at=12.5]").unwrap();
+        assert_eq!((line.display.clone(), line.spoken.clone()), (None, None));
+        assert_eq!(line.display_duration(), Duration::ZERO);
+        assert_eq!(
+            line.sfx[0].time(Duration::ZERO, Duration::ZERO).unwrap(),
+            Duration::from_millis(12500)
+        );
+
+        // 効果音だけの行は既定の間隔を空けず、前後の行の間隔は一度だけになる
+        let mut timeline = Timeline::default();
+        let before = ScriptLine::parse(1, "はい").unwrap();
+        let sfx = ScriptLine::parse(2, "[sfx chime.wav]").unwrap();
+        let after = ScriptLine::parse(3, "どうぞ").unwrap();
+        assert_eq!(before.default_gap(0.5), 0.5);
+        assert_eq!(sfx.default_gap(0.5), 0.0);
+        let second = Duration::from_secs(1);
+        timeline.place(&before.timing, 0.5, second).unwrap();
+        let sfx_start = timeline
+            .place(&sfx.timing, sfx.default_gap(0.5), sfx.display_duration())
+            .unwrap();
+        assert_eq!(sfx_start, second);
+        let start = timeline
+            .place(&after.timing, after.default_gap(0.5), second)
+            .unwrap();
+        assert_eq!(start, Duration::from_millis(1500));
+
+        assert!(ScriptLine::parse(3, "a [sfx]").is_err());
+        assert!(ScriptLine::parse(3, "a [sfx a.wav").is_err());
+        assert!(ScriptLine::parse(3, "a [sfx a.wav at=-1]").is_err());
+        assert!(ScriptLine::parse(3, "a [sfx a.wav volume=3]").is_err());
+        // Duration に収まらない時刻・ずれは panic せずにエラーにする
+        assert!(ScriptLine::parse(3, "a [sfx a.wav at=1e300]").is_err());
+        assert!(ScriptLine::parse(3, "a [sfx a.wav offset=1e300]").is_err());
+        assert!(ScriptLine::parse(3, "{only=display duration=1e300} a").is_err());
+        let line = ScriptLine::parse(3, "a [sfx a.wav offset=1.8e19]").unwrap();
+        assert!(line.sfx[0].time(Duration::MAX, Duration::ZERO).is_err());
+        assert_eq!(
+            ScriptLine::parse(3, "[sfxx] a").unwrap().display.as_deref(),
+            Some("[sfxx] a")
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_header() {
+        assert!(ScriptLine::parse(1, "{司会 こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{司会 speed=1.2} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{司会 rate=fast} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{司会 rate=0} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{司会 rate=inf} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{司会 pitch=-inf} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{司会 pitch=NaN} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{司会 pitch=48} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{only=display duration=inf} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{司会 ゲスト} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{only=both} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{duration=2} こんにちは").is_err());
+        assert!(ScriptLine::parse(1, "{rec=a.wav only=display} こんにちは").is_err());
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let lines = parse_script("一行目\n\n{ゲスト} 三行目\n{rate=x} 四行目");
+        assert_eq!(lines.len(), 3);
+        let error = lines[2].as_ref().unwrap_err();
+        assert_eq!(error.number, 4);
+        assert_eq!(error.text, "{rate=x} 四行目");
+        assert_eq!(lines[1].as_ref().unwrap().number, 3);
+        assert_eq!(
+            lines[1].as_ref().unwrap().speaker.as_deref(),
+            Some("ゲスト")
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::{io, time::Duration};
+
+/// 行の開始時刻の指定（脚本のヘッダの `at=`）
+///
+/// - `at=12.5`: 動画の先頭からの秒数
+/// - `at=prev.start`: 直前の行と同時に始める
+/// - `at=prev.end-0.3`: 直前の行の終わる 0.3 秒前に割り込む
+/// - `at=intro.end+1`: `id=intro` の行が終わって 1 秒後に始める
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anchor {
+    Absolute(Duration),
+    Start(LineRef, f64),
+    End(LineRef, f64),
+}
+
+/// 基準にする行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineRef {
+    /// 直前に配置した行
+    Previous,
+    /// `id=` で名前を付けた行
+    Id(String),
+}
+
+impl Anchor {
+    pub fn parse(value: &str) -> Result<Self, io::Error> {
+        if let Ok(seconds) = value.parse::<f64>() {
+            // 負の値・"inf"・Duration に収まらない値（1e300 など）は弾く
+            return Duration::try_from_secs_f64(seconds)
+                .map(Anchor::Absolute)
+                .map_err(|_| invalid(format!("invalid anchor time: {}", value)));
+        }
+
+        for (edge, start) in [(".start", true), (".end", false)] {
+            let Some(position) = value.find(edge) else {
+                continue;
+            };
+            let name = &value[..position];
+            let offset = match &value[position + edge.len()..] {
+                "" => 0.0,
+                rest if rest.starts_with(['+', '-']) => rest
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|offset| is_valid_offset(*offset))
+                    .ok_or_else(|| invalid(format!("invalid anchor offset: {}", value)))?,
+                _ => continue,
+            };
+            let line = match name {
+                "" => return Err(invalid(format!("anchor needs a line: {}", value))),
+                "prev" => LineRef::Previous,
+                id => LineRef::Id(id.to_string()),
+            };
+            return Ok(match start {
+                true => Anchor::Start(line, offset),
+                false => Anchor::End(line, offset),
+            });
+        }
+        Err(invalid(format!(
+            "anchor must be seconds or <line>.start/.end[+-seconds]: {}",
+            value
+        )))
+    }
+}
+
+/// 行の配置の指定
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timing {
+    /// 他の行から参照するための名前（`id=`）
+    pub id: Option<String>,
+    /// 前の行との間隔（秒、`gap=`）。負の値なら前の行に重ねる
+    pub gap: Option<f64>,
+    /// 開始時刻の指定（`at=`）。`gap` より優先する
+    pub at: Option<Anchor>,
+}
+
+/// 配置した行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placed {
+    pub id: Option<String>,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// 行の開始時刻を決める
+/// 指定のない行は、それまでに配置した行がすべて終わってから間隔を空けて始める
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    placed: Vec<Placed>,
+}
+
+impl Timeline {
+    /// 行を配置し、開始時刻を返す
+    /// `default_gap` は `gap` を指定しない行の前の行との間隔（秒）
+    pub fn place(
+        &mut self,
+        timing: &Timing,
+        default_gap: f64,
+        duration: Duration,
+    ) -> Result<Duration, io::Error> {
+        if let Some(id) = &timing.id {
+            if self.find(id).is_some() {
+                return Err(invalid(format!("duplicate line id `{}`", id)));
+            }
+        }
+
+        let start = match &timing.at {
+            Some(anchor) => self.resolve(anchor)?,
+            None => {
+                let seconds = match self.end() {
+                    // 最初の行は先頭から
+                    None => timing.gap.unwrap_or(0.0),
+                    Some(end) => end.as_secs_f64() + timing.gap.unwrap_or(default_gap),
+                };
+                to_duration(seconds)?
+            }
+        };
+        let end = start
+            .checked_add(duration)
+            .ok_or_else(|| invalid(format!("line ends out of range: {:?}", start)))?;
+
+        self.placed.push(Placed {
+            id: timing.id.clone(),
+            start,
+            end,
+        });
+        Ok(start)
+    }
+
+    /// 配置の指定（`gap` / `at`）を使わずに、それまでの行に続けて配置する
+    /// 指定が誤っていた行を置くのに使う。`id` は他の行で使われていなければ残す
+    pub fn place_fallback(
+        &mut self,
+        timing: &Timing,
+        default_gap: f64,
+        duration: Duration,
+    ) -> Result<Duration, io::Error> {
+        let id = timing.id.clone().filter(|id| self.find(id).is_none());
+        let timing = Timing {
+            id,
+            gap: None,
+            at: None,
+        };
+        self.place(&timing, default_gap, duration)
+    }
+
+    /// 配置済みの行を基準に時刻を求める（先頭より前は 0）
+    pub fn resolve(&self, anchor: &Anchor) -> Result<Duration, io::Error> {
+        let seconds = match anchor {
+            Anchor::Absolute(time) => time.as_secs_f64(),
+            Anchor::Start(line, offset) => self.line(line)?.start.as_secs_f64() + offset,
+            Anchor::End(line, offset) => self.line(line)?.end.as_secs_f64() + offset,
+        };
+        to_duration(seconds)
+    }
+
+    /// 配置した行のうち最も遅い終了時刻
+    pub fn end(&self) -> Option<Duration> {
+        self.placed.iter().map(|p| p.end).max()
+    }
+
+    fn find(&self, id: &str) -> Option<&Placed> {
+        self.placed.iter().find(|p| p.id.as_deref() == Some(id))
+    }
+
+    fn line(&self, line: &LineRef) -> Result<&Placed, io::Error> {
+        match line {
+            LineRef::Previous => self
+                .placed
+                .last()
+                .ok_or_else(|| invalid("`prev` is used on the first line".to_string())),
+            LineRef::Id(id) => self.find(id).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "unknown line id `{}` (lines can only refer to earlier lines)",
+                        id
+                    ),
+                )
+            }),
+        }
+    }
+}
+
+/// 間隔・ずれの秒数として使える値かどうか（有限で、絶対値が Duration に収まる）
+pub fn is_valid_offset(seconds: f64) -> bool {
+    Duration::try_from_secs_f64(seconds.abs()).is_ok()
+}
+
+/// 秒数を時刻にする（先頭より前は 0）。Duration に収まらない時刻はエラー
+fn to_duration(seconds: f64) -> Result<Duration, io::Error> {
+    Duration::try_from_secs_f64(seconds.max(0.0))
+        .map_err(|_| invalid(format!("time out of range: {} s", seconds)))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: f64) -> Duration {
+        Duration::from_secs_f64(seconds)
+    }
+
+    fn timing(id: Option<&str>, gap: Option<f64>, at: Option<&str>) -> Timing {
+        Timing {
+            id: id.map(str::to_string),
+            gap,
+            at: at.map(|a| Anchor::parse(a).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_parse_anchor() {
+        assert_eq!(Anchor::parse("12.5").unwrap(), Anchor::Absolute(secs(12.5)));
+        assert_eq!(
+            Anchor::parse("prev.start").unwrap(),
+            Anchor::Start(LineRef::Previous, 0.0)
+        );
+        assert_eq!(
+            Anchor::parse("intro.end-0.3").unwrap(),
+            Anchor::End(LineRef::Id("intro".to_string()), -0.3)
+        );
+        assert_eq!(
+            Anchor::parse("q.1.start+1").unwrap(),
+            Anchor::Start(LineRef::Id("q.1".to_string()), 1.0)
+        );
+        assert!(Anchor::parse("-1").is_err());
+        assert!(Anchor::parse("intro").is_err());
+        assert!(Anchor::parse(".start").is_err());
+        assert!(Anchor::parse("intro.end+x").is_err());
+        // 無限大や Duration に収まらない値は panic せずにエラーにする
+        for value in [
+            "inf",
+            "NaN",
+            "1e300",
+            "q1.end+inf",
+            "q1.start-NaN",
+            "q1.end+1e300",
+        ] {
+            assert!(Anchor::parse(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_sequential_and_gap() {
+        let mut timeline = Timeline::default();
+        let t = Timing::default();
+        assert_eq!(timeline.place(&t, 1.0, secs(2.0)).unwrap(), secs(0.0));
+        assert_eq!(timeline.place(&t, 1.0, secs(2.0)).unwrap(), secs(3.0));
+        // 負の間隔で前の行に割り込む
+        let interrupt = timing(None, Some(-0.5), None);
+        assert_eq!(
+            timeline.place(&interrupt, 1.0, secs(1.0)).unwrap(),
+            secs(4.5)
+        );
+        assert_eq!(timeline.end(), Some(secs(5.5)));
+
+        // 収まらない時刻は panic せずにエラーにする
+        let far = timing(None, Some(1e300), None);
+        assert!(timeline.place(&far, 1.0, secs(1.0)).is_err());
+        assert!(timeline.place(&t, 1.0, Duration::MAX).is_err());
+    }
+
+    #[test]
+    fn test_anchors() {
+        let mut timeline = Timeline::default();
+        let question = timing(Some("q"), None, None);
+        assert_eq!(
+            timeline.place(&question, 1.0, secs(4.0)).unwrap(),
+            secs(0.0)
+        );
+        // 同時に話す短い相づち
+        let nod = timing(None, None, Some("prev.start+1"));
+        assert_eq!(timeline.place(&nod, 1.0, secs(0.5)).unwrap(), secs(1.0));
+        // 指定のない行は長い方が終わってから
+        let answer = timing(None, None, None);
+        assert_eq!(timeline.place(&answer, 1.0, secs(2.0)).unwrap(), secs(5.0));
+        // 名前で参照する・先頭より前は 0 に揃える
+        let echo = timing(None, None, Some("q.end-0.5"));
+        assert_eq!(timeline.place(&echo, 1.0, secs(1.0)).unwrap(), secs(3.5));
+        let early = timing(None, None, Some("q.start-3"));
+        assert_eq!(timeline.place(&early, 1.0, secs(1.0)).unwrap(), secs(0.0));
+        let absolute = timing(None, None, Some("20"));
+        assert_eq!(
+            timeline.place(&absolute, 1.0, secs(1.0)).unwrap(),
+            secs(20.0)
+        );
+
+        assert_eq!(
+            timeline
+                .place(&timing(None, None, Some("later.start")), 1.0, secs(1.0))
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(timeline.place(&question, 1.0, secs(1.0)).is_err());
+        assert!(Timeline::default()
+            .place(&timing(None, None, Some("prev.end")), 1.0, secs(1.0))
+            .is_err());
+    }
+
+    #[test]
+    fn test_place_fallback() {
+        let mut timeline = Timeline::default();
+        let question = timing(Some("q"), None, None);
+        timeline.place(&question, 1.0, secs(2.0)).unwrap();
+
+        // 誤った参照は無視して前の行に続け、id は残す
+        let broken = timing(Some("a"), None, Some("missing.end"));
+        assert!(timeline.place(&broken, 1.0, secs(1.0)).is_err());
+        assert_eq!(
+            timeline.place_fallback(&broken, 1.0, secs(1.0)).unwrap(),
+            secs(3.0)
+        );
+        let after = timing(None, None, Some("a.end"));
+        assert_eq!(timeline.place(&after, 1.0, secs(1.0)).unwrap(), secs(4.0));
+
+        // 重複した id は付けない
+        assert_eq!(
+            timeline.place_fallback(&question, 1.0, secs(1.0)).unwrap(),
+            secs(6.0)
+        );
+        let echo = timing(None, None, Some("q.end"));
+        assert_eq!(timeline.place(&echo, 1.0, secs(1.0)).unwrap(), secs(2.0));
+    }
+}
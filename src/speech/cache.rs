@@ -0,0 +1,204 @@
+use std::{fs, io, path::Path, time::Duration};
+
+use async_trait::async_trait;
+
+use super::{timepoint, SpeechEngine, SynthesisRequest, Synthesized, VoiceInfo};
+
+/// 合成結果をディスクにキャッシュするエンジン
+/// 同じ内容（エンジン・言語・音声・テキスト・SSML・速度/高さ/音量）の合成は再実行しない
+pub struct CachedEngine {
+    inner: Box<dyn SpeechEngine>,
+    dir: String,
+    /// 合成後の加工など、キーに含める追加の条件
+    salt: String,
+}
+
+impl CachedEngine {
+    pub fn new(inner: Box<dyn SpeechEngine>, dir: &str) -> Self {
+        CachedEngine {
+            inner,
+            dir: dir.to_string(),
+            salt: String::new(),
+        }
+    }
+
+    /// キャッシュキーに含める追加の条件を指定する
+    pub fn with_salt(mut self, salt: &str) -> Self {
+        self.salt = salt.to_string();
+        self
+    }
+
+    /// キャッシュキー
+    pub fn key(&self, request: &SynthesisRequest) -> String {
+        let source = format!(
+            "engine={}\nlang={}\nvoice={}\ntext={}\nssml={}\nprosody={}\neffects={}\nclauses={}\nsalt={}",
+            self.inner.name(),
+            request.lang,
+            request.voice,
+            request.text,
+            request.ssml.as_deref().unwrap_or_default(),
+            request.prosody.key(),
+            request.effects.key(),
+            request.clauses,
+            self.salt,
+        );
+        format!("{:016x}", fnv1a(source.as_bytes()))
+    }
+
+    /// 音声・再生時間・読み上げの時刻のファイル
+    fn paths(&self, key: &str) -> (String, String, String) {
+        let base = Path::new(&self.dir).join(key);
+        let path = |extension: &str| base.with_extension(extension).to_string_lossy().to_string();
+        (path("audio"), path("duration"), path("timepoints"))
+    }
+}
+
+/// FNV-1a (64bit)。実行環境によらず同じ値になるハッシュ
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[async_trait]
+impl SpeechEngine for CachedEngine {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn supports_ssml(&self) -> bool {
+        self.inner.supports_ssml()
+    }
+
+    fn supports_prosody(&self) -> bool {
+        self.inner.supports_prosody()
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        let key = self.key(request);
+        let (audio, duration, timepoints) = self.paths(&key);
+
+        if let Ok(nanos) = fs::read_to_string(&duration) {
+            if let (Ok(nanos), true) = (nanos.trim().parse::<u64>(), fs::exists(&audio)?) {
+                fs::copy(&audio, &request.output)?;
+                return Ok(Synthesized {
+                    path: request.output.clone(),
+                    duration: Duration::from_nanos(nanos),
+                    timepoints: timepoint::from_lines(
+                        &fs::read_to_string(&timepoints).unwrap_or_default(),
+                    ),
+                });
+            }
+        }
+
+        let synthesized = self.inner.synthesize(request).await?;
+        fs::create_dir_all(&self.dir)?;
+        fs::copy(&synthesized.path, &audio)?;
+        fs::write(&timepoints, timepoint::to_lines(&synthesized.timepoints))?;
+        fs::write(&duration, synthesized.duration.as_nanos().to_string())?;
+
+        Ok(synthesized)
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        self.inner.list_voices(lang).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::config::FakeConfig,
+        speech::{
+            effects::{Band, Effects},
+            fake::FakeEngine,
+            prosody::Prosody,
+        },
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// 呼び出し回数を数えるエンジン
+    struct Counting {
+        inner: FakeEngine,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl SpeechEngine for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.synthesize(request).await
+        }
+
+        async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+            self.inner.list_voices(lang).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_and_key() {
+        let dir = std::env::temp_dir().join("auto-mv-tts-cache-test");
+        let _ = fs::remove_dir_all(&dir);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let engine = CachedEngine::new(
+            Box::new(Counting {
+                inner: FakeEngine::new(FakeConfig::default()),
+                calls: calls.clone(),
+            }),
+            dir.to_str().unwrap(),
+        );
+
+        let output = std::env::temp_dir().join("auto-mv-tts-cache-test.wav");
+        let request =
+            SynthesisRequest::new("こんにちは、世界", output.to_str().unwrap(), "ja-JP", "A");
+
+        let first = engine.synthesize(&request).await.unwrap();
+        fs::remove_file(&output).unwrap();
+        let second = engine.synthesize(&request).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(second.timepoints.len(), 1);
+        assert!(output.exists());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 速度・高さ・音量が異なれば別のキーになる
+        let faster = SynthesisRequest {
+            prosody: Prosody {
+                rate: Some(1.2),
+                ..Prosody::default()
+            },
+            ..request.clone()
+        };
+        assert_ne!(engine.key(&request), engine.key(&faster));
+        engine.synthesize(&faster).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // 音響効果が異なれば別のキーになる
+        let radio = SynthesisRequest {
+            effects: Effects {
+                band: Some(Band::Radio),
+                ..Effects::default()
+            },
+            ..request.clone()
+        };
+        assert_ne!(engine.key(&request), engine.key(&radio));
+
+        // 区切りの時刻を求めるかどうかで返す時刻が変わるため、区切りの数もキーに含める
+        let split = request.clone().with_clauses(1);
+        assert_ne!(engine.key(&request), engine.key(&split));
+
+        // 加工の条件が異なれば別のキーになる
+        let key = engine.key(&request);
+        assert_ne!(key, engine.with_salt("trim=off").key(&request));
+    }
+}
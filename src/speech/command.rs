@@ -0,0 +1,354 @@
+use std::{collections::HashMap, fs, io, process::Stdio};
+
+use async_trait::async_trait;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use super::{voice::Gender, SpeechEngine, SynthesisRequest, Synthesized, VoiceInfo};
+use crate::{
+    ffmpeg::probe,
+    models::config::{CommandConfig, TextInput},
+};
+
+/// 任意のコマンドを呼び出す音声合成エンジン
+/// 引数のテンプレートの `{名前}` を依頼内容で置き換えて実行する
+///
+/// - `{text}`: 読み上げるテキスト（`text_input` が `argument` の場合）
+/// - `{textfile}`: テキストを書き出した一時ファイル（`text_input` が `file` の場合）
+/// - `{output}`: 出力する WAV ファイル
+/// - `{voice}` `{lang}`: 音声名と言語コード
+/// - `{rate}` `{pitch}` `{gain}`: 速度の倍率・ピッチ（半音）・音量（dB）
+/// - `vars` に定義した任意の値（辞書のパスなど）
+pub struct CommandEngine {
+    config: CommandConfig,
+}
+
+const BUILTIN: [&str; 8] = [
+    "text", "textfile", "output", "voice", "lang", "rate", "pitch", "gain",
+];
+
+impl CommandEngine {
+    /// テンプレートを検証してエンジンを作る
+    pub fn new(config: CommandConfig) -> Result<Self, io::Error> {
+        if config.argv.is_empty() {
+            return Err(invalid("speech.command.argv is empty".to_string()));
+        }
+        for name in config.argv.iter().flat_map(|arg| placeholders(arg)) {
+            if !BUILTIN.contains(&name) && !config.vars.contains_key(name) {
+                return Err(invalid(format!("unknown placeholder `{{{}}}`", name)));
+            }
+        }
+        let required = match config.text_input {
+            TextInput::Argument => Some("text"),
+            TextInput::File => Some("textfile"),
+            TextInput::Stdin => None,
+        };
+        if let Some(name) = required.filter(|name| !uses(&config, name)) {
+            return Err(invalid(format!(
+                "argv must contain `{{{}}}` for text_input {:?}",
+                name, config.text_input
+            )));
+        }
+        if !uses(&config, "output") {
+            return Err(invalid("argv must contain `{output}`".to_string()));
+        }
+        Ok(CommandEngine { config })
+    }
+
+    /// 依頼内容でテンプレートを展開する
+    fn argv(&self, request: &SynthesisRequest, textfile: &str) -> Vec<String> {
+        let mut values: HashMap<&str, String> = self
+            .config
+            .vars
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.clone()))
+            .collect();
+        values.insert("text", request.text.clone());
+        values.insert("textfile", textfile.to_string());
+        values.insert("output", request.output.clone());
+        values.insert("voice", request.voice.clone());
+        values.insert("lang", request.lang.clone());
+        values.insert("rate", format!("{}", request.prosody.rate()));
+        values.insert("pitch", format!("{}", request.prosody.pitch()));
+        values.insert("gain", format!("{}", request.prosody.gain()));
+
+        self.config
+            .argv
+            .iter()
+            .map(|arg| expand(arg, &values))
+            .collect()
+    }
+}
+
+/// `{名前}` を値に置き換える。置き換えた値の中の `{}` はそのまま残す
+fn expand(arg: &str, values: &HashMap<&str, String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = arg;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        expanded.push_str(&rest[..open]);
+        let name = &rest[open + 1..open + close];
+        match values.get(name) {
+            Some(value) => expanded.push_str(value),
+            None => expanded.push_str(&rest[open..open + close + 1]),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// 引数に含まれる `{名前}` の一覧
+fn placeholders(arg: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = arg;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        names.push(&rest[open + 1..open + close]);
+        rest = &rest[open + close + 1..];
+    }
+    names
+}
+
+fn uses(config: &CommandConfig, name: &str) -> bool {
+    config
+        .argv
+        .iter()
+        .any(|arg| placeholders(arg).contains(&name))
+}
+
+/// 破棄時にファイルを削除する
+struct RemoveOnDrop(String);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[async_trait]
+impl SpeechEngine for CommandEngine {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// 速度・高さ・音量をすべて引数で渡せる場合のみ対応とする
+    fn supports_prosody(&self) -> bool {
+        ["rate", "pitch", "gain"]
+            .iter()
+            .all(|name| uses(&self.config, name))
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        let textfile = format!("{}.txt", request.output);
+        // 失敗して途中で戻る場合もテキストファイルを残さない
+        let _textfile = match self.config.text_input {
+            TextInput::File => {
+                let guard = RemoveOnDrop(textfile.clone());
+                fs::write(&textfile, &request.text)?;
+                Some(guard)
+            }
+            _ => None,
+        };
+
+        let argv = self.argv(request, &textfile);
+        let mut command = Command::new(&argv[0]);
+        command.args(&argv[1..]);
+        if self.config.text_input == TextInput::Stdin {
+            command.stdin(Stdio::piped());
+        }
+        let mut child = command.spawn().map_err(|e| {
+            io::Error::new(e.kind(), format!("{}: failed to start: {}", argv[0], e))
+        })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            if let Err(e) = stdin.write_all(request.text.as_bytes()).await {
+                // 標準入力を読まずに終了したコマンドも回収してから失敗を返す
+                drop(stdin);
+                let status = child.wait().await?;
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!(
+                        "{}: failed to write text: {} (status: {})",
+                        argv[0], e, status
+                    ),
+                ));
+            }
+        }
+        let status = child.wait().await?;
+
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{}: failed to synthesize speech - status: {}",
+                argv[0], status
+            )));
+        }
+
+        Ok(Synthesized {
+            path: request.output.clone(),
+            duration: probe::duration(&request.output)?,
+            timepoints: Vec::new(),
+        })
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        if lang.is_some_and(|l| l != self.config.language) {
+            return Ok(vec![]);
+        }
+        let voices = self
+            .config
+            .voices
+            .iter()
+            .map(|name| VoiceInfo {
+                name: name.clone(),
+                language_codes: vec![self.config.language.clone()],
+                gender: Gender::Unspecified,
+                sample_rate: None,
+            })
+            .collect();
+        Ok(voices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::wav::write_wav;
+
+    fn config(argv: &[&str], text_input: TextInput) -> CommandConfig {
+        CommandConfig {
+            argv: argv.iter().map(|s| s.to_string()).collect(),
+            text_input,
+            vars: HashMap::from([("dict".to_string(), "/usr/share/dic".to_string())]),
+            ..CommandConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(CommandEngine::new(config(
+            &[
+                "open_jtalk",
+                "-x",
+                "{dict}",
+                "-m",
+                "{voice}",
+                "-ow",
+                "{output}",
+                "{textfile}"
+            ],
+            TextInput::File
+        ))
+        .is_ok());
+        // 未定義の名前
+        assert!(
+            CommandEngine::new(config(&["tts", "{model}", "{output}"], TextInput::Stdin)).is_err()
+        );
+        // テキストの渡し方に必要な名前がない
+        assert!(CommandEngine::new(config(&["tts", "{output}"], TextInput::Argument)).is_err());
+        assert!(CommandEngine::new(config(&["tts", "{text}"], TextInput::Argument)).is_err());
+        assert!(CommandEngine::new(config(&[], TextInput::Stdin)).is_err());
+    }
+
+    #[test]
+    fn test_argv() {
+        let engine = CommandEngine::new(config(
+            &[
+                "espeak-ng",
+                "-v",
+                "{voice}",
+                "-s{rate}",
+                "-w",
+                "{output}",
+                "{text}",
+            ],
+            TextInput::Argument,
+        ))
+        .unwrap();
+        assert!(!engine.supports_prosody());
+
+        let request = SynthesisRequest::new("hello", "out.wav", "en-US", "en-us");
+        assert_eq!(
+            engine.argv(&request, "out.wav.txt"),
+            vec!["espeak-ng", "-v", "en-us", "-s1", "-w", "out.wav", "hello"]
+        );
+
+        // テキスト中の `{}` は置き換えない
+        let request = SynthesisRequest::new("{output} {x}", "out.wav", "en-US", "en-us");
+        assert_eq!(engine.argv(&request, "out.wav.txt")[6], "{output} {x}");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_synthesize() {
+        let dir = std::env::temp_dir();
+        let source = dir.join("auto-mv-command-source.wav");
+        let source = source.to_str().unwrap();
+        write_wav(source, 8000, 1, &[0; 4000]).unwrap();
+        let received = dir.join("auto-mv-command-received.txt");
+        let received = received.to_str().unwrap();
+
+        // テキストを受け取った内容として保存し、用意した WAV を出力にコピーする
+        let cases = [
+            (
+                TextInput::Argument,
+                "printf %s \"$1\" > {received}; cp {source} \"$2\"",
+            ),
+            (TextInput::Stdin, "cat > {received}; cp {source} \"$2\""),
+            (TextInput::File, "cp \"$1\" {received}; cp {source} \"$2\""),
+        ];
+        for (text_input, script) in cases {
+            let text = match text_input {
+                TextInput::Argument => "{text}",
+                TextInput::Stdin => "-",
+                TextInput::File => "{textfile}",
+            };
+            let mut config = config(&["sh", "-c", script, "sh", text, "{output}"], text_input);
+            config
+                .vars
+                .insert("received".to_string(), received.to_string());
+            config.vars.insert("source".to_string(), source.to_string());
+            let engine = CommandEngine::new(config).unwrap();
+
+            let output = dir.join("auto-mv-command.wav");
+            let request =
+                SynthesisRequest::new("こんにちは", output.to_str().unwrap(), "ja-JP", "mei");
+            let synthesized = engine.synthesize(&request).await.unwrap();
+            assert_eq!(synthesized.duration, std::time::Duration::from_millis(500));
+            assert_eq!(fs::read_to_string(received).unwrap(), "こんにちは");
+        }
+
+        // 失敗したコマンドはエラーになる
+        let engine = CommandEngine::new(config(
+            &["sh", "-c", "exit 1", "{output}"],
+            TextInput::Stdin,
+        ))
+        .unwrap();
+        let request = SynthesisRequest::new("a", "unused.wav", "ja-JP", "mei");
+        assert!(engine.synthesize(&request).await.is_err());
+
+        // 標準入力を読まずに終了しても、書き込みの失敗を返す
+        let text = "あ".repeat(1 << 20);
+        let request = SynthesisRequest::new(&text, "unused.wav", "ja-JP", "mei");
+        assert!(engine.synthesize(&request).await.is_err());
+
+        // 起動・合成に失敗してもテキストファイルを残さない
+        let output = dir.join("auto-mv-command-failed.wav");
+        let output = output.to_str().unwrap();
+        let request = SynthesisRequest::new("a", output, "ja-JP", "mei");
+        for argv in [
+            ["sh", "-c", "exit 1", "{textfile}", "{output}"],
+            ["auto-mv-missing-command", "{textfile}", "{output}", "", ""],
+        ] {
+            let engine = CommandEngine::new(config(&argv, TextInput::File)).unwrap();
+            assert!(engine.synthesize(&request).await.is_err());
+            assert!(!std::path::Path::new(&format!("{}.txt", output)).exists());
+        }
+    }
+}
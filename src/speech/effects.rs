@@ -0,0 +1,182 @@
+use std::{fmt::Write, fs, io};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{SpeechEngine, SynthesisRequest, Synthesized, VoiceInfo};
+use crate::ffmpeg::{self, probe};
+
+/// 話者ごとの音響効果（合成後に ffmpeg のオーディオフィルタでかける）
+/// 長さを変えないため、読み上げの時刻はそのまま使える
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Effects {
+    /// 高さの変更（半音）。速度は変えない
+    pub pitch_shift: Option<f64>,
+    /// 電話・無線のような帯域の制限
+    pub band: Option<Band>,
+    /// イコライザ
+    pub eq: Vec<EqBand>,
+    /// 残響
+    pub reverb: Option<Reverb>,
+    /// 左右の定位（-1.0 が左、0.0 が中央、1.0 が右）。指定するとステレオになる
+    pub pan: Option<f64>,
+}
+
+/// 帯域の制限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Band {
+    /// 300Hz〜3.4kHz
+    Telephone,
+    /// 500Hz〜4.5kHz に圧縮をかけた無線の音
+    Radio,
+}
+
+/// イコライザの1バンド（ピーキング）
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct EqBand {
+    /// 中心周波数 (Hz)
+    pub frequency: f64,
+    /// 増減 (dB)
+    pub gain_db: f64,
+    /// Q 値
+    #[serde(default = "default_q")]
+    pub q: f64,
+}
+
+fn default_q() -> f64 {
+    1.0
+}
+
+/// 残響（`room_ms` 間隔の3つの反射を `decay` ずつ減衰させる）
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Reverb {
+    pub room_ms: u32,
+    /// 反射ごとの減衰（0.0〜1.0）
+    pub decay: f64,
+}
+
+impl Default for Reverb {
+    fn default() -> Self {
+        Reverb {
+            room_ms: 60,
+            decay: 0.4,
+        }
+    }
+}
+
+impl Effects {
+    /// 効果が何も指定されていないかどうか
+    pub fn is_empty(&self) -> bool {
+        *self == Effects::default()
+    }
+
+    /// キャッシュキー用の文字列表現
+    pub fn key(&self) -> String {
+        if self.is_empty() {
+            return "none".to_string();
+        }
+        let mut key = String::new();
+        if let Some(pitch) = self.pitch_shift {
+            let _ = write!(key, "pitch={:.3},", pitch);
+        }
+        if let Some(band) = self.band {
+            let _ = write!(key, "band={:?},", band);
+        }
+        for eq in &self.eq {
+            let _ = write!(key, "eq={:.1}/{:.2}/{:.2},", eq.frequency, eq.gain_db, eq.q);
+        }
+        if let Some(reverb) = self.reverb {
+            let _ = write!(key, "reverb={}/{:.3},", reverb.room_ms, reverb.decay);
+        }
+        if let Some(pan) = self.pan {
+            let _ = write!(key, "pan={:.3},", pan);
+        }
+        key
+    }
+}
+
+/// 合成した音声に話者ごとの音響効果をかけるエンジン
+/// キャッシュの内側に置き、効果をかけた音声をキャッシュする
+pub struct EffectsEngine {
+    inner: Box<dyn SpeechEngine>,
+}
+
+impl EffectsEngine {
+    pub fn new(inner: Box<dyn SpeechEngine>) -> Self {
+        EffectsEngine { inner }
+    }
+}
+
+#[async_trait]
+impl SpeechEngine for EffectsEngine {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn supports_ssml(&self) -> bool {
+        self.inner.supports_ssml()
+    }
+
+    fn supports_prosody(&self) -> bool {
+        self.inner.supports_prosody()
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        if request.effects.is_empty() {
+            return self.inner.synthesize(request).await;
+        }
+
+        // 一時ファイルに合成してから加工する
+        let dry = SynthesisRequest {
+            output: format!("{}.dry.wav", request.output),
+            ..request.clone()
+        };
+        let synthesized = self.inner.synthesize(&dry).await?;
+        let sample_rate = probe::audio_info(&synthesized.path)?.sample_rate;
+
+        ffmpeg::command::apply_effects(
+            &synthesized.path,
+            &request.output,
+            sample_rate,
+            &request.effects,
+        )?;
+        fs::remove_file(&synthesized.path)?;
+
+        Ok(Synthesized {
+            path: request.output.clone(),
+            duration: probe::duration(&request.output)?,
+            timepoints: synthesized.timepoints,
+        })
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        self.inner.list_voices(lang).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_key() {
+        let effects: Effects = serde_json::from_str(
+            r#"{"band": "radio", "eq": [{"frequency": 3000, "gain_db": 4.5}], "reverb": {}, "pan": -0.5}"#,
+        )
+        .unwrap();
+        assert_eq!(effects.band, Some(Band::Radio));
+        assert_eq!(effects.eq[0].q, 1.0);
+        assert_eq!(effects.reverb, Some(Reverb::default()));
+        assert!(!effects.is_empty());
+        assert_eq!(
+            effects.key(),
+            "band=Radio,eq=3000.0/4.50/1.00,reverb=60/0.400,pan=-0.500,"
+        );
+
+        assert!(Effects::default().is_empty());
+        assert_eq!(Effects::default().key(), "none");
+    }
+}
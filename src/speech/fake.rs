@@ -0,0 +1,155 @@
+use std::{f64::consts::PI, io, time::Duration};
+
+use async_trait::async_trait;
+
+use super::{
+    timepoint::{self, Timepoint},
+    voice::Gender,
+    SpeechEngine, SynthesisRequest, Synthesized, VoiceInfo,
+};
+use crate::{
+    audio::wav::write_wav,
+    models::config::{FakeConfig, FakeSignal},
+};
+
+/// テスト用の決定的な音声合成エンジン
+/// テキストの文字数から長さを決め、サイン波または無音の WAV を書き出す
+pub struct FakeEngine {
+    config: FakeConfig,
+}
+
+impl FakeEngine {
+    pub fn new(config: FakeConfig) -> Self {
+        FakeEngine { config }
+    }
+
+    /// テキストに対して生成される音声の長さ
+    pub fn duration_for(&self, text: &str) -> Duration {
+        let chars = text.chars().filter(|c| !c.is_whitespace()).count();
+        let secs = (chars as f64 * self.config.seconds_per_char).max(self.config.min_seconds);
+        Duration::from_secs_f64(secs)
+    }
+
+    /// 句読点の区切りの時刻。長さを文字数で按分する
+    pub fn timepoints_for(&self, text: &str, duration: Duration) -> Vec<Timepoint> {
+        let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let spoken = |end: usize| {
+            text.chars()
+                .take(end)
+                .filter(|c| !c.is_whitespace())
+                .count()
+        };
+        timepoint::clause_ends(text)
+            .into_iter()
+            .filter(|end| *end < text.chars().count())
+            .enumerate()
+            .map(|(i, end)| {
+                let ratio = spoken(end) as f64 / chars.len() as f64;
+                Timepoint::new(&timepoint::clause_mark(i + 1), duration.mul_f64(ratio))
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl SpeechEngine for FakeEngine {
+    fn name(&self) -> &str {
+        "fake"
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        let duration = self.duration_for(&request.text);
+        let sample_rate = self.config.sample_rate;
+        let frames = (duration.as_secs_f64() * sample_rate as f64).round() as usize;
+
+        let samples: Vec<i16> = match self.config.signal {
+            FakeSignal::Silence => vec![0; frames],
+            FakeSignal::Tone => (0..frames)
+                .map(|n| {
+                    let t = n as f64 / sample_rate as f64;
+                    let v = (2.0 * PI * self.config.frequency * t).sin() * 0.5;
+                    (v * i16::MAX as f64) as i16
+                })
+                .collect(),
+        };
+        write_wav(&request.output, sample_rate, 1, &samples)?;
+
+        let duration = Duration::from_secs_f64(frames as f64 / sample_rate as f64);
+        Ok(Synthesized {
+            path: request.output.clone(),
+            duration,
+            timepoints: self.timepoints_for(&request.text, duration),
+        })
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        let lang = lang.unwrap_or("ja-JP");
+        Ok(vec![VoiceInfo {
+            name: format!("{}-Fake-A", lang),
+            language_codes: vec![lang.to_string()],
+            gender: Gender::Neutral,
+            sample_rate: Some(self.config.sample_rate),
+        }])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::wav;
+
+    #[tokio::test]
+    async fn test_synthesize_is_deterministic() {
+        let engine = FakeEngine::new(FakeConfig::default());
+        let output = std::env::temp_dir().join("auto-mv-fake-engine.wav");
+        let output = output.to_str().unwrap();
+        let request = SynthesisRequest::new("おはよう世界！", output, "ja-JP", "ja-JP-Fake-A");
+
+        let first = engine.synthesize(&request).await.unwrap();
+        let second = engine.synthesize(&request).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.duration, engine.duration_for("おはよう世界！"));
+
+        // 書き出したヘッダから再計算した長さと一致する
+        assert_eq!(wav::duration(output).unwrap(), first.duration);
+    }
+
+    #[tokio::test]
+    async fn test_silence() {
+        let config = FakeConfig {
+            signal: FakeSignal::Silence,
+            ..FakeConfig::default()
+        };
+        let engine = FakeEngine::new(config);
+        let output = std::env::temp_dir().join("auto-mv-fake-silence.wav");
+        let output = output.to_str().unwrap();
+        let request = SynthesisRequest::new("a", output, "ja-JP", "ja-JP-Fake-A");
+
+        engine.synthesize(&request).await.unwrap();
+        let bytes = std::fs::read(output).unwrap();
+        assert!(bytes[44..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_duration_for() {
+        let engine = FakeEngine::new(FakeConfig::default());
+        assert_eq!(engine.duration_for(""), Duration::from_secs_f64(0.5));
+        assert_eq!(
+            engine.duration_for("あいうえおかきくけこ"),
+            Duration::from_secs_f64(1.5)
+        );
+    }
+
+    #[test]
+    fn test_timepoints_for() {
+        let engine = FakeEngine::new(FakeConfig::default());
+        let timepoints = engine.timepoints_for("はい、そう。です。", Duration::from_secs(9));
+        assert_eq!(
+            timepoints,
+            vec![
+                Timepoint::new("clause-1", Duration::from_secs(3)),
+                Timepoint::new("clause-2", Duration::from_secs(6)),
+            ]
+        );
+    }
+}
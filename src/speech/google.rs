@@ -0,0 +1,747 @@
+use std::{
+    env, fs,
+    future::Future,
+    io,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use gauth::serv_account::ServiceAccount;
+use log::warn;
+use prost::Message;
+use tokio::{process::Command, sync::Mutex};
+use tonic::{
+    metadata::MetadataValue,
+    transport::{Channel, ClientTlsConfig},
+    Request,
+};
+
+use super::{
+    prosody::Prosody, timepoint::Timepoint, voice::Gender, SpeechEngine, SynthesisRequest,
+    Synthesized, VoiceInfo,
+};
+use crate::{
+    ffmpeg::probe,
+    models::config::{AudioEncoding, GoogleAuth, GoogleConfig},
+};
+
+/// google.cloud.texttospeech.v1 の生成コード
+pub mod proto {
+    tonic::include_proto!("google.cloud.texttospeech.v1");
+}
+
+/// google.cloud.texttospeech.v1beta1 の生成コード
+/// SSML の `<mark>` の時刻（`enable_time_pointing`）は v1beta1 にしかないため、mark を含むリクエストだけに使う
+pub mod proto_beta {
+    tonic::include_proto!("google.cloud.texttospeech.v1beta1");
+}
+
+use proto::{
+    synthesis_input::InputSource, text_to_speech_client::TextToSpeechClient, AudioConfig,
+    ListVoicesRequest, SynthesisInput, SynthesizeSpeechRequest, VoiceSelectionParams,
+};
+
+const SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// API が受け付ける速度・高さ（半音）・音量（dB）の範囲
+const SPEAKING_RATE_RANGE: (f64, f64) = (0.25, 4.0);
+const PITCH_RANGE: (f64, f64) = (-20.0, 20.0);
+const VOLUME_GAIN_DB_RANGE: (f64, f64) = (-96.0, 16.0);
+
+/// gcloud のアクセストークンを使い回す時間（トークン自体の有効期限は 1 時間）
+const GCLOUD_TOKEN_LIFETIME: Duration = Duration::from_secs(50 * 60);
+
+/// アクセストークンの取得方法
+enum Authenticator {
+    None,
+    ServiceAccount(ServiceAccount),
+    /// 取得したトークンと取得した時刻
+    Gcloud(Option<(String, Instant)>),
+}
+
+impl Authenticator {
+    fn from_config(config: &GoogleConfig) -> Result<Self, io::Error> {
+        match config.auth {
+            GoogleAuth::None => Ok(Authenticator::None),
+            GoogleAuth::ServiceAccount => {
+                let path = config.credentials.as_deref().ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "google.credentials is required for service_account auth",
+                    )
+                })?;
+                Ok(Authenticator::ServiceAccount(ServiceAccount::from_file(
+                    path,
+                    vec![SCOPE],
+                )))
+            }
+            GoogleAuth::Adc => match env::var("GOOGLE_APPLICATION_CREDENTIALS") {
+                Ok(path) => Ok(Authenticator::ServiceAccount(ServiceAccount::from_file(
+                    &path,
+                    vec![SCOPE],
+                ))),
+                Err(_) => Ok(Authenticator::Gcloud(None)),
+            },
+        }
+    }
+
+    /// `authorization` ヘッダに設定する値を返す
+    async fn bearer(&mut self) -> Result<Option<String>, io::Error> {
+        match self {
+            Authenticator::None => Ok(None),
+            Authenticator::ServiceAccount(sa) => {
+                // gauth は "Bearer xxx" 形式で返す
+                let token = sa.access_token().await.map_err(io::Error::other)?;
+                Ok(Some(token))
+            }
+            Authenticator::Gcloud(cached) => {
+                if let Some((token, fetched)) = cached {
+                    if fetched.elapsed() < GCLOUD_TOKEN_LIFETIME {
+                        return Ok(Some(token.clone()));
+                    }
+                }
+                let output = Command::new("gcloud")
+                    .args(["auth", "application-default", "print-access-token"])
+                    .output()
+                    .await?;
+                if !output.status.success() {
+                    return Err(io::Error::other(format!(
+                        "Failed to get access token from gcloud: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+                let token = format!("Bearer {}", String::from_utf8_lossy(&output.stdout).trim());
+                *cached = Some((token.clone(), Instant::now()));
+                Ok(Some(token))
+            }
+        }
+    }
+
+    /// 使い回しているトークンを捨てる。捨てたら true
+    /// （サービスアカウントのトークンは gauth が期限を見て取り直す）
+    fn invalidate(&mut self) -> bool {
+        match self {
+            Authenticator::Gcloud(cached) => cached.take().is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// Google Cloud Text-to-Speech の gRPC クライアントによる音声合成エンジン
+pub struct GoogleEngine {
+    client: TextToSpeechClient<Channel>,
+    beta: proto_beta::text_to_speech_client::TextToSpeechClient<Channel>,
+    auth: Mutex<Authenticator>,
+    config: GoogleConfig,
+}
+
+impl GoogleEngine {
+    /// 接続は最初のリクエスト時に行う
+    pub fn new(config: GoogleConfig) -> Result<Self, io::Error> {
+        let mut endpoint = Channel::from_shared(config.endpoint.clone())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if config.endpoint.starts_with("https://") {
+            endpoint = endpoint
+                .tls_config(ClientTlsConfig::new().with_native_roots())
+                .map_err(io::Error::other)?;
+        }
+
+        let channel = endpoint.connect_lazy();
+        Ok(GoogleEngine {
+            client: TextToSpeechClient::new(channel.clone()),
+            beta: proto_beta::text_to_speech_client::TextToSpeechClient::new(channel),
+            auth: Mutex::new(Authenticator::from_config(&config)?),
+            config,
+        })
+    }
+
+    /// 認証ヘッダを付けたリクエストを作る
+    async fn request<T>(&self, message: T) -> Result<Request<T>, io::Error> {
+        let mut request = Request::new(message);
+        if let Some(bearer) = self.auth.lock().await.bearer().await? {
+            let value = MetadataValue::try_from(bearer.as_str())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            request.metadata_mut().insert("authorization", value);
+        }
+        Ok(request)
+    }
+
+    /// 認証ヘッダを付けて送る
+    /// 使い回した gcloud のトークンが失効していた（Unauthenticated）場合は、取り直して 1 回だけ送り直す
+    async fn call<T, R, F, Fut>(&self, message: T, send: F) -> Result<R, io::Error>
+    where
+        T: Clone,
+        F: Fn(Request<T>) -> Fut,
+        Fut: Future<Output = Result<tonic::Response<R>, tonic::Status>>,
+    {
+        let response = match send(self.request(message.clone()).await?).await {
+            Err(status)
+                if status.code() == tonic::Code::Unauthenticated
+                    && self.auth.lock().await.invalidate() =>
+            {
+                send(self.request(message).await?).await
+            }
+            response => response,
+        };
+        response.map(|r| r.into_inner()).map_err(status_to_io)
+    }
+
+    /// 設定値に行ごとの速度・高さ・音量を重ねた音声設定
+    /// 重ねた値が API の範囲を超える場合は、InvalidArgument で失敗しないよう範囲に収める
+    fn audio_config(&self, prosody: &Prosody) -> AudioConfig {
+        let encoding = match self.config.audio_encoding {
+            AudioEncoding::Linear16 => proto::AudioEncoding::Linear16,
+            AudioEncoding::Mp3 => proto::AudioEncoding::Mp3,
+            AudioEncoding::OggOpus => proto::AudioEncoding::OggOpus,
+            AudioEncoding::Mulaw => proto::AudioEncoding::Mulaw,
+            AudioEncoding::Alaw => proto::AudioEncoding::Alaw,
+        };
+        AudioConfig {
+            audio_encoding: encoding as i32,
+            // 0.0 はエンジンの既定値
+            speaking_rate: match self.config.speaking_rate * prosody.rate() {
+                0.0 => 0.0,
+                rate => clamp("speaking_rate", rate, SPEAKING_RATE_RANGE),
+            },
+            pitch: clamp("pitch", self.config.pitch + prosody.pitch(), PITCH_RANGE),
+            volume_gain_db: clamp(
+                "volume_gain_db",
+                self.config.volume_gain_db + prosody.gain(),
+                VOLUME_GAIN_DB_RANGE,
+            ),
+            sample_rate_hertz: self.config.sample_rate_hertz.unwrap_or(0),
+            effects_profile_id: vec![],
+        }
+    }
+}
+
+/// 値を範囲に収める。収まらなければ警告する
+fn clamp(name: &str, value: f64, (min, max): (f64, f64)) -> f64 {
+    let clamped = value.clamp(min, max);
+    if clamped != value {
+        warn!(
+            "google: {} {} is outside [{}, {}]; using {}",
+            name, value, min, max, clamped
+        );
+    }
+    clamped
+}
+
+/// 同じ形のメッセージを別の版の型に変換する
+/// v1beta1 は v1 に項目を足したもので、共通の項目のフィールド番号は同じ
+fn convert<T: Message, U: Message + Default>(message: &T) -> Result<U, io::Error> {
+    U::decode(message.encode_to_vec().as_slice())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// gRPC のステータスを io::Error に変換する
+/// 入力・認証・権限の誤りは再試行しても変わらないため、RetryEngine が再試行しない種類にする
+fn status_to_io(status: tonic::Status) -> io::Error {
+    use tonic::Code;
+    let kind = match status.code() {
+        Code::InvalidArgument | Code::OutOfRange | Code::FailedPrecondition => {
+            io::ErrorKind::InvalidInput
+        }
+        Code::NotFound => io::ErrorKind::NotFound,
+        Code::AlreadyExists => io::ErrorKind::AlreadyExists,
+        Code::PermissionDenied | Code::Unauthenticated => io::ErrorKind::PermissionDenied,
+        Code::Unimplemented => io::ErrorKind::Unsupported,
+        Code::DeadlineExceeded => io::ErrorKind::TimedOut,
+        _ => io::ErrorKind::Other,
+    };
+    io::Error::new(
+        kind,
+        format!(
+            "Text-to-Speech API error: {:?}: {}",
+            status.code(),
+            status.message()
+        ),
+    )
+}
+
+#[async_trait]
+impl SpeechEngine for GoogleEngine {
+    fn name(&self) -> &str {
+        "google"
+    }
+
+    fn supports_ssml(&self) -> bool {
+        true
+    }
+
+    fn supports_prosody(&self) -> bool {
+        true
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        let input_source = match &request.ssml {
+            Some(ssml) => InputSource::Ssml(ssml.clone()),
+            None => InputSource::Text(request.text.clone()),
+        };
+        let message = SynthesizeSpeechRequest {
+            input: Some(SynthesisInput {
+                input_source: Some(input_source),
+            }),
+            voice: Some(VoiceSelectionParams {
+                language_code: request.lang.clone(),
+                name: request.voice.clone(),
+                ssml_gender: proto::SsmlVoiceGender::Unspecified as i32,
+            }),
+            audio_config: Some(self.audio_config(&request.prosody)),
+        };
+
+        // SSML の <mark> を含むリクエストだけ v1beta1 で送り、読み上げられた時刻を受け取る
+        let has_marks = request.ssml.as_ref().is_some_and(|s| s.contains("<mark "));
+        let (audio_content, timepoints) = if has_marks {
+            let mut message: proto_beta::SynthesizeSpeechRequest = convert(&message)?;
+            message.enable_time_pointing = vec![proto_beta::TimepointType::SsmlMark as i32];
+            let response = self
+                .call(message, |request| {
+                    let mut client = self.beta.clone();
+                    async move { client.synthesize_speech(request).await }
+                })
+                .await?;
+            // 負の値や NaN などの時刻は使えないので捨てる
+            let timepoints = response
+                .timepoints
+                .iter()
+                .filter_map(|t| match Duration::try_from_secs_f64(t.time_seconds) {
+                    Ok(time) => Some(Timepoint::new(&t.mark_name, time)),
+                    Err(_) => {
+                        warn!(
+                            "google: ignoring invalid timepoint `{}` at {} s",
+                            t.mark_name, t.time_seconds
+                        );
+                        None
+                    }
+                })
+                .collect();
+            (response.audio_content, timepoints)
+        } else {
+            let response = self
+                .call(message, |request| {
+                    let mut client = self.client.clone();
+                    async move { client.synthesize_speech(request).await }
+                })
+                .await?;
+            (response.audio_content, Vec::new())
+        };
+
+        fs::write(&request.output, &audio_content)?;
+        // MP3・OGG_OPUS も ffprobe で長さを調べる
+        let duration = probe::duration(&request.output)?;
+
+        Ok(Synthesized {
+            path: request.output.clone(),
+            duration,
+            timepoints,
+        })
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        let message = ListVoicesRequest {
+            language_code: lang.unwrap_or_default().to_string(),
+        };
+
+        let response = self
+            .call(message, |request| {
+                let mut client = self.client.clone();
+                async move { client.list_voices(request).await }
+            })
+            .await?;
+
+        Ok(response
+            .voices
+            .into_iter()
+            .map(|v| VoiceInfo {
+                gender: match v.ssml_gender() {
+                    proto::SsmlVoiceGender::Female => Gender::Female,
+                    proto::SsmlVoiceGender::Male => Gender::Male,
+                    proto::SsmlVoiceGender::Neutral => Gender::Neutral,
+                    proto::SsmlVoiceGender::Unspecified => Gender::Unspecified,
+                },
+                sample_rate: u32::try_from(v.natural_sample_rate_hertz)
+                    .ok()
+                    .filter(|r| *r > 0),
+                name: v.name,
+                language_codes: v.language_codes,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        audio::wav::write_wav,
+        models::config::RetryConfig,
+        speech::{retry::RetryEngine, voice::VoiceCatalog},
+    };
+    use proto::{text_to_speech_server::TextToSpeech, ListVoicesResponse, Voice};
+    use proto_beta::{synthesis_input::InputSource as BetaInputSource, TimepointType};
+    use std::sync::{Arc, Mutex as StdMutex};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tonic::{
+        transport::{server::TcpIncoming, Server},
+        Response, Status,
+    };
+
+    /// 受け取ったリクエストを記録し、固定の WAV を返すモックサーバ（v1 と v1beta1）
+    /// 時刻を求められた場合は、SSML の mark ごとに 0.25 秒ずつ進めた時刻を返す
+    #[derive(Default, Clone)]
+    struct MockTts {
+        /// 使われた版と、v1beta1 の型にそろえたリクエスト
+        requests: Arc<StdMutex<Vec<(&'static str, proto_beta::SynthesizeSpeechRequest)>>>,
+        authorization: Arc<StdMutex<Option<String>>>,
+    }
+
+    impl MockTts {
+        fn synthesize(
+            &self,
+            version: &'static str,
+            request: tonic::Request<proto_beta::SynthesizeSpeechRequest>,
+        ) -> Result<proto_beta::SynthesizeSpeechResponse, Box<Status>> {
+            *self.authorization.lock().unwrap() = request
+                .metadata()
+                .get("authorization")
+                .map(|v| v.to_str().unwrap().to_string());
+
+            let request = request.into_inner();
+            if request.voice.as_ref().unwrap().name.is_empty() {
+                return Err(Box::new(Status::invalid_argument("voice name is required")));
+            }
+            let timepoints = match &request.input.as_ref().unwrap().input_source {
+                Some(BetaInputSource::Ssml(ssml))
+                    if request
+                        .enable_time_pointing
+                        .contains(&(TimepointType::SsmlMark as i32)) =>
+                {
+                    ssml.split("<mark name=\"")
+                        .skip(1)
+                        .enumerate()
+                        .map(|(i, rest)| proto_beta::Timepoint {
+                            mark_name: rest.split('"').next().unwrap().to_string(),
+                            time_seconds: (i + 1) as f64 * 0.25,
+                        })
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            self.requests.lock().unwrap().push((version, request));
+
+            // 1秒分の無音
+            let path = env::temp_dir().join("auto-mv-google-mock.wav");
+            write_wav(path.to_str().unwrap(), 24000, 1, &vec![0; 24000]).unwrap();
+            let audio_content = fs::read(path).unwrap();
+            Ok(proto_beta::SynthesizeSpeechResponse {
+                audio_content,
+                timepoints,
+                audio_config: None,
+            })
+        }
+    }
+
+    #[tonic::async_trait]
+    impl TextToSpeech for MockTts {
+        async fn list_voices(
+            &self,
+            request: tonic::Request<ListVoicesRequest>,
+        ) -> Result<Response<ListVoicesResponse>, Status> {
+            let lang = request.into_inner().language_code;
+            let voices = [("ja-JP", "ja-JP-Neural2-B"), ("en-US", "en-US-Neural2-A")]
+                .into_iter()
+                .filter(|(code, _)| lang.is_empty() || *code == lang)
+                .map(|(code, name)| Voice {
+                    language_codes: vec![code.to_string()],
+                    name: name.to_string(),
+                    ssml_gender: proto::SsmlVoiceGender::Female as i32,
+                    natural_sample_rate_hertz: 24000,
+                })
+                .collect();
+            Ok(Response::new(ListVoicesResponse { voices }))
+        }
+
+        async fn synthesize_speech(
+            &self,
+            request: tonic::Request<SynthesizeSpeechRequest>,
+        ) -> Result<Response<proto::SynthesizeSpeechResponse>, Status> {
+            let (metadata, extensions, message) = request.into_parts();
+            let message = convert(&message).unwrap();
+            let request = tonic::Request::from_parts(metadata, extensions, message);
+            let response = self.synthesize("v1", request).map_err(|e| *e)?;
+            Ok(Response::new(convert(&response).unwrap()))
+        }
+    }
+
+    #[tonic::async_trait]
+    impl proto_beta::text_to_speech_server::TextToSpeech for MockTts {
+        async fn list_voices(
+            &self,
+            _request: tonic::Request<proto_beta::ListVoicesRequest>,
+        ) -> Result<Response<proto_beta::ListVoicesResponse>, Status> {
+            Err(Status::unimplemented("list_voices is served by v1"))
+        }
+
+        async fn synthesize_speech(
+            &self,
+            request: tonic::Request<proto_beta::SynthesizeSpeechRequest>,
+        ) -> Result<Response<proto_beta::SynthesizeSpeechResponse>, Status> {
+            self.synthesize("v1beta1", request)
+                .map(Response::new)
+                .map_err(|e| *e)
+        }
+    }
+
+    async fn serve(mock: MockTts) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(proto::text_to_speech_server::TextToSpeechServer::new(
+                    mock.clone(),
+                ))
+                .add_service(proto_beta::text_to_speech_server::TextToSpeechServer::new(
+                    mock,
+                ))
+                .serve_with_incoming(incoming),
+        );
+        format!("http://{}", addr)
+    }
+
+    fn config(endpoint: String) -> GoogleConfig {
+        GoogleConfig {
+            endpoint,
+            auth: GoogleAuth::None,
+            speaking_rate: 1.5,
+            pitch: -2.0,
+            ..GoogleConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_synthesize() {
+        let mock = MockTts::default();
+        let engine = GoogleEngine::new(config(serve(mock.clone()).await)).unwrap();
+
+        let output = env::temp_dir().join("auto-mv-google-engine.wav");
+        let output = output.to_str().unwrap();
+        let request = SynthesisRequest::new("こんにちは", output, "ja-JP", "ja-JP-Neural2-B");
+        let synthesized = engine.synthesize(&request).await.unwrap();
+
+        assert_eq!(synthesized.path, output);
+        assert_eq!(synthesized.duration, Duration::from_secs(1));
+        assert_eq!(*mock.authorization.lock().unwrap(), None);
+
+        // mark のないリクエストは v1 で送る
+        let requests = mock.requests.lock().unwrap();
+        let (version, sent) = &requests[0];
+        assert_eq!(*version, "v1");
+        assert_eq!(
+            sent.input.as_ref().unwrap().input_source,
+            Some(BetaInputSource::Text("こんにちは".to_string()))
+        );
+        let voice = sent.voice.as_ref().unwrap();
+        assert_eq!(voice.language_code, "ja-JP");
+        assert_eq!(voice.name, "ja-JP-Neural2-B");
+        let audio = sent.audio_config.as_ref().unwrap();
+        assert_eq!(audio.audio_encoding, proto::AudioEncoding::Linear16 as i32);
+        assert_eq!(audio.speaking_rate, 1.5);
+        assert_eq!(audio.pitch, -2.0);
+    }
+
+    #[tokio::test]
+    async fn test_synthesize_prosody() {
+        let mock = MockTts::default();
+        let engine = GoogleEngine::new(config(serve(mock.clone()).await)).unwrap();
+        assert!(engine.supports_prosody());
+
+        let output = env::temp_dir().join("auto-mv-google-engine-prosody.wav");
+        let mut request = SynthesisRequest::new(
+            "こんにちは",
+            output.to_str().unwrap(),
+            "ja-JP",
+            "ja-JP-Neural2-B",
+        );
+        request.prosody = Prosody {
+            rate: Some(0.8),
+            pitch: Some(3.0),
+            gain: Some(-6.0),
+        };
+        engine.synthesize(&request).await.unwrap();
+
+        // 設定値に行ごとの値が重なる
+        let requests = mock.requests.lock().unwrap();
+        let audio = requests[0].1.audio_config.as_ref().unwrap();
+        assert!((audio.speaking_rate - 1.2).abs() < 1e-9);
+        assert_eq!(audio.pitch, 1.0);
+        assert_eq!(audio.volume_gain_db, -6.0);
+
+        // 重ねた値が API の範囲を超えれば範囲に収める
+        let engine = GoogleEngine::new(GoogleConfig {
+            speaking_rate: 2.5,
+            pitch: -15.0,
+            volume_gain_db: 10.0,
+            ..config("http://127.0.0.1:1".to_string())
+        })
+        .unwrap();
+        let audio = engine.audio_config(&Prosody {
+            rate: Some(2.0),
+            pitch: Some(-10.0),
+            gain: Some(10.0),
+        });
+        assert_eq!(audio.speaking_rate, 4.0);
+        assert_eq!(audio.pitch, -20.0);
+        assert_eq!(audio.volume_gain_db, 16.0);
+        let audio = engine.audio_config(&Prosody {
+            rate: Some(0.05),
+            ..Prosody::default()
+        });
+        assert_eq!(audio.speaking_rate, 0.25);
+    }
+
+    #[tokio::test]
+    async fn test_synthesize_ssml() {
+        let mock = MockTts::default();
+        let engine = GoogleEngine::new(config(serve(mock.clone()).await)).unwrap();
+        assert!(engine.supports_ssml());
+
+        let output = env::temp_dir().join("auto-mv-google-engine-ssml.wav");
+        let ssml =
+            "<speak>こんにちは<break time=\"500ms\"/><mark name=\"clause-1\"/>さようなら</speak>"
+                .to_string();
+        let request = SynthesisRequest::new(
+            "こんにちは",
+            output.to_str().unwrap(),
+            "ja-JP",
+            "ja-JP-Neural2-B",
+        )
+        .with_ssml(Some(ssml.clone()));
+        let synthesized = engine.synthesize(&request).await.unwrap();
+        assert_eq!(
+            synthesized.timepoints,
+            vec![Timepoint::new("clause-1", Duration::from_millis(250))]
+        );
+
+        // mark を含むリクエストだけ v1beta1 で送り、時刻を求める
+        {
+            let requests = mock.requests.lock().unwrap();
+            let (version, sent) = &requests[0];
+            assert_eq!(*version, "v1beta1");
+            assert_eq!(
+                sent.input.as_ref().unwrap().input_source,
+                Some(BetaInputSource::Ssml(ssml))
+            );
+            assert_eq!(
+                sent.enable_time_pointing,
+                vec![TimepointType::SsmlMark as i32]
+            );
+        }
+
+        let plain = "<speak>こんにちは<break time=\"500ms\"/>さようなら</speak>".to_string();
+        let synthesized = engine
+            .synthesize(&request.clone().with_ssml(Some(plain)))
+            .await
+            .unwrap();
+        assert!(synthesized.timepoints.is_empty());
+        let requests = mock.requests.lock().unwrap();
+        assert_eq!(requests[1].0, "v1");
+        assert!(requests[1].1.enable_time_pointing.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_synthesize_error() {
+        let engine = GoogleEngine::new(config(serve(MockTts::default()).await)).unwrap();
+
+        let output = env::temp_dir().join("auto-mv-google-engine-error.wav");
+        let request = SynthesisRequest::new("a", output.to_str().unwrap(), "ja-JP", "");
+        let err = engine.synthesize(&request).await.unwrap_err();
+        assert!(err.to_string().contains("voice name is required"));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // 400 番台にあたるステータスは再試行せずにすぐ失敗する
+        let retry = RetryEngine::new(
+            Box::new(engine),
+            RetryConfig {
+                max_attempts: 3,
+                initial_backoff_ms: 60_000,
+                ..RetryConfig::default()
+            },
+            None,
+        );
+        let result = tokio::time::timeout(Duration::from_secs(5), retry.synthesize(&request)).await;
+        assert_eq!(
+            result
+                .expect("retried a permanent error")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn test_status_to_io() {
+        let kind = |status: Status| status_to_io(status).kind();
+        assert_eq!(
+            kind(Status::invalid_argument("rate")),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            kind(Status::unauthenticated("key")),
+            io::ErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            kind(Status::permission_denied("key")),
+            io::ErrorKind::PermissionDenied
+        );
+        assert_eq!(kind(Status::not_found("voice")), io::ErrorKind::NotFound);
+        // 一時的な障害は再試行する
+        assert_eq!(kind(Status::unavailable("busy")), io::ErrorKind::Other);
+        assert_eq!(
+            kind(Status::resource_exhausted("quota")),
+            io::ErrorKind::Other
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_voices() {
+        let engine = GoogleEngine::new(config(serve(MockTts::default()).await)).unwrap();
+
+        let voices = engine.list_voices(Some("ja-JP")).await.unwrap();
+        assert_eq!(voices.len(), 1);
+        assert_eq!(voices[0].name, "ja-JP-Neural2-B");
+        assert_eq!(voices[0].gender, Gender::Female);
+        assert_eq!(voices[0].sample_rate, Some(24000));
+
+        let voices = engine.list_voices(None).await.unwrap();
+        assert_eq!(voices.len(), 2);
+
+        let catalog = VoiceCatalog::discover(&engine, None).await.unwrap();
+        assert_eq!(catalog.languages(), vec!["en-US", "ja-JP"]);
+        assert!(catalog.get("en-US-Neural2-A").is_ok());
+    }
+
+    #[test]
+    fn test_service_account_requires_credentials() {
+        let config = GoogleConfig {
+            auth: GoogleAuth::ServiceAccount,
+            ..GoogleConfig::default()
+        };
+        assert!(Authenticator::from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gcloud_token_cache() {
+        // 期限内のトークンは gcloud を呼ばずに使い回す
+        let mut auth = Authenticator::Gcloud(Some(("Bearer cached".to_string(), Instant::now())));
+        assert_eq!(
+            auth.bearer().await.unwrap().as_deref(),
+            Some("Bearer cached")
+        );
+        assert!(auth.invalidate());
+        assert!(!auth.invalidate());
+        assert!(!Authenticator::None.invalidate());
+    }
+}
@@ -0,0 +1,203 @@
+use std::{fs, io};
+
+use regex::Regex;
+use serde::Deserialize;
+
+/// 読み上げ用の発音辞書
+/// 表記（または正規表現）に一致した部分を読みに差し替える。字幕は元の表記のまま
+///
+/// ```json
+/// { "entries": [
+///   { "surface": "東京", "reading": "とうきょう" },
+///   { "pattern": "(\\d+)GB", "reading": "$1ギガバイト" }
+/// ] }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Lexicon {
+    rules: Vec<Rule>,
+}
+
+/// 辞書ファイルの1項目
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexiconEntry {
+    /// 置き換える表記
+    pub surface: Option<String>,
+    /// 置き換える正規表現。`reading` では `$1` などで参照できる
+    pub pattern: Option<String>,
+    /// 読み（SSML 対応エンジンには `<sub alias>` として渡す）
+    pub reading: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LexiconFile {
+    entries: Vec<LexiconEntry>,
+}
+
+#[derive(Debug, Clone)]
+enum Rule {
+    Surface(String, String),
+    Pattern(Regex, String),
+}
+
+/// 辞書による置き換え1件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    pub surface: String,
+    pub reading: String,
+}
+
+/// 辞書を適用したテキストの断片
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Sub(Substitution),
+}
+
+impl Lexicon {
+    pub fn new(entries: Vec<LexiconEntry>) -> Result<Self, io::Error> {
+        let rules = entries
+            .into_iter()
+            .map(|entry| match (entry.surface, entry.pattern) {
+                (Some(surface), None) if !surface.is_empty() => {
+                    Ok(Rule::Surface(surface, entry.reading))
+                }
+                (None, Some(pattern)) => Regex::new(&pattern)
+                    .map(|regex| Rule::Pattern(regex, entry.reading))
+                    .map_err(|e| invalid(format!("invalid lexicon pattern `{}`: {}", pattern, e))),
+                _ => Err(invalid(format!(
+                    "lexicon entry for `{}` needs exactly one of surface or pattern",
+                    entry.reading
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Lexicon { rules })
+    }
+
+    /// JSON の辞書ファイルを読み込む
+    pub fn from_file(path: &str) -> Result<Self, io::Error> {
+        let content = fs::read_to_string(path)?;
+        let file: LexiconFile = serde_json::from_str(&content).map_err(io::Error::other)?;
+        Lexicon::new(file.entries)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// テキストの一部を読みに置き換える規則（発音辞書・テキスト正規化）
+pub trait Substitute {
+    /// 最も手前で一致した範囲とその読み
+    fn find(&self, text: &str) -> Option<(usize, usize, String)>;
+
+    /// テキストに規則を適用し、置き換えた部分と残りの部分に分ける
+    fn apply(&self, text: &str) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut rest = text;
+
+        while let Some((start, end, reading)) = self.find(rest).filter(|(s, e, _)| s < e) {
+            if start > 0 {
+                segments.push(Segment::Text(rest[..start].to_string()));
+            }
+            segments.push(Segment::Sub(Substitution {
+                surface: rest[start..end].to_string(),
+                reading,
+            }));
+            rest = &rest[end..];
+        }
+
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+        segments
+    }
+}
+
+/// 候補のうち最も手前で一致したものを選ぶ。同じ位置なら長い一致、次に先頭側の候補を優先する
+pub fn earliest(
+    candidates: impl Iterator<Item = (usize, usize, String)>,
+) -> Option<(usize, usize, String)> {
+    candidates
+        .filter(|(start, end, _)| start < end)
+        .min_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+}
+
+impl Substitute for Lexicon {
+    fn find(&self, text: &str) -> Option<(usize, usize, String)> {
+        earliest(self.rules.iter().filter_map(|rule| rule.find(text)))
+    }
+}
+
+impl Rule {
+    /// 一致した範囲と読み
+    fn find(&self, text: &str) -> Option<(usize, usize, String)> {
+        match self {
+            Rule::Surface(surface, reading) => text
+                .find(surface.as_str())
+                .map(|start| (start, start + surface.len(), reading.clone())),
+            Rule::Pattern(regex, reading) => regex.captures(text).map(|captures| {
+                let matched = captures.get(0).unwrap();
+                let mut expanded = String::new();
+                captures.expand(reading, &mut expanded);
+                (matched.start(), matched.end(), expanded)
+            }),
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(surface: Option<&str>, pattern: Option<&str>, reading: &str) -> LexiconEntry {
+        LexiconEntry {
+            surface: surface.map(str::to_string),
+            pattern: pattern.map(str::to_string),
+            reading: reading.to_string(),
+        }
+    }
+
+    fn sub(surface: &str, reading: &str) -> Segment {
+        Segment::Sub(Substitution {
+            surface: surface.to_string(),
+            reading: reading.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_apply_surface_and_pattern() {
+        let lexicon = Lexicon::new(vec![
+            entry(Some("東京"), None, "とうきょう"),
+            entry(Some("東京都"), None, "とうきょうと"),
+            entry(None, Some(r"(\d+)GB"), "$1ギガバイト"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            lexicon.apply("東京都の東京で16GB"),
+            vec![
+                sub("東京都", "とうきょうと"),
+                Segment::Text("の".to_string()),
+                sub("東京", "とうきょう"),
+                Segment::Text("で".to_string()),
+                sub("16GB", "16ギガバイト"),
+            ]
+        );
+        assert_eq!(
+            lexicon.apply("大阪"),
+            vec![Segment::Text("大阪".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_invalid_entries() {
+        assert!(Lexicon::new(vec![entry(None, Some("(unclosed"), "x")]).is_err());
+        assert!(Lexicon::new(vec![entry(None, None, "x")]).is_err());
+        assert!(Lexicon::new(vec![entry(Some("a"), Some("a"), "x")]).is_err());
+        assert!(Lexicon::from_file("./source/not-found.json").is_err());
+    }
+}
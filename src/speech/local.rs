@@ -1,6 +1,64 @@
-use std::{fs::File, process::Command, time::Duration};
+use std::{io, time::Duration};
 
-use std::io::{self, Read};
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use crate::ffmpeg::probe;
+
+use super::{voice, SpeechEngine, SynthesisRequest, Synthesized, VoiceInfo};
+
+/// ローカルバイナリ（speech.exe）による音声合成エンジン
+pub struct LocalEngine {
+    binary: String,
+}
+
+impl LocalEngine {
+    pub fn new(binary: &str) -> Self {
+        LocalEngine {
+            binary: binary.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechEngine for LocalEngine {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        let duration = command(
+            &self.binary,
+            &request.text,
+            &request.output,
+            &request.lang,
+            &request.voice,
+        )
+        .await?;
+
+        Ok(Synthesized {
+            path: request.output.clone(),
+            duration,
+            timepoints: Vec::new(),
+        })
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        // バイナリには一覧取得の機能がないため、既知の音声を返す
+        let voices = voice::VoiceCatalog::builtin()
+            .voices()
+            .iter()
+            .map(|v| VoiceInfo {
+                name: v.id.clone(),
+                language_codes: vec![v.language.clone()],
+                gender: v.gender,
+                sample_rate: None,
+            })
+            .filter(|v| lang.is_none_or(|l| v.language_codes.iter().any(|c| c == l)))
+            .collect();
+        Ok(voices)
+    }
+}
 
 // local binary:: speech.exe
 /// テキストを音声に変換する
@@ -8,62 +66,27 @@ use std::io::{self, Read};
 // -output string:  uotput path & filename (default "./speech-voice.wav")
 // -lang string:    language code (default "ja-JP")
 // -voice string:   voice name (default "jp-JP-Standard-A")
-pub fn command(
+async fn command(
+    binary: &str,
     text: &str,
     output: &str,
-    lang: Option<String>,
-    voice: Option<String>,
+    lang: &str,
+    voice: &str,
 ) -> Result<Duration, io::Error> {
-    let lang = if let Some(l) = lang {
-        l
-    } else {
-        "ja-JP".to_string()
-    };
-    let voice = if let Some(v) = voice {
-        v
-    } else {
-        "jp-JP-Standard-A".to_string()
-    };
-
-    let status = Command::new("speech")
+    let status = Command::new(binary)
         .args([
-            "-text", text, "-output", output, "-lang", &lang, "-voice", &voice,
+            "-text", text, "-output", output, "-lang", lang, "-voice", voice,
         ])
-        .status()?;
+        .status()
+        .await?;
 
     if !status.success() {
-        eprintln!(
-            "Error: Failed to generate blank video - status: {:?}",
-            status
-        );
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to generate blank video",
-        ));
+        eprintln!("Error: Failed to synthesize speech - status: {:?}", status);
+        return Err(io::Error::other("Failed to synthesize speech"));
     }
 
     // 音声ファイルの再生時間を取得
-    let duration = get_wav_duration(output)?;
-
-    Ok(duration)
-}
-fn get_wav_duration(file_path: &str) -> Result<Duration, io::Error> {
-    let mut file = File::open(file_path)?;
-
-    let mut header = [0u8; 44]; // WAVヘッダのサイズは44バイト。
-    file.read_exact(&mut header)?;
-
-    // サンプリングレートを取得
-    let sample_rate = u32::from_le_bytes(header[24..28].try_into().unwrap());
-    // チャンネル数を取得
-    let num_channels = u16::from_le_bytes(header[22..24].try_into().unwrap());
-    // データサイズを取得
-    let data_size = u32::from_le_bytes(header[40..44].try_into().unwrap());
-
-    // 再生時間（秒）を計算
-    let f = data_size as f32 / (sample_rate as f32 * num_channels as f32 * 2.0); // 16ビット=2バイト
-
-    let duration = Duration::from_secs_f32(f);
+    let duration = probe::duration(output)?;
 
     Ok(duration)
 }
@@ -72,16 +95,33 @@ fn get_wav_duration(file_path: &str) -> Result<Duration, io::Error> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_command() {
-        let text = "おはよう世界！";
-        let output = "./source/voice.wav";
+    #[tokio::test]
+    #[ignore = "requires the speech binary on PATH"]
+    async fn test_command() {
+        let engine = LocalEngine::new("speech");
+        let request = SynthesisRequest::new(
+            "おはよう世界！",
+            "./source/voice.wav",
+            "ja-JP",
+            "ja-JP-Standard-A",
+        );
 
-        match command(text, output, None, None) {
-            Ok(duration) => {
-                println!("Success! {:?}", duration);
+        match engine.synthesize(&request).await {
+            Ok(synthesized) => {
+                println!("Success! {:?}", synthesized.duration);
             }
             Err(e) => panic!("Error: {:?}", e),
         }
     }
+
+    #[tokio::test]
+    async fn test_list_voices() {
+        let engine = LocalEngine::new("speech");
+
+        let voices = engine.list_voices(Some("ja-JP")).await.unwrap();
+        assert_eq!(voices.len(), voice::Name::all().len());
+
+        let voices = engine.list_voices(Some("en-US")).await.unwrap();
+        assert!(voices.is_empty());
+    }
 }
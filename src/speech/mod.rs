@@ -1,2 +1,237 @@
+pub mod cache;
+pub mod command;
+pub mod effects;
+pub mod fake;
+pub mod google;
+pub mod lexicon;
 pub mod local;
+pub mod normalize;
+pub mod prosody;
+pub mod recording;
+pub mod retry;
+pub mod ssml;
+pub mod timepoint;
+pub mod trim;
 pub mod voice;
+pub mod voicevox;
+
+use std::{io, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::models::config::{EngineKind, SpeechConfig};
+
+/// 音声合成の依頼内容
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthesisRequest {
+    /// 読み上げるテキスト（タグを含まない）
+    pub text: String,
+    /// SSML 文書。SSML に対応するエンジンは `text` の代わりにこちらを使う
+    pub ssml: Option<String>,
+    /// 出力先の音声ファイルパス
+    pub output: String,
+    /// 言語コード (例: ja-JP)
+    pub lang: String,
+    /// 音声名 (例: ja-JP-Standard-A)
+    pub voice: String,
+    /// 読み上げの速度・高さ・音量
+    pub prosody: prosody::Prosody,
+    /// 合成後にかける音響効果
+    pub effects: effects::Effects,
+    /// 句読点の区切りの数（`Markup::mark_clauses` の戻り値）。区切りの時刻を求めるエンジンはこの数と照合する
+    pub clauses: usize,
+}
+
+impl SynthesisRequest {
+    pub fn new(text: &str, output: &str, lang: &str, voice: &str) -> Self {
+        SynthesisRequest {
+            text: text.to_string(),
+            ssml: None,
+            output: output.to_string(),
+            lang: lang.to_string(),
+            voice: voice.to_string(),
+            prosody: prosody::Prosody::default(),
+            effects: effects::Effects::default(),
+            clauses: 0,
+        }
+    }
+
+    /// SSML を指定する
+    pub fn with_ssml(mut self, ssml: Option<String>) -> Self {
+        self.ssml = ssml;
+        self
+    }
+
+    /// 速度・高さ・音量を指定する
+    pub fn with_prosody(mut self, prosody: prosody::Prosody) -> Self {
+        self.prosody = prosody;
+        self
+    }
+
+    /// 音響効果を指定する
+    pub fn with_effects(mut self, effects: effects::Effects) -> Self {
+        self.effects = effects;
+        self
+    }
+
+    /// 句読点の区切りの数を指定する
+    pub fn with_clauses(mut self, clauses: usize) -> Self {
+        self.clauses = clauses;
+        self
+    }
+}
+
+/// 音声合成の結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Synthesized {
+    /// 生成された音声ファイルのパス
+    pub path: String,
+    /// 音声の再生時間
+    pub duration: Duration,
+    /// mark などが読み上げられた時刻（エンジンが返さなければ空）
+    pub timepoints: Vec<timepoint::Timepoint>,
+}
+
+/// エンジンが提供する音声の情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoiceInfo {
+    pub name: String,
+    pub language_codes: Vec<String>,
+    pub gender: voice::Gender,
+    /// 音声本来のサンプリングレート（不明なら None）
+    pub sample_rate: Option<u32>,
+}
+
+/// 音声合成エンジンの共通インターフェース
+/// main.rs はこのトレイトだけに依存し、実装は設定で差し替える
+#[async_trait]
+pub trait SpeechEngine: Send + Sync {
+    /// エンジン名（ログ出力用）
+    fn name(&self) -> &str;
+
+    /// SSML を受け付けるかどうか
+    fn supports_ssml(&self) -> bool {
+        false
+    }
+
+    /// 速度・高さ・音量をエンジン自身で反映できるかどうか
+    /// false のエンジンは ProsodyAdapter が合成後に ffmpeg で加工する
+    fn supports_prosody(&self) -> bool {
+        false
+    }
+
+    /// テキストを音声ファイルに変換し、ファイルパスと再生時間を返す
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error>;
+
+    /// 利用可能な音声の一覧を返す
+    /// `lang` を指定した場合はその言語に対応する音声のみ
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error>;
+}
+
+/// 設定に従って音声合成エンジンを生成する
+pub fn engine_from_config(config: &SpeechConfig) -> Result<Box<dyn SpeechEngine>, io::Error> {
+    let engine: Box<dyn SpeechEngine> = match config.engine {
+        EngineKind::Local => Box::new(local::LocalEngine::new(&config.local.binary)),
+        EngineKind::Fake => Box::new(fake::FakeEngine::new(config.fake.clone())),
+        EngineKind::Google => Box::new(google::GoogleEngine::new(config.google.clone())?),
+        EngineKind::Voicevox => Box::new(voicevox::VoicevoxEngine::new(config.voicevox.clone())),
+        EngineKind::Command => Box::new(command::CommandEngine::new(config.command.clone())?),
+    };
+    let engine: Box<dyn SpeechEngine> = Box::new(retry::RetryEngine::new(
+        engine,
+        config.retry.clone(),
+        config.requests_per_minute,
+    ));
+    let engine: Box<dyn SpeechEngine> = if engine.supports_prosody() {
+        engine
+    } else {
+        Box::new(prosody::ProsodyAdapter::new(engine))
+    };
+    let engine: Box<dyn SpeechEngine> = if config.trim.enabled {
+        Box::new(trim::TrimmedEngine::new(engine, config.trim.clone()))
+    } else {
+        engine
+    };
+    let engine: Box<dyn SpeechEngine> = Box::new(effects::EffectsEngine::new(engine));
+    Ok(match &config.cache_dir {
+        Some(dir) => Box::new(cache::CachedEngine::new(engine, dir).with_salt(&format!(
+            "config={}\ntrim={}",
+            config.engine_key(),
+            config.trim.key()
+        ))),
+        None => engine,
+    })
+}
+
+/// 設定に従って音声カタログを用意する
+/// JSON ファイル > エンジンの list_voices > 組み込みの ja-JP 音声 の順に優先する
+/// VOICEVOX・コマンドは音声名がエンジン固有のため、常にエンジンから取得する
+pub async fn catalog_from_config(
+    config: &SpeechConfig,
+    engine: &dyn SpeechEngine,
+) -> Result<voice::VoiceCatalog, io::Error> {
+    if let Some(path) = &config.voices {
+        voice::VoiceCatalog::from_file(path)
+    } else if config.discover_voices
+        || matches!(config.engine, EngineKind::Voicevox | EngineKind::Command)
+    {
+        voice::VoiceCatalog::discover(engine, Some(&config.language)).await
+    } else {
+        Ok(voice::VoiceCatalog::builtin())
+    }
+}
+
+/// 設定に従って発音辞書を読み込む。未指定なら空の辞書
+pub fn lexicon_from_config(config: &SpeechConfig) -> Result<lexicon::Lexicon, io::Error> {
+    match &config.lexicon {
+        Some(path) => lexicon::Lexicon::from_file(path),
+        None => Ok(lexicon::Lexicon::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::GoogleAuth;
+
+    #[tokio::test]
+    async fn test_engine_from_config() {
+        let mut config = SpeechConfig::default();
+        assert_eq!(engine_from_config(&config).unwrap().name(), "local");
+        // ffmpeg での加工とキャッシュで包まれる
+        assert!(engine_from_config(&config).unwrap().supports_prosody());
+
+        config.engine = EngineKind::Fake;
+        assert_eq!(engine_from_config(&config).unwrap().name(), "fake");
+
+        config.engine = EngineKind::Google;
+        config.google.auth = GoogleAuth::None;
+        assert_eq!(engine_from_config(&config).unwrap().name(), "google");
+
+        config.engine = EngineKind::Voicevox;
+        assert_eq!(engine_from_config(&config).unwrap().name(), "voicevox");
+
+        // テンプレートが空ならエラー
+        config.engine = EngineKind::Command;
+        assert!(engine_from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_catalog_from_config() {
+        let mut config = SpeechConfig {
+            engine: EngineKind::Fake,
+            ..SpeechConfig::default()
+        };
+        let engine = engine_from_config(&config).unwrap();
+
+        let catalog = catalog_from_config(&config, engine.as_ref()).await.unwrap();
+        assert_eq!(catalog, voice::VoiceCatalog::builtin());
+
+        config.discover_voices = true;
+        let catalog = catalog_from_config(&config, engine.as_ref()).await.unwrap();
+        assert_eq!(catalog.by_language("ja-JP").unwrap()[0].id, "ja-JP-Fake-A");
+
+        config.voices = Some("./source/not-found.json".to_string());
+        assert!(catalog_from_config(&config, engine.as_ref()).await.is_err());
+    }
+}
@@ -0,0 +1,580 @@
+use regex::{Captures, Regex};
+
+use super::lexicon::{earliest, Substitute};
+use crate::models::config::NormalizeConfig;
+
+/// 読み上げ前のテキスト正規化
+/// 数値・金額・単位・日付・時刻・バージョン・メールアドレス・URL を読みやすい形に展開する
+/// 字幕には適用しない（`Markup::substitute` で `sub` として差し込む）
+pub struct Normalizer {
+    language: Language,
+    rules: Vec<(Kind, Regex)>,
+}
+
+/// 展開に使う言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Japanese,
+    English,
+}
+
+impl Language {
+    /// 言語コード (例: ja-JP) から判定する。未対応の言語は None
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.split(['-', '_']).next()? {
+            "ja" => Some(Language::Japanese),
+            "en" => Some(Language::English),
+            _ => None,
+        }
+    }
+}
+
+/// 正規化の種類（一致位置が同じ場合は先頭側を優先する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Url,
+    Email,
+    Date,
+    Time,
+    Version,
+    Currency,
+    Unit,
+    Number,
+}
+
+const NUMBER: &str = r"\d{1,3}(?:,\d{3})+(?:\.\d+)?|\d+(?:\.\d+)?";
+
+/// 単位と読み（長いものから順に並べる）
+const UNITS: [(&str, &str, &str); 24] = [
+    ("Gbps", "ギガビーピーエス", "gigabits per second"),
+    ("Mbps", "メガビーピーエス", "megabits per second"),
+    ("kbps", "キロビーピーエス", "kilobits per second"),
+    ("GHz", "ギガヘルツ", "gigahertz"),
+    ("MHz", "メガヘルツ", "megahertz"),
+    ("kHz", "キロヘルツ", "kilohertz"),
+    ("TB", "テラバイト", "terabytes"),
+    ("GB", "ギガバイト", "gigabytes"),
+    ("MB", "メガバイト", "megabytes"),
+    ("KB", "キロバイト", "kilobytes"),
+    ("kB", "キロバイト", "kilobytes"),
+    ("km", "キロメートル", "kilometers"),
+    ("cm", "センチメートル", "centimeters"),
+    ("mm", "ミリメートル", "millimeters"),
+    ("kg", "キログラム", "kilograms"),
+    ("mg", "ミリグラム", "milligrams"),
+    ("mL", "ミリリットル", "milliliters"),
+    ("ml", "ミリリットル", "milliliters"),
+    ("ms", "ミリ秒", "milliseconds"),
+    ("Hz", "ヘルツ", "hertz"),
+    ("°C", "度", "degrees Celsius"),
+    ("℃", "度", "degrees Celsius"),
+    ("%", "パーセント", "percent"),
+    ("m", "メートル", "meters"),
+];
+
+/// 通貨記号と読み
+const CURRENCIES: [(&str, &str, &str); 5] = [
+    ("¥", "円", "yen"),
+    ("￥", "円", "yen"),
+    ("$", "ドル", "dollars"),
+    ("€", "ユーロ", "euros"),
+    ("£", "ポンド", "pounds"),
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+impl Normalizer {
+    /// 設定と読み上げる言語から正規化を用意する
+    /// 無効化されている、または未対応の言語なら None
+    pub fn new(config: &NormalizeConfig, language: &str) -> Option<Self> {
+        let language = Language::from_code(language).filter(|_| config.enabled)?;
+
+        let units = UNITS
+            .iter()
+            .map(|(unit, _, _)| regex::escape(unit))
+            .collect::<Vec<_>>()
+            .join("|");
+        let currencies = CURRENCIES
+            .iter()
+            .map(|(symbol, _, _)| regex::escape(symbol))
+            .collect::<Vec<_>>()
+            .join("|");
+        let patterns = [
+            (
+                Kind::Url,
+                config.urls,
+                r"https?://[A-Za-z0-9\-._~:/?#@!$&'*+,;=%]+".to_string(),
+            ),
+            (
+                Kind::Email,
+                config.emails,
+                r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9\-]+(?:\.[A-Za-z0-9\-]+)*\.[A-Za-z]{2,}".to_string(),
+            ),
+            (
+                Kind::Date,
+                config.dates,
+                r"(\d{4})[/\-](\d{1,2})[/\-](\d{1,2})".to_string(),
+            ),
+            (
+                Kind::Time,
+                config.times,
+                r"(\d{1,2}):(\d{2})(?::(\d{2}))?".to_string(),
+            ),
+            (
+                Kind::Version,
+                config.versions,
+                r"[vV](\d+(?:\.\d+)+)|(\d+\.\d+\.\d+(?:\.\d+)*)".to_string(),
+            ),
+            (
+                Kind::Currency,
+                config.currency,
+                format!(r"({})\s?({})", currencies, NUMBER),
+            ),
+            (
+                Kind::Unit,
+                config.units,
+                format!(r"({})\s?({})", NUMBER, units),
+            ),
+            (Kind::Number, config.numbers, format!("({})", NUMBER)),
+        ];
+        let rules = patterns
+            .into_iter()
+            .filter(|(_, enabled, _)| *enabled)
+            .map(|(kind, _, pattern)| (kind, Regex::new(&pattern).unwrap()))
+            .collect();
+
+        Some(Normalizer { language, rules })
+    }
+
+    /// 一致した部分の読み。展開できない場合は None
+    fn expand(&self, kind: Kind, captures: &Captures) -> Option<String> {
+        let text = &captures[0];
+        let reading = match kind {
+            Kind::Url => {
+                let body = text
+                    .split_once("://")
+                    .map(|(_, body)| body)
+                    .unwrap_or(text)
+                    .trim_end_matches('/');
+                self.spell_address(body)
+            }
+            Kind::Email => self.spell_address(text),
+            Kind::Date => {
+                let year = captures[1].parse::<u64>().ok()?;
+                let month = captures[2]
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|m| (1..=12).contains(m))?;
+                let day = captures[3]
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|d| (1..=31).contains(d))?;
+                match self.language {
+                    Language::Japanese => format!(
+                        "{}年{}月{}日",
+                        kanji_number(year),
+                        kanji_number(month),
+                        kanji_number(day)
+                    ),
+                    Language::English => format!(
+                        "{} {}, {}",
+                        MONTHS[month as usize - 1],
+                        english_ordinal(day),
+                        english_number(year)
+                    ),
+                }
+            }
+            Kind::Time => {
+                let hour = captures[1].parse::<u64>().ok().filter(|h| *h <= 24)?;
+                let minute = captures[2].parse::<u64>().ok().filter(|m| *m < 60)?;
+                let second = match captures.get(3) {
+                    Some(s) => Some(s.as_str().parse::<u64>().ok().filter(|s| *s < 60)?),
+                    None => None,
+                };
+                self.time(hour, minute, second)
+            }
+            Kind::Version => {
+                let version = captures.get(1).or(captures.get(2))?.as_str();
+                let parts = version
+                    .split('.')
+                    .map(|part| part.parse::<u64>().ok().map(|n| self.number(n)))
+                    .collect::<Option<Vec<_>>>()?;
+                match self.language {
+                    Language::Japanese => format!("バージョン{}", parts.join("点")),
+                    Language::English => format!("version {}", parts.join(" point ")),
+                }
+            }
+            Kind::Currency => {
+                let (_, ja, en) = CURRENCIES.iter().find(|(s, _, _)| *s == &captures[1])?;
+                let amount = self.decimal(&captures[2])?;
+                match self.language {
+                    Language::Japanese => format!("{}{}", amount, ja),
+                    Language::English => format!("{} {}", amount, en),
+                }
+            }
+            Kind::Unit => {
+                let (_, ja, en) = UNITS.iter().find(|(u, _, _)| *u == &captures[2])?;
+                let amount = self.decimal(&captures[1])?;
+                match self.language {
+                    Language::Japanese => format!("{}{}", amount, ja),
+                    Language::English => format!("{} {}", amount, en),
+                }
+            }
+            Kind::Number => self.decimal(&captures[1])?,
+        };
+        Some(reading)
+    }
+
+    fn number(&self, n: u64) -> String {
+        match self.language {
+            Language::Japanese => kanji_number(n),
+            Language::English => english_number(n),
+        }
+    }
+
+    /// 桁区切りや小数を含む数値の読み
+    fn decimal(&self, text: &str) -> Option<String> {
+        let text = text.replace(',', "");
+        let (integer, fraction) = match text.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (text.as_str(), None),
+        };
+        let mut out = self.number(integer.parse::<u64>().ok()?);
+        if let Some(fraction) = fraction {
+            let digits = fraction
+                .chars()
+                .map(|c| self.number(c.to_digit(10).unwrap() as u64))
+                .collect::<Vec<_>>();
+            match self.language {
+                Language::Japanese => {
+                    out.push('点');
+                    out.push_str(&digits.concat());
+                }
+                Language::English => {
+                    out.push_str(" point ");
+                    out.push_str(&digits.join(" "));
+                }
+            }
+        }
+        Some(out)
+    }
+
+    fn time(&self, hour: u64, minute: u64, second: Option<u64>) -> String {
+        match self.language {
+            Language::Japanese => {
+                let mut out = format!("{}時", kanji_number(hour));
+                if minute > 0 || second.is_some() {
+                    out.push_str(&format!("{}分", kanji_number(minute)));
+                }
+                if let Some(second) = second {
+                    out.push_str(&format!("{}秒", kanji_number(second)));
+                }
+                out
+            }
+            Language::English => {
+                let mut out = english_number(hour);
+                match minute {
+                    0 if second.is_none() => out.push_str(" o'clock"),
+                    1..=9 => out.push_str(&format!(" oh {}", english_number(minute))),
+                    _ => out.push_str(&format!(" {}", english_number(minute))),
+                }
+                if let Some(second) = second {
+                    out.push_str(&format!(" and {} seconds", english_number(second)));
+                }
+                out
+            }
+        }
+    }
+
+    /// メールアドレスや URL の記号を読みに置き換える
+    fn spell_address(&self, text: &str) -> String {
+        let mut out = String::new();
+        for c in text.chars() {
+            let word = match (c, self.language) {
+                ('@', Language::Japanese) => "アット",
+                ('@', Language::English) => "at",
+                ('.', Language::Japanese) => "ドット",
+                ('.', Language::English) => "dot",
+                ('/', Language::Japanese) => "スラッシュ",
+                ('/', Language::English) => "slash",
+                ('-', Language::Japanese) => "ハイフン",
+                ('-', Language::English) => "dash",
+                ('_', Language::Japanese) => "アンダースコア",
+                ('_', Language::English) => "underscore",
+                _ => {
+                    out.push(c);
+                    continue;
+                }
+            };
+            out.push(' ');
+            out.push_str(word);
+            out.push(' ');
+        }
+        out.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+impl Substitute for Normalizer {
+    fn find(&self, text: &str) -> Option<(usize, usize, String)> {
+        earliest(self.rules.iter().filter_map(|(kind, regex)| {
+            regex
+                .captures_iter(text)
+                .filter(|captures| {
+                    // 英字に続く数値や、英字が続く単位（MP3、5min など）は対象外
+                    let m = captures.get(0).unwrap();
+                    let before = text[..m.start()].chars().next_back();
+                    let after = text[m.end()..].chars().next();
+                    !before.is_some_and(|c| c.is_ascii_alphanumeric())
+                        && !after.is_some_and(|c| c.is_ascii_alphanumeric())
+                })
+                .find_map(|captures| {
+                    let m = captures.get(0).unwrap();
+                    let reading = self.expand(*kind, &captures)?;
+                    Some((m.start(), m.end(), reading))
+                })
+        }))
+    }
+}
+
+/// 漢数字の読み（例: 1200 → 千二百）
+pub fn kanji_number(n: u64) -> String {
+    const DIGITS: [&str; 10] = ["", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+    const GROUPS: [&str; 5] = ["", "万", "億", "兆", "京"];
+
+    if n == 0 {
+        return "ゼロ".to_string();
+    }
+
+    let mut out = String::new();
+    let mut groups = Vec::new();
+    let mut rest = n;
+    while rest > 0 {
+        groups.push(rest % 10_000);
+        rest /= 10_000;
+    }
+    for (i, group) in groups.iter().enumerate().rev() {
+        if *group == 0 {
+            continue;
+        }
+        for (place, unit) in [(1000, "千"), (100, "百"), (10, "十")] {
+            let digit = (group / place % 10) as usize;
+            if digit > 1 {
+                out.push_str(DIGITS[digit]);
+            }
+            if digit > 0 {
+                out.push_str(unit);
+            }
+        }
+        out.push_str(DIGITS[(group % 10) as usize]);
+        out.push_str(GROUPS[i]);
+    }
+    out
+}
+
+/// 英語の数の読み（例: 1200 → one thousand two hundred）
+pub fn english_number(n: u64) -> String {
+    const ONES: [&str; 20] = [
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: [&str; 10] = [
+        "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+    const SCALES: [(u64, &str); 6] = [
+        (1_000_000_000_000_000_000, "quintillion"),
+        (1_000_000_000_000_000, "quadrillion"),
+        (1_000_000_000_000, "trillion"),
+        (1_000_000_000, "billion"),
+        (1_000_000, "million"),
+        (1_000, "thousand"),
+    ];
+
+    fn below_thousand(n: u64) -> String {
+        let mut words = Vec::new();
+        if n >= 100 {
+            words.push(format!("{} hundred", ONES[(n / 100) as usize]));
+        }
+        match n % 100 {
+            0 => {}
+            r @ 1..=19 => words.push(ONES[r as usize].to_string()),
+            r if r % 10 == 0 => words.push(TENS[(r / 10) as usize].to_string()),
+            r => words.push(format!(
+                "{}-{}",
+                TENS[(r / 10) as usize],
+                ONES[(r % 10) as usize]
+            )),
+        }
+        words.join(" ")
+    }
+
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut words = Vec::new();
+    let mut rest = n;
+    for (scale, name) in SCALES {
+        if rest >= scale {
+            words.push(format!("{} {}", below_thousand(rest / scale), name));
+            rest %= scale;
+        }
+    }
+    if rest > 0 {
+        words.push(below_thousand(rest));
+    }
+    words.join(" ")
+}
+
+/// 英語の序数の読み（例: 13 → thirteenth、21 → twenty-first）
+fn english_ordinal(n: u64) -> String {
+    let words = english_number(n);
+    let split = words.rfind([' ', '-']).map_or(0, |i| i + 1);
+    let (head, last) = words.split_at(split);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        tens if tens.ends_with('y') => format!("{}ieth", &tens[..tens.len() - 1]),
+        other => format!("{}th", other),
+    };
+    format!("{}{}", head, last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::speech::lexicon::Segment;
+
+    fn normalize(normalizer: &Normalizer, text: &str) -> String {
+        normalizer
+            .apply(text)
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text,
+                Segment::Sub(sub) => sub.reading,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_kanji_number() {
+        assert_eq!(kanji_number(0), "ゼロ");
+        assert_eq!(kanji_number(10), "十");
+        assert_eq!(kanji_number(1200), "千二百");
+        assert_eq!(kanji_number(2024), "二千二十四");
+        assert_eq!(kanji_number(10_000), "一万");
+        assert_eq!(kanji_number(120_000_305), "一億二千万三百五");
+    }
+
+    #[test]
+    fn test_english_number() {
+        assert_eq!(english_number(0), "zero");
+        assert_eq!(english_number(13), "thirteen");
+        assert_eq!(english_number(1200), "one thousand two hundred");
+        assert_eq!(english_number(2_000_045), "two million forty-five");
+    }
+
+    #[test]
+    fn test_english_ordinal() {
+        assert_eq!(english_ordinal(1), "first");
+        assert_eq!(english_ordinal(12), "twelfth");
+        assert_eq!(english_ordinal(13), "thirteenth");
+        assert_eq!(english_ordinal(20), "twentieth");
+        assert_eq!(english_ordinal(22), "twenty-second");
+        assert_eq!(english_ordinal(31), "thirty-first");
+    }
+
+    #[test]
+    fn test_normalize_japanese() {
+        let normalizer = Normalizer::new(&NormalizeConfig::default(), "ja-JP").unwrap();
+
+        assert_eq!(
+            normalize(&normalizer, "2024/10/13に公開"),
+            "二千二十四年十月十三日に公開"
+        );
+        assert_eq!(normalize(&normalizer, "5Mbps"), "五メガビーピーエス");
+        assert_eq!(normalize(&normalizer, "¥1,200です"), "千二百円です");
+        assert_eq!(normalize(&normalizer, "v0.1.0"), "バージョンゼロ点一点ゼロ");
+        assert_eq!(normalize(&normalizer, "12:30開始"), "十二時三十分開始");
+        assert_eq!(
+            normalize(&normalizer, "3.14と50%"),
+            "三点一四と五十パーセント"
+        );
+        assert_eq!(
+            normalize(&normalizer, "mail@example.com"),
+            "mail アット example ドット com"
+        );
+        assert_eq!(
+            normalize(&normalizer, "https://example.com/docs/を参照"),
+            "example ドット com スラッシュ docsを参照"
+        );
+        // 英字に続く数値はそのまま
+        assert_eq!(normalize(&normalizer, "MP3"), "MP3");
+        // 範囲外の日付は数値として読む
+        assert_eq!(normalize(&normalizer, "2024/13/40"), "二千二十四/十三/四十");
+    }
+
+    #[test]
+    fn test_normalize_english() {
+        let normalizer = Normalizer::new(&NormalizeConfig::default(), "en-US").unwrap();
+
+        assert_eq!(
+            normalize(&normalizer, "on 2024/10/13"),
+            "on October thirteenth, two thousand twenty-four"
+        );
+        assert_eq!(normalize(&normalizer, "$5"), "five dollars");
+        assert_eq!(normalize(&normalizer, "at 9:05"), "at nine oh five");
+        assert_eq!(normalize(&normalizer, "v1.2"), "version one point two");
+    }
+
+    #[test]
+    fn test_normalize_config() {
+        let config = NormalizeConfig {
+            numbers: false,
+            ..NormalizeConfig::default()
+        };
+        let normalizer = Normalizer::new(&config, "ja-JP").unwrap();
+        assert_eq!(normalize(&normalizer, "3個で5GB"), "3個で五ギガバイト");
+
+        let disabled = NormalizeConfig {
+            enabled: false,
+            ..NormalizeConfig::default()
+        };
+        assert!(Normalizer::new(&disabled, "ja-JP").is_none());
+        assert!(Normalizer::new(&NormalizeConfig::default(), "fr-FR").is_none());
+    }
+}
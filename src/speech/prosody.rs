@@ -0,0 +1,220 @@
+use std::{fs, io};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{timepoint, SpeechEngine, SynthesisRequest, Synthesized, VoiceInfo};
+use crate::ffmpeg::{self, probe};
+
+/// 指定できる高さの範囲（半音）
+pub const MAX_PITCH_SEMITONES: f64 = 24.0;
+
+/// 読み上げの速度・高さ・音量
+/// 未指定の項目は上位（話者設定や既定値）の値を引き継ぐ
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Prosody {
+    /// 読み上げ速度の倍率（1.0 が標準）
+    pub rate: Option<f64>,
+    /// ピッチ（半音単位、0.0 が標準）
+    pub pitch: Option<f64>,
+    /// 音量の増減（dB、0.0 が標準）
+    pub gain: Option<f64>,
+}
+
+impl Prosody {
+    /// `other` で指定された項目を上書きする
+    pub fn merge(&self, other: &Prosody) -> Prosody {
+        Prosody {
+            rate: other.rate.or(self.rate),
+            pitch: other.pitch.or(self.pitch),
+            gain: other.gain.or(self.gain),
+        }
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate.unwrap_or(1.0)
+    }
+
+    pub fn pitch(&self) -> f64 {
+        self.pitch.unwrap_or(0.0)
+    }
+
+    pub fn gain(&self) -> f64 {
+        self.gain.unwrap_or(0.0)
+    }
+
+    /// 標準の読み上げから変更がないかどうか
+    pub fn is_neutral(&self) -> bool {
+        self.rate() == 1.0 && self.pitch() == 0.0 && self.gain() == 0.0
+    }
+
+    /// 値が加工できる範囲にあるかを確かめる
+    /// 速度は正の有限値、高さは ±`MAX_PITCH_SEMITONES` 半音、音量は有限値に限る
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.rate().is_finite() && self.rate() > 0.0) {
+            return Err(format!("rate must be positive: {}", self.rate()));
+        }
+        validate_pitch(self.pitch())?;
+        if !self.gain().is_finite() {
+            return Err(format!("gain must be finite: {}", self.gain()));
+        }
+        Ok(())
+    }
+
+    /// キャッシュキー用の文字列表現
+    pub fn key(&self) -> String {
+        format!(
+            "rate={:.3},pitch={:.3},gain={:.3}",
+            self.rate(),
+            self.pitch(),
+            self.gain()
+        )
+    }
+}
+
+/// 高さ（半音）が ±`MAX_PITCH_SEMITONES` の範囲にあるかを確かめる
+pub fn validate_pitch(pitch: f64) -> Result<(), String> {
+    match pitch.is_finite() && pitch.abs() <= MAX_PITCH_SEMITONES {
+        true => Ok(()),
+        false => Err(format!(
+            "pitch must be within ±{} semitones: {}",
+            MAX_PITCH_SEMITONES, pitch
+        )),
+    }
+}
+
+/// 速度・高さ・音量を指定できないエンジン向けに、合成後の音声を ffmpeg で加工する
+pub struct ProsodyAdapter {
+    inner: Box<dyn SpeechEngine>,
+}
+
+impl ProsodyAdapter {
+    pub fn new(inner: Box<dyn SpeechEngine>) -> Self {
+        ProsodyAdapter { inner }
+    }
+}
+
+#[async_trait]
+impl SpeechEngine for ProsodyAdapter {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn supports_ssml(&self) -> bool {
+        self.inner.supports_ssml()
+    }
+
+    fn supports_prosody(&self) -> bool {
+        true
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        if request.prosody.is_neutral() {
+            return self.inner.synthesize(request).await;
+        }
+        request
+            .prosody
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        // 一時ファイルに合成してから加工する
+        let raw = SynthesisRequest {
+            output: format!("{}.raw.wav", request.output),
+            ..request.clone()
+        };
+        let synthesized = self.inner.synthesize(&raw).await?;
+        let sample_rate = probe::audio_info(&synthesized.path)?.sample_rate;
+
+        ffmpeg::command::adjust_prosody(
+            &synthesized.path,
+            &request.output,
+            sample_rate,
+            &request.prosody,
+        )?;
+        fs::remove_file(&synthesized.path)?;
+
+        // 速度を変えた分だけ時刻も伸縮する
+        let rate = request.prosody.rate();
+        Ok(Synthesized {
+            path: request.output.clone(),
+            duration: probe::duration(&request.output)?,
+            timepoints: timepoint::map_times(&synthesized.timepoints, |t| t.div_f64(rate)),
+        })
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        self.inner.list_voices(lang).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let speaker = Prosody {
+            rate: Some(1.2),
+            pitch: Some(-2.0),
+            gain: None,
+        };
+        let line = Prosody {
+            rate: None,
+            pitch: Some(3.0),
+            gain: Some(-6.0),
+        };
+
+        let merged = speaker.merge(&line);
+        assert_eq!(merged.rate(), 1.2);
+        assert_eq!(merged.pitch(), 3.0);
+        assert_eq!(merged.gain(), -6.0);
+        assert!(!merged.is_neutral());
+        assert!(Prosody::default().is_neutral());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Prosody::default().validate().is_ok());
+        for invalid in [
+            Prosody {
+                rate: Some(0.0),
+                ..Prosody::default()
+            },
+            Prosody {
+                rate: Some(f64::INFINITY),
+                ..Prosody::default()
+            },
+            Prosody {
+                pitch: Some(f64::NEG_INFINITY),
+                ..Prosody::default()
+            },
+            Prosody {
+                pitch: Some(30.0),
+                ..Prosody::default()
+            },
+            Prosody {
+                gain: Some(f64::NAN),
+                ..Prosody::default()
+            },
+        ] {
+            assert!(invalid.validate().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!(
+            Prosody::default().key(),
+            "rate=1.000,pitch=0.000,gain=0.000"
+        );
+        assert_ne!(
+            Prosody::default().key(),
+            Prosody {
+                rate: Some(1.1),
+                ..Prosody::default()
+            }
+            .key()
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::Path,
+    time::Duration,
+};
+
+use log::warn;
+use serde::Serialize;
+
+use super::Synthesized;
+use crate::{
+    audio::{align, ingest, trim, wav},
+    ffmpeg::probe,
+    models::{
+        config::{AudioFormat, RecordingConfig, SplitConfig, TrimConfig},
+        script::ScriptLine,
+    },
+};
+
+/// 行に使う録音を探す
+/// `rec=` の指定があればそのファイル（見つからなければエラー）、なければ読み上げのある行に限り
+/// `dir` 内の `{id}.{拡張子}`・`line-{行番号}.{拡張子}` を探す
+pub fn find(config: &RecordingConfig, script: &ScriptLine) -> Result<Option<String>, io::Error> {
+    let dir = Path::new(&config.dir);
+    if let Some(file) = &script.recording {
+        let path = dir.join(file);
+        if !path.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("recording not found: {}", path.display()),
+            ));
+        }
+        return Ok(Some(path.to_string_lossy().to_string()));
+    }
+    if script.spoken.is_none() {
+        return Ok(None);
+    }
+
+    let names = script
+        .timing
+        .id
+        .iter()
+        .cloned()
+        .chain([format!("line-{}", script.number)]);
+    for name in names {
+        for extension in &config.extensions {
+            let path = dir.join(format!("{}.{}", name, extension));
+            if path.is_file() {
+                return Ok(Some(path.to_string_lossy().to_string()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// 録音を取り込み、行の音声 `output` にする
+/// 合成した音声と同じく、プロジェクトの形式に変換して前後の無音を整える
+pub fn import(
+    path: &str,
+    output: &str,
+    format: &AudioFormat,
+    trim: &TrimConfig,
+) -> Result<Synthesized, io::Error> {
+    let converted = ingest::ingest(path, format)?;
+    if trim.enabled {
+        trim::trim_file(&converted, output, trim)?;
+    } else {
+        fs::copy(&converted, output)?;
+    }
+
+    Ok(Synthesized {
+        path: output.to_string(),
+        duration: probe::duration(output)?,
+        // 録音には読み上げの時刻がないため、字幕は行全体で出す
+        timepoints: Vec::new(),
+    })
+}
+
+/// 通しの録音から切り出した1行分の区間
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TakeSegment {
+    /// 脚本ファイル上の行番号
+    pub line: usize,
+    pub id: Option<String>,
+    /// 録音の先頭からの声の開始時刻（秒）
+    pub start: f64,
+    /// 録音の先頭からの声の終了時刻（秒）
+    pub end: f64,
+    /// 設定で開始位置を指定した行かどうか
+    pub pinned: bool,
+    /// 切り出した音声のパス
+    pub path: String,
+}
+
+/// 通しの録音を無音の位置で `lines` の各行に分け、行ごとの音声を `clip_dir` に切り出す
+/// 切り出した音声は `import()` で行ごとの録音と同じように取り込む
+pub fn split_take(
+    path: &str,
+    lines: &[&ScriptLine],
+    config: &SplitConfig,
+    format: &AudioFormat,
+    clip_dir: &str,
+) -> Result<Vec<TakeSegment>, io::Error> {
+    let converted = ingest::ingest(path, format)?;
+    let (info, samples) = wav::read_samples(&converted)?;
+    let silences = align::silences(
+        &samples,
+        info.channels,
+        info.sample_rate,
+        config.threshold_db,
+        Duration::from_millis(config.min_silence_ms),
+    );
+
+    // 行の id または行番号で指定した開始位置
+    let mut pins = Vec::new();
+    let mut used = HashSet::new();
+    for (i, script) in lines.iter().enumerate() {
+        let keys = script.timing.id.iter().cloned();
+        let keys = keys.chain([script.number.to_string()]);
+        if let Some((key, seconds)) = keys
+            .filter_map(|key| config.pins.get(&key).map(|s| (key, *s)))
+            .next()
+        {
+            pins.push((i, Duration::from_secs_f64(seconds.max(0.0))));
+            used.insert(key);
+        }
+    }
+    for key in config.pins.keys().filter(|key| !used.contains(*key)) {
+        warn!("{}: pin `{}` does not match any recorded line", path, key);
+    }
+
+    let segments = align::align(&silences, info.duration(), lines.len(), &pins)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+
+    let channels = info.channels as usize;
+    let frames = samples.len() / channels;
+    let frame = |time: Duration| {
+        ((time.as_secs_f64() * info.sample_rate as f64).round() as usize).min(frames)
+    };
+    let padding = Duration::from_millis(config.padding_ms);
+    let mut result = Vec::new();
+    for (i, ((start, end), script)) in segments.into_iter().zip(lines).enumerate() {
+        let output = format!("{}/take-line-{}.wav", clip_dir, script.number);
+        let (from, to) = (frame(start.saturating_sub(padding)), frame(end + padding));
+        ingest::write(&output, format, &samples[from * channels..to * channels])?;
+        result.push(TakeSegment {
+            line: script.number,
+            id: script.timing.id.clone(),
+            start: start.as_secs_f64(),
+            end: end.as_secs_f64(),
+            pinned: pins.iter().any(|(pinned, _)| *pinned == i),
+            path: output,
+        });
+    }
+    Ok(result)
+}
+
+/// 通しの録音で続けて読んだ行の間隔（秒）。キーは行番号、値は直前に読んだ行の行番号と間隔
+/// 録音上の声と声の間隔から、取り込んだ音声の前後に付く余白（`trim` が有効ならその余白、
+/// 無効なら切り出しの余白）を除き、並べたときに声の間隔が録音と同じになるようにする
+pub fn take_gaps(
+    segments: &[TakeSegment],
+    split: &SplitConfig,
+    trim: &TrimConfig,
+) -> HashMap<usize, (usize, f64)> {
+    let (head, tail) = match trim.enabled {
+        true => (trim.head_padding_ms, trim.tail_padding_ms),
+        false => (split.padding_ms, split.padding_ms),
+    };
+    let padding = (head + tail) as f64 / 1000.0;
+    segments
+        .windows(2)
+        .map(|pair| {
+            let (prev, next) = (&pair[0], &pair[1]);
+            (next.line, (prev.line, next.start - prev.end - padding))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::wav;
+    use std::time::Duration;
+
+    fn setup(name: &str) -> (std::path::PathBuf, RecordingConfig) {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let config = RecordingConfig {
+            dir: dir.to_string_lossy().to_string(),
+            ..RecordingConfig::default()
+        };
+        (dir, config)
+    }
+
+    #[test]
+    fn test_find() {
+        let (dir, config) = setup("auto-mv-recording-find");
+        for file in ["intro.mp3", "intro.wav", "line-3.flac", "take.wav"] {
+            fs::write(dir.join(file), b"").unwrap();
+        }
+        let found = |line: &str, number: usize| {
+            let script = ScriptLine::parse(number, line).unwrap();
+            find(&config, &script).map(|p| {
+                p.map(|p| {
+                    Path::new(&p)
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+            })
+        };
+
+        // id の次に行番号で探し、拡張子は設定の順に優先する
+        assert_eq!(
+            found("{id=intro} ようこそ", 3).unwrap().as_deref(),
+            Some("intro.wav")
+        );
+        assert_eq!(
+            found("{id=q1} 質問です", 3).unwrap().as_deref(),
+            Some("line-3.flac")
+        );
+        assert_eq!(found("質問です", 4).unwrap(), None);
+        // 字幕だけの行は合成しないため録音も探さない
+        assert_eq!(found("{only=display} 質問です", 3).unwrap(), None);
+
+        assert_eq!(
+            found("{rec=take.wav} 質問です", 4).unwrap().as_deref(),
+            Some("take.wav")
+        );
+        assert!(found("{rec=missing.wav} 質問です", 4).is_err());
+    }
+
+    #[test]
+    fn test_import() {
+        let (dir, _) = setup("auto-mv-recording-import");
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        // 前後に 0.5 秒ずつ無音のある 1 秒の 48kHz モノラル録音
+        let mut samples = vec![0i16; 24000];
+        samples.extend((0..48000).map(|i| if i % 2 == 0 { 8000 } else { -8000 }));
+        samples.extend([0i16; 24000]);
+        wav::write_wav(&path("take.wav"), 48000, 1, &samples).unwrap();
+
+        let format = AudioFormat {
+            cache_dir: path("converted"),
+            ..AudioFormat::default()
+        };
+        let trim = TrimConfig {
+            head_padding_ms: 0,
+            tail_padding_ms: 0,
+            ..TrimConfig::default()
+        };
+        let imported = import(&path("take.wav"), &path("line-1.wav"), &format, &trim).unwrap();
+        assert_eq!(imported.path, path("line-1.wav"));
+        assert!(imported.timepoints.is_empty());
+        // 無音を取り除いた声の長さが行の長さになる
+        assert!((imported.duration.as_secs_f64() - 1.0).abs() < 0.001);
+
+        let (info, _) = wav::read_samples(&imported.path).unwrap();
+        assert_eq!((info.sample_rate, info.channels), (48000, 2));
+
+        let untrimmed = TrimConfig {
+            enabled: false,
+            ..trim
+        };
+        let imported = import(&path("take.wav"), &path("line-2.wav"), &format, &untrimmed).unwrap();
+        assert_eq!(imported.duration, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_split_take() {
+        let (dir, _) = setup("auto-mv-recording-take");
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        // 8kHz で 声 1秒・無音 0.5秒・声 1秒・無音 1秒・声 0.5秒
+        let tone = |seconds: f64| {
+            (0..(8000.0 * seconds) as usize).map(|i| if i % 2 == 0 { 8000i16 } else { -8000 })
+        };
+        let silence = |seconds: f64| std::iter::repeat_n(0i16, (8000.0 * seconds) as usize);
+        let samples: Vec<i16> = tone(1.0)
+            .chain(silence(0.5))
+            .chain(tone(1.0))
+            .chain(silence(1.0))
+            .chain(tone(0.5))
+            .collect();
+        wav::write_wav(&path("take.wav"), 8000, 1, &samples).unwrap();
+
+        let scripts = [
+            ScriptLine::parse(1, "{id=a} 一行目").unwrap(),
+            ScriptLine::parse(2, "二行目").unwrap(),
+            ScriptLine::parse(3, "三行目").unwrap(),
+        ];
+        let lines: Vec<&ScriptLine> = scripts.iter().collect();
+        let format = AudioFormat {
+            sample_rate: 8000,
+            channels: 1,
+            cache_dir: path("converted"),
+            ..AudioFormat::default()
+        };
+        let config = SplitConfig {
+            padding_ms: 0,
+            ..SplitConfig::default()
+        };
+        let segments = split_take(&path("take.wav"), &lines, &config, &format, &path("")).unwrap();
+        let times: Vec<(usize, f64, f64)> =
+            segments.iter().map(|s| (s.line, s.start, s.end)).collect();
+        assert_eq!(times, vec![(1, 0.0, 1.0), (2, 1.5, 2.5), (3, 3.5, 4.0)]);
+        assert_eq!(
+            probe::duration(&segments[1].path).unwrap(),
+            Duration::from_secs(1)
+        );
+        assert_eq!(segments[0].id.as_deref(), Some("a"));
+
+        // 並べる間隔は録音上の間隔から取り込んだ音声の余白を除いたもの
+        let gaps = take_gaps(&segments, &config, &TrimConfig::default());
+        assert_eq!(gaps.len(), 2);
+        for (line, expected) in [(2, (1, 0.35)), (3, (2, 0.85))] {
+            let (previous, gap) = gaps[&line];
+            assert_eq!(previous, expected.0);
+            assert!((gap - expected.1).abs() < 1e-9, "{}", gap);
+        }
+        let untrimmed = TrimConfig {
+            enabled: false,
+            ..TrimConfig::default()
+        };
+        assert_eq!(take_gaps(&segments, &config, &untrimmed)[&2], (1, 0.5));
+
+        // 2行だけなら長い無音で区切り、指定があればその位置で区切る
+        let segments =
+            split_take(&path("take.wav"), &lines[..2], &config, &format, &path("")).unwrap();
+        assert_eq!((segments[0].end, segments[1].start), (2.5, 3.5));
+        let pinned = SplitConfig {
+            pins: [("2".to_string(), 1.2)].into(),
+            ..config.clone()
+        };
+        let segments =
+            split_take(&path("take.wav"), &lines[..2], &pinned, &format, &path("")).unwrap();
+        assert_eq!((segments[0].end, segments[1].start), (1.0, 1.2));
+        assert!(segments[1].pinned);
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use log::warn;
+use tokio::sync::Mutex;
+
+use super::{SpeechEngine, SynthesisRequest, Synthesized, VoiceInfo};
+use crate::models::config::RetryConfig;
+
+/// 失敗した合成を指数バックオフで再試行し、呼び出し頻度を制限するエンジン
+pub struct RetryEngine {
+    inner: Box<dyn SpeechEngine>,
+    config: RetryConfig,
+    limiter: Option<RateLimiter>,
+}
+
+impl RetryEngine {
+    pub fn new(
+        inner: Box<dyn SpeechEngine>,
+        config: RetryConfig,
+        requests_per_minute: Option<u32>,
+    ) -> Self {
+        RetryEngine {
+            inner,
+            config,
+            limiter: requests_per_minute.map(RateLimiter::per_minute),
+        }
+    }
+}
+
+/// 再試行しても結果が変わらないエラーかどうか
+fn is_permanent(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::InvalidInput
+            | io::ErrorKind::InvalidData
+            | io::ErrorKind::NotFound
+            | io::ErrorKind::PermissionDenied
+            | io::ErrorKind::Unsupported
+    )
+}
+
+#[async_trait]
+impl SpeechEngine for RetryEngine {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn supports_ssml(&self) -> bool {
+        self.inner.supports_ssml()
+    }
+
+    fn supports_prosody(&self) -> bool {
+        self.inner.supports_prosody()
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        let mut attempt = 1;
+        loop {
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire().await;
+            }
+            match self.inner.synthesize(request).await {
+                Ok(synthesized) => return Ok(synthesized),
+                Err(e) if attempt >= self.config.max_attempts || is_permanent(&e) => return Err(e),
+                Err(e) => {
+                    let backoff = self.config.backoff(attempt);
+                    warn!(
+                        "{}: attempt {}/{} failed: {}; retrying in {:?}",
+                        self.inner.name(),
+                        attempt,
+                        self.config.max_attempts,
+                        e,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        self.inner.list_voices(lang).await
+    }
+}
+
+/// 一定間隔より短い間隔で呼び出さないようにする
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn per_minute(requests: u32) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs(60) / requests.max(1),
+            next: Mutex::new(None),
+        }
+    }
+
+    /// 次の呼び出しが許可されるまで待つ
+    pub async fn acquire(&self) {
+        let mut next = self.next.lock().await;
+        let now = Instant::now();
+        let at = next.map_or(now, |next| next.max(now));
+        *next = Some(at + self.interval);
+        drop(next);
+        tokio::time::sleep(at - now).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::config::FakeConfig, speech::fake::FakeEngine};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// 指定回数だけ失敗するエンジン
+    struct Flaky {
+        failures: usize,
+        kind: io::ErrorKind,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl SpeechEngine for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(io::Error::new(self.kind, "unavailable"));
+            }
+            FakeEngine::new(FakeConfig::default())
+                .synthesize(request)
+                .await
+        }
+
+        async fn list_voices(&self, _lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+            Ok(vec![])
+        }
+    }
+
+    fn engine(failures: usize, kind: io::ErrorKind) -> (RetryEngine, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let config = RetryConfig {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 2,
+            multiplier: 2.0,
+        };
+        let flaky = Flaky {
+            failures,
+            kind,
+            calls: calls.clone(),
+        };
+        (RetryEngine::new(Box::new(flaky), config, None), calls)
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let output = std::env::temp_dir().join("auto-mv-retry.wav");
+        let request = SynthesisRequest::new("あ", output.to_str().unwrap(), "ja-JP", "A");
+
+        let (retry, calls) = engine(2, io::ErrorKind::Other);
+        assert!(retry.synthesize(&request).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let (retry, calls) = engine(3, io::ErrorKind::Other);
+        assert!(retry.synthesize(&request).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // 入力の誤りは再試行しない
+        let (retry, calls) = engine(1, io::ErrorKind::InvalidInput);
+        assert!(retry.synthesize(&request).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = RetryConfig {
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 3000,
+            multiplier: 2.0,
+        };
+        assert_eq!(config.backoff(1), Duration::from_millis(500));
+        assert_eq!(config.backoff(2), Duration::from_millis(1000));
+        assert_eq!(config.backoff(3), Duration::from_millis(2000));
+        assert_eq!(config.backoff(4), Duration::from_millis(3000));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter() {
+        // 6000 回/分 = 10ms 間隔
+        let limiter = RateLimiter::per_minute(6000);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,597 @@
+use std::{fmt::Write, io};
+
+use super::{
+    lexicon::{Segment, Substitute, Substitution},
+    timepoint,
+};
+
+/// 脚本中のマークアップを解析した結果
+///
+/// 記法（角括弧のタグ。未知のタグはそのまま文字として扱う）
+/// - `[pause 500ms]` `[pause 1.5s]` `[pause strong]`: 間を空ける
+/// - `[emphasis]...[/emphasis]` `[emphasis strong]...[/emphasis]`: 強調
+/// - `[say-as date format=ymd]2024/10/13[/say-as]`: 読み方の指定
+///   (cardinal, ordinal, characters, date, time, telephone など)
+/// - `[sub とうきょう]東京[/sub]`: 読みの差し替え（字幕は元の表記のまま）
+/// - `[prosody rate=slow pitch=+2st]...[/prosody]`: 速度・高さ・音量
+/// - `[mark 名前]`: 読み上げられた時刻をエンジンから受け取る位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct Markup {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Break(String),
+    Mark(String),
+    Element(Element, Vec<Node>),
+}
+
+/// 子要素を持つタグ
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    Emphasis(String),
+    SayAs {
+        interpret_as: String,
+        format: Option<String>,
+        detail: Option<String>,
+    },
+    Sub(String),
+    Prosody(Vec<(String, String)>),
+}
+
+impl Element {
+    fn tag(&self) -> &'static str {
+        match self {
+            Element::Emphasis(_) => "emphasis",
+            Element::SayAs { .. } => "say-as",
+            Element::Sub(_) => "sub",
+            Element::Prosody(_) => "prosody",
+        }
+    }
+}
+
+const SAY_AS: [&str; 11] = [
+    "cardinal",
+    "number",
+    "ordinal",
+    "characters",
+    "spell-out",
+    "fraction",
+    "unit",
+    "verbatim",
+    "date",
+    "time",
+    "telephone",
+];
+const EMPHASIS: [&str; 4] = ["strong", "moderate", "reduced", "none"];
+const STRENGTH: [&str; 6] = ["none", "x-weak", "weak", "medium", "strong", "x-strong"];
+const PROSODY: [&str; 3] = ["rate", "pitch", "volume"];
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+impl Markup {
+    /// 脚本の1行を解析する
+    pub fn parse(line: &str) -> Result<Self, io::Error> {
+        // 開いているタグと、その子要素
+        let mut stack: Vec<(Element, Vec<Node>)> = Vec::new();
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut text = String::new();
+        let mut rest = line;
+
+        while let Some(open) = rest.find('[') {
+            let Some(close) = rest[open..].find(']').map(|i| open + i) else {
+                break;
+            };
+            let inner = rest[open + 1..close].trim();
+
+            let parsed = parse_tag(inner)?;
+            if parsed.is_none() {
+                // 未知のタグは文字として残す
+                text.push_str(&rest[..=close]);
+                rest = &rest[close + 1..];
+                continue;
+            }
+
+            text.push_str(&rest[..open]);
+            rest = &rest[close + 1..];
+            let current = stack.last_mut().map(|(_, c)| c).unwrap_or(&mut nodes);
+            flush(&mut text, current);
+
+            match parsed.unwrap() {
+                Tag::Break(value) => current.push(Node::Break(value)),
+                Tag::Mark(name) => current.push(Node::Mark(name)),
+                Tag::Open(element) => stack.push((element, Vec::new())),
+                Tag::Close(name) => {
+                    let (element, children) = stack
+                        .pop()
+                        .ok_or_else(|| invalid(format!("unexpected [/{}]", name)))?;
+                    if element.tag() != name {
+                        return Err(invalid(format!(
+                            "[/{}] does not match [{}]",
+                            name,
+                            element.tag()
+                        )));
+                    }
+                    if matches!(element, Element::Sub(_))
+                        && children.iter().any(|c| !matches!(c, Node::Text(_)))
+                    {
+                        return Err(invalid("[sub] can only contain text".to_string()));
+                    }
+                    let node = Node::Element(element, children);
+                    stack
+                        .last_mut()
+                        .map(|(_, c)| c)
+                        .unwrap_or(&mut nodes)
+                        .push(node);
+                }
+            }
+        }
+
+        text.push_str(rest);
+        if let Some((element, _)) = stack.last() {
+            return Err(invalid(format!("[{}] is not closed", element.tag())));
+        }
+        flush(&mut text, &mut nodes);
+
+        Ok(Markup { nodes })
+    }
+
+    /// タグを含むかどうか
+    pub fn has_markup(&self) -> bool {
+        self.nodes.iter().any(|n| !matches!(n, Node::Text(_)))
+    }
+
+    /// SSML 文書を生成する
+    pub fn to_ssml(&self) -> String {
+        let mut out = String::from("<speak>");
+        write_ssml(&self.nodes, &mut out);
+        out.push_str("</speak>");
+        out
+    }
+
+    /// 字幕用のテキスト（タグを除き、元の表記を残す）
+    pub fn display_text(&self) -> String {
+        let mut out = String::new();
+        write_plain(&self.nodes, false, &mut out);
+        out
+    }
+
+    /// SSML に対応しないエンジン用の読み上げテキスト（`sub` は読みに置き換える）
+    pub fn spoken_text(&self) -> String {
+        let mut out = String::new();
+        write_plain(&self.nodes, true, &mut out);
+        out
+    }
+
+    /// 句読点の区切りごとに `timepoint::clause_mark` の mark を挿入する
+    /// 戻り値は挿入した数。`sub` と `say-as` の中と行末には挿入しない
+    pub fn mark_clauses(&mut self) -> usize {
+        let mut count = 0;
+        mark_clauses(&mut self.nodes, &mut count);
+        // 行末の区切りは時刻を取っても字幕を区切れないため除く
+        if count > 0 && pop_trailing_mark(&mut self.nodes, &timepoint::clause_mark(count)) {
+            count -= 1;
+        }
+        count
+    }
+
+    /// mark の名前と、字幕用のテキスト中の位置（文字数）
+    pub fn marks(&self) -> Vec<(String, usize)> {
+        let mut marks = Vec::new();
+        let mut offset = 0;
+        collect_marks(&self.nodes, &mut offset, &mut marks);
+        marks
+    }
+
+    /// 発音辞書やテキスト正規化を適用し、一致した部分を `sub` に置き換える
+    /// 脚本で明示した `sub` と `say-as` の中は変更しない
+    pub fn substitute(&mut self, rules: &dyn Substitute) -> Vec<Substitution> {
+        let mut substitutions = Vec::new();
+        substitute(&mut self.nodes, rules, &mut substitutions);
+        substitutions
+    }
+}
+
+fn mark_clauses(nodes: &mut Vec<Node>, count: &mut usize) {
+    for node in std::mem::take(nodes) {
+        match node {
+            Node::Text(text) => {
+                let chars: Vec<char> = text.chars().collect();
+                let mut start = 0;
+                // テキストの末尾の区切りにも挿入する（行末のものは後で除く）
+                for end in timepoint::clause_ends(&text) {
+                    nodes.push(Node::Text(chars[start..end].iter().collect()));
+                    *count += 1;
+                    nodes.push(Node::Mark(timepoint::clause_mark(*count)));
+                    start = end;
+                }
+                flush(&mut chars[start..].iter().collect(), nodes);
+            }
+            Node::Element(element, mut children)
+                if !matches!(element, Element::Sub(_) | Element::SayAs { .. }) =>
+            {
+                mark_clauses(&mut children, count);
+                nodes.push(Node::Element(element, children));
+            }
+            node => nodes.push(node),
+        }
+    }
+}
+
+/// 末尾（要素の中の末尾を含む）が指定の mark なら取り除く
+fn pop_trailing_mark(nodes: &mut Vec<Node>, name: &str) -> bool {
+    match nodes.last_mut() {
+        Some(Node::Mark(mark)) if mark == name => {
+            nodes.pop();
+            true
+        }
+        Some(Node::Element(_, children)) => pop_trailing_mark(children, name),
+        _ => false,
+    }
+}
+
+fn collect_marks(nodes: &[Node], offset: &mut usize, marks: &mut Vec<(String, usize)>) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => *offset += text.chars().count(),
+            Node::Break(_) => {}
+            Node::Mark(name) => marks.push((name.clone(), *offset)),
+            Node::Element(_, children) => collect_marks(children, offset, marks),
+        }
+    }
+}
+
+fn substitute(
+    nodes: &mut Vec<Node>,
+    rules: &dyn Substitute,
+    substitutions: &mut Vec<Substitution>,
+) {
+    for node in std::mem::take(nodes) {
+        match node {
+            Node::Text(text) => {
+                for segment in rules.apply(&text) {
+                    match segment {
+                        Segment::Text(text) => nodes.push(Node::Text(text)),
+                        Segment::Sub(substitution) => {
+                            nodes.push(Node::Element(
+                                Element::Sub(substitution.reading.clone()),
+                                vec![Node::Text(substitution.surface.clone())],
+                            ));
+                            substitutions.push(substitution);
+                        }
+                    }
+                }
+            }
+            Node::Element(element, mut children)
+                if !matches!(element, Element::Sub(_) | Element::SayAs { .. }) =>
+            {
+                substitute(&mut children, rules, substitutions);
+                nodes.push(Node::Element(element, children));
+            }
+            node => nodes.push(node),
+        }
+    }
+}
+
+enum Tag {
+    Break(String),
+    Mark(String),
+    Open(Element),
+    Close(String),
+}
+
+/// タグの中身を解析する。既知のタグでなければ None
+fn parse_tag(inner: &str) -> Result<Option<Tag>, io::Error> {
+    if let Some(name) = inner.strip_prefix('/') {
+        let name = name.trim();
+        return Ok(matches!(name, "emphasis" | "say-as" | "sub" | "prosody")
+            .then(|| Tag::Close(name.to_string())));
+    }
+
+    let mut parts = inner.split_whitespace();
+    let Some(name) = parts.next() else {
+        return Ok(None);
+    };
+    let args: Vec<&str> = parts.collect();
+    let positional: Vec<&str> = args.iter().filter(|a| !a.contains('=')).copied().collect();
+    let named = |key: &str| {
+        args.iter()
+            .find_map(|a| a.strip_prefix(key).and_then(|v| v.strip_prefix('=')))
+            .map(|v| v.to_string())
+    };
+
+    let tag = match name {
+        "pause" | "break" => {
+            let value = positional.first().copied().unwrap_or("medium");
+            if !STRENGTH.contains(&value) && parse_time(value).is_none() {
+                return Err(invalid(format!("invalid pause '{}'", value)));
+            }
+            Tag::Break(value.to_string())
+        }
+        "mark" => {
+            let name = positional
+                .first()
+                .copied()
+                .filter(|_| positional.len() == 1);
+            let name = name.ok_or_else(|| invalid("[mark] requires one name".to_string()))?;
+            Tag::Mark(name.to_string())
+        }
+        "emphasis" => {
+            let level = positional.first().copied().unwrap_or("moderate");
+            if !EMPHASIS.contains(&level) {
+                return Err(invalid(format!("invalid emphasis level '{}'", level)));
+            }
+            Tag::Open(Element::Emphasis(level.to_string()))
+        }
+        "say-as" => {
+            let interpret_as = positional
+                .first()
+                .copied()
+                .ok_or_else(|| invalid("[say-as] requires a type".to_string()))?;
+            if !SAY_AS.contains(&interpret_as) {
+                return Err(invalid(format!("invalid say-as type '{}'", interpret_as)));
+            }
+            Tag::Open(Element::SayAs {
+                interpret_as: interpret_as.to_string(),
+                format: named("format"),
+                detail: named("detail"),
+            })
+        }
+        "sub" => {
+            let alias = positional.join(" ");
+            if alias.is_empty() {
+                return Err(invalid("[sub] requires an alias".to_string()));
+            }
+            Tag::Open(Element::Sub(alias))
+        }
+        "prosody" => {
+            let mut attrs = Vec::new();
+            for arg in &args {
+                let (key, value) = arg
+                    .split_once('=')
+                    .ok_or_else(|| invalid(format!("invalid prosody attribute '{}'", arg)))?;
+                if !PROSODY.contains(&key) {
+                    return Err(invalid(format!("unknown prosody attribute '{}'", key)));
+                }
+                attrs.push((key.to_string(), value.to_string()));
+            }
+            if attrs.is_empty() {
+                return Err(invalid(
+                    "[prosody] requires rate, pitch or volume".to_string(),
+                ));
+            }
+            Tag::Open(Element::Prosody(attrs))
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(tag))
+}
+
+/// "500ms" や "1.5s" を SSML の time 値として検証する
+fn parse_time(value: &str) -> Option<f64> {
+    let number = value
+        .strip_suffix("ms")
+        .or_else(|| value.strip_suffix('s'))?;
+    number.parse::<f64>().ok().filter(|n| *n >= 0.0)
+}
+
+fn flush(text: &mut String, nodes: &mut Vec<Node>) {
+    if !text.is_empty() {
+        nodes.push(Node::Text(std::mem::take(text)));
+    }
+}
+
+/// XML の特殊文字をエスケープする
+pub fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn write_ssml(nodes: &[Node], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(&escape_xml(text)),
+            Node::Break(value) => {
+                if parse_time(value).is_some() {
+                    let _ = write!(out, "<break time=\"{}\"/>", value);
+                } else {
+                    let _ = write!(out, "<break strength=\"{}\"/>", value);
+                }
+            }
+            Node::Mark(name) => {
+                let _ = write!(out, "<mark name=\"{}\"/>", escape_xml(name));
+            }
+            Node::Element(element, children) => {
+                match element {
+                    Element::Emphasis(level) => {
+                        let _ = write!(out, "<emphasis level=\"{}\">", level);
+                    }
+                    Element::SayAs {
+                        interpret_as,
+                        format,
+                        detail,
+                    } => {
+                        let _ = write!(out, "<say-as interpret-as=\"{}\"", interpret_as);
+                        if let Some(format) = format {
+                            let _ = write!(out, " format=\"{}\"", escape_xml(format));
+                        }
+                        if let Some(detail) = detail {
+                            let _ = write!(out, " detail=\"{}\"", escape_xml(detail));
+                        }
+                        out.push('>');
+                    }
+                    Element::Sub(alias) => {
+                        let _ = write!(out, "<sub alias=\"{}\">", escape_xml(alias));
+                    }
+                    Element::Prosody(attrs) => {
+                        out.push_str("<prosody");
+                        for (key, value) in attrs {
+                            let _ = write!(out, " {}=\"{}\"", key, escape_xml(value));
+                        }
+                        out.push('>');
+                    }
+                }
+                write_ssml(children, out);
+                let _ = write!(out, "</{}>", element.tag());
+            }
+        }
+    }
+}
+
+fn write_plain(nodes: &[Node], spoken: bool, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Break(_) | Node::Mark(_) => {}
+            Node::Element(Element::Sub(alias), _) if spoken => out.push_str(alias),
+            Node::Element(_, children) => write_plain(children, spoken, out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text() {
+        let markup = Markup::parse("月明かりの下、キャンバスに願いを描く。").unwrap();
+        assert!(!markup.has_markup());
+        assert_eq!(
+            markup.to_ssml(),
+            "<speak>月明かりの下、キャンバスに願いを描く。</speak>"
+        );
+        assert_eq!(
+            markup.display_text(),
+            "月明かりの下、キャンバスに願いを描く。"
+        );
+    }
+
+    #[test]
+    fn test_to_ssml() {
+        let markup = Markup::parse(
+            "[say-as date format=ymd]2024/10/13[/say-as]に[sub とうきょう]東京[/sub]で[pause 500ms][emphasis strong]公開[/emphasis]",
+        )
+        .unwrap();
+
+        assert!(markup.has_markup());
+        assert_eq!(
+            markup.to_ssml(),
+            "<speak><say-as interpret-as=\"date\" format=\"ymd\">2024/10/13</say-as>に<sub alias=\"とうきょう\">東京</sub>で<break time=\"500ms\"/><emphasis level=\"strong\">公開</emphasis></speak>"
+        );
+        assert_eq!(markup.display_text(), "2024/10/13に東京で公開");
+        assert_eq!(markup.spoken_text(), "2024/10/13にとうきょうで公開");
+    }
+
+    #[test]
+    fn test_nested_prosody() {
+        let markup =
+            Markup::parse("[prosody rate=slow pitch=+2st]ゆっくり[pause][say-as characters]ABC[/say-as][/prosody]")
+                .unwrap();
+
+        assert_eq!(
+            markup.to_ssml(),
+            "<speak><prosody rate=\"slow\" pitch=\"+2st\">ゆっくり<break strength=\"medium\"/><say-as interpret-as=\"characters\">ABC</say-as></prosody></speak>"
+        );
+        assert_eq!(markup.display_text(), "ゆっくりABC");
+    }
+
+    #[test]
+    fn test_escape_and_unknown_tags() {
+        let markup = Markup::parse("[注] A & B <C>").unwrap();
+        assert!(!markup.has_markup());
+        assert_eq!(markup.to_ssml(), "<speak>[注] A &amp; B &lt;C&gt;</speak>");
+        assert_eq!(markup.display_text(), "[注] A & B <C>");
+    }
+
+    #[test]
+    fn test_substitute_lexicon() {
+        use crate::speech::lexicon::Lexicon;
+
+        let lexicon = Lexicon::new(
+            serde_json::from_str(
+                r#"[{"surface": "東京", "reading": "とうきょう"}, {"surface": "行方", "reading": "なめがた"}]"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let mut markup =
+            Markup::parse("行方市から[emphasis]東京[/emphasis]へ[sub ひがしきょう]東京[/sub]")
+                .unwrap();
+
+        let substitutions = markup.substitute(&lexicon);
+        assert_eq!(
+            substitutions
+                .iter()
+                .map(|s| (s.surface.as_str(), s.reading.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("行方", "なめがた"), ("東京", "とうきょう")]
+        );
+        assert_eq!(markup.display_text(), "行方市から東京へ東京");
+        assert_eq!(
+            markup.spoken_text(),
+            "なめがた市からとうきょうへひがしきょう"
+        );
+        assert_eq!(
+            markup.to_ssml(),
+            "<speak><sub alias=\"なめがた\">行方</sub>市から<emphasis level=\"moderate\"><sub alias=\"とうきょう\">東京</sub></emphasis>へ<sub alias=\"ひがしきょう\">東京</sub></speak>"
+        );
+    }
+
+    #[test]
+    fn test_marks() {
+        let mut markup = Markup::parse(
+            "[mark intro]はい、[sub とうきょう]東京[/sub]です。[emphasis]本当に。[/emphasis]",
+        )
+        .unwrap();
+        assert_eq!(markup.mark_clauses(), 2);
+        assert_eq!(
+            markup.to_ssml(),
+            "<speak><mark name=\"intro\"/>はい、<mark name=\"clause-1\"/><sub alias=\"とうきょう\">東京</sub>です。<mark name=\"clause-2\"/><emphasis level=\"moderate\">本当に。</emphasis></speak>"
+        );
+        assert_eq!(markup.display_text(), "はい、東京です。本当に。");
+        assert_eq!(markup.spoken_text(), "はい、とうきょうです。本当に。");
+        assert_eq!(
+            markup.marks(),
+            vec![
+                ("intro".to_string(), 0),
+                ("clause-1".to_string(), 3),
+                ("clause-2".to_string(), 8),
+            ]
+        );
+
+        // 行末の mark は脚本で指定したものなら残す
+        let mut markup = Markup::parse("はい。[mark end]").unwrap();
+        assert_eq!(markup.mark_clauses(), 1);
+        assert_eq!(markup.marks()[1], ("end".to_string(), 3));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Markup::parse("[emphasis]閉じていない").is_err());
+        assert!(Markup::parse("[emphasis]a[/prosody]").is_err());
+        assert!(Markup::parse("a[/sub]").is_err());
+        assert!(Markup::parse("[pause soon]").is_err());
+        assert!(Markup::parse("[say-as]1[/say-as]").is_err());
+        assert!(Markup::parse("[say-as color]1[/say-as]").is_err());
+        assert!(Markup::parse("[sub]a[/sub]").is_err());
+        assert!(Markup::parse("[sub a][emphasis]b[/emphasis][/sub]").is_err());
+        assert!(Markup::parse("[prosody speed=2]a[/prosody]").is_err());
+        assert!(Markup::parse("[mark]").is_err());
+        assert!(Markup::parse("[mark a b]").is_err());
+    }
+}
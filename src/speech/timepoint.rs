@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+/// 音声中で特定の位置が読み上げられた時刻
+/// SSML の `<mark>` や、エンジンが返す区切りの時刻を音声の先頭からの経過時間で持つ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timepoint {
+    pub name: String,
+    pub time: Duration,
+}
+
+impl Timepoint {
+    pub fn new(name: &str, time: Duration) -> Self {
+        Timepoint {
+            name: name.to_string(),
+            time,
+        }
+    }
+}
+
+/// 句読点の区切りに自動で挿入する mark の名前（1始まり）
+/// SSML に対応しないエンジンも、この名前で k 番目の区切りの時刻を返す
+pub fn clause_mark(index: usize) -> String {
+    format!("clause-{}", index)
+}
+
+/// 句読点の区切りの位置（文字数）。句読点と続く閉じ括弧・空白の直後を返す
+pub fn clause_ends(text: &str) -> Vec<usize> {
+    let chars: Vec<char> = text.chars().collect();
+    let is_clause = |i: usize| match chars[i] {
+        '、' | '。' | '，' | '．' | '！' | '？' | '!' | '?' => true,
+        // 小数点などと区別するため、英語の , . は直後が空白か末尾の場合のみ
+        ',' | '.' => chars.get(i + 1).is_none_or(|c| c.is_whitespace()),
+        _ => false,
+    };
+    let is_trailing = |c: char| c.is_whitespace() || "」』）)】\"'".contains(c);
+
+    let mut ends = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !is_clause(i) {
+            i += 1;
+            continue;
+        }
+        while i < chars.len() && (is_clause(i) || is_trailing(chars[i])) {
+            i += 1;
+        }
+        ends.push(i);
+    }
+    ends
+}
+
+/// 時刻を加工する（速度の変更や無音の除去に合わせる）
+pub fn map_times(timepoints: &[Timepoint], f: impl Fn(Duration) -> Duration) -> Vec<Timepoint> {
+    timepoints
+        .iter()
+        .map(|t| Timepoint {
+            name: t.name.clone(),
+            time: f(t.time),
+        })
+        .collect()
+}
+
+/// キャッシュに保存する形式（1行に「ナノ秒<TAB>名前」）
+pub fn to_lines(timepoints: &[Timepoint]) -> String {
+    timepoints
+        .iter()
+        .map(|t| format!("{}\t{}\n", t.time.as_nanos(), t.name))
+        .collect()
+}
+
+/// `to_lines` の形式を読み込む。解釈できない行は無視する
+pub fn from_lines(content: &str) -> Vec<Timepoint> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (nanos, name) = line.split_once('\t')?;
+            Some(Timepoint {
+                name: name.to_string(),
+                time: Duration::from_nanos(nanos.parse().ok()?),
+            })
+        })
+        .collect()
+}
+
+/// 字幕の一区間（行の音声の先頭からの時刻）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caption {
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+}
+
+/// 字幕の表記を mark の位置で区切り、各区間に読み上げの時刻を割り当てる
+/// `marks` は mark の名前と表記中の位置（文字数）。時刻が分からない mark や
+/// 時刻が前後する mark では区切らない
+pub fn split_text(
+    text: &str,
+    marks: &[(String, usize)],
+    timepoints: &[Timepoint],
+    duration: Duration,
+) -> Vec<Caption> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut points: Vec<(usize, Duration)> = marks
+        .iter()
+        .filter_map(|(name, offset)| {
+            let time = timepoints.iter().find(|t| &t.name == name)?.time;
+            Some((*offset, time.min(duration)))
+        })
+        .filter(|(offset, _)| *offset > 0 && *offset < chars.len())
+        .collect();
+    points.sort();
+
+    let mut bounds = vec![(0, Duration::ZERO)];
+    for point in points {
+        let last = bounds.last().unwrap();
+        if point.0 > last.0 && point.1 > last.1 {
+            bounds.push(point);
+        }
+    }
+    bounds.push((chars.len(), duration));
+
+    let mut captions: Vec<Caption> = Vec::new();
+    for pair in bounds.windows(2) {
+        let text: String = chars[pair[0].0..pair[1].0].iter().collect();
+        match captions.last_mut() {
+            // 空白だけの区間は前の区間に含める
+            Some(last) if text.trim().is_empty() => {
+                last.text.push_str(&text);
+                last.end = pair[1].1;
+            }
+            _ => captions.push(Caption {
+                start: pair[0].1,
+                end: pair[1].1,
+                text,
+            }),
+        }
+    }
+    captions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_lines_roundtrip() {
+        let timepoints = vec![
+            Timepoint::new("clause-1", ms(1200)),
+            Timepoint::new("intro", ms(50)),
+        ];
+        assert_eq!(from_lines(&to_lines(&timepoints)), timepoints);
+        assert_eq!(
+            from_lines("broken\n12\tok\n"),
+            vec![Timepoint::new("ok", Duration::from_nanos(12))]
+        );
+    }
+
+    #[test]
+    fn test_clause_ends() {
+        assert_eq!(
+            clause_ends("はい、そうです。「ええ。」本当に！？"),
+            vec![3, 8, 13, 18]
+        );
+        assert_eq!(clause_ends("Yes, 3.5 points. Done."), vec![5, 17, 22]);
+        assert!(clause_ends("区切りなし").is_empty());
+    }
+
+    #[test]
+    fn test_split_text() {
+        let text = "月明かりの下、キャンバスに願いを描く。";
+        let marks = vec![("clause-1".to_string(), 7), ("unknown".to_string(), 10)];
+        let timepoints = vec![Timepoint::new("clause-1", ms(800))];
+
+        let captions = split_text(text, &marks, &timepoints, ms(2000));
+        assert_eq!(
+            captions,
+            vec![
+                Caption {
+                    start: ms(0),
+                    end: ms(800),
+                    text: "月明かりの下、".to_string()
+                },
+                Caption {
+                    start: ms(800),
+                    end: ms(2000),
+                    text: "キャンバスに願いを描く。".to_string()
+                },
+            ]
+        );
+
+        // 時刻がなければ1区間のまま
+        assert_eq!(split_text(text, &marks, &[], ms(2000)).len(), 1);
+        // 時刻が前後する mark では区切らない
+        let marks = vec![("a".to_string(), 3), ("b".to_string(), 7)];
+        let timepoints = vec![Timepoint::new("a", ms(900)), Timepoint::new("b", ms(500))];
+        assert_eq!(split_text(text, &marks, &timepoints, ms(2000)).len(), 2);
+    }
+}
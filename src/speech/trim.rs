@@ -0,0 +1,109 @@
+use std::{fs, io};
+
+use async_trait::async_trait;
+use log::warn;
+
+use super::{timepoint, SpeechEngine, SynthesisRequest, Synthesized, VoiceInfo};
+use crate::{audio::trim, models::config::TrimConfig};
+
+/// 合成した音声の前後の無音を整えるエンジン
+/// 字幕の開始・終了が実際に声が聞こえる区間と揃うようにする
+pub struct TrimmedEngine {
+    inner: Box<dyn SpeechEngine>,
+    config: TrimConfig,
+}
+
+impl TrimmedEngine {
+    pub fn new(inner: Box<dyn SpeechEngine>, config: TrimConfig) -> Self {
+        TrimmedEngine { inner, config }
+    }
+}
+
+#[async_trait]
+impl SpeechEngine for TrimmedEngine {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn supports_ssml(&self) -> bool {
+        self.inner.supports_ssml()
+    }
+
+    fn supports_prosody(&self) -> bool {
+        self.inner.supports_prosody()
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        let synthesized = self.inner.synthesize(request).await?;
+
+        let raw = format!("{}.untrimmed.wav", synthesized.path);
+        fs::rename(&synthesized.path, &raw)?;
+        let trimmed = trim::trim_file(&raw, &synthesized.path, &self.config);
+        match trimmed {
+            Ok(trimmed) => {
+                fs::remove_file(&raw)?;
+                // 取り除いた無音の分だけ時刻をずらす
+                let timepoints =
+                    timepoint::map_times(&synthesized.timepoints, |t| trimmed.map_time(t));
+                Ok(Synthesized {
+                    path: synthesized.path,
+                    duration: trimmed.duration,
+                    timepoints,
+                })
+            }
+            Err(e) => {
+                // WAV 以外（MP3 など）はそのまま使う
+                warn!("{}: silence trimming skipped: {}", synthesized.path, e);
+                fs::rename(&raw, &synthesized.path)?;
+                Ok(synthesized)
+            }
+        }
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        self.inner.list_voices(lang).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::config::{FakeConfig, FakeSignal},
+        speech::fake::FakeEngine,
+    };
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_trimmed_engine() {
+        let config = TrimConfig {
+            head_padding_ms: 100,
+            tail_padding_ms: 200,
+            ..TrimConfig::default()
+        };
+        let output = std::env::temp_dir().join("auto-mv-trimmed-engine.wav");
+        let output = output.to_str().unwrap();
+        let request = SynthesisRequest::new("あいう", output, "ja-JP", "A");
+
+        // トーンはほぼ全区間が発話なので、パディングの分だけ長くなる
+        let engine = TrimmedEngine::new(
+            Box::new(FakeEngine::new(FakeConfig::default())),
+            config.clone(),
+        );
+        let synthesized = engine.synthesize(&request).await.unwrap();
+        let expected = Duration::from_millis(500 + 300);
+        assert!(synthesized.duration <= expected);
+        assert!(expected - synthesized.duration < Duration::from_millis(1));
+
+        // 無音はパディングだけが残る
+        let engine = TrimmedEngine::new(
+            Box::new(FakeEngine::new(FakeConfig {
+                signal: FakeSignal::Silence,
+                ..FakeConfig::default()
+            })),
+            config,
+        );
+        let synthesized = engine.synthesize(&request).await.unwrap();
+        assert_eq!(synthesized.duration, Duration::from_millis(300));
+    }
+}
@@ -10,7 +10,11 @@
 // 日本語（日本）	プレミアム	ja-JP	ja-JP-Wavenet-C	男性
 // 日本語（日本）	プレミアム	ja-JP	ja-JP-Wavenet-D 男性
 
-use std::fmt;
+use std::{collections::BTreeSet, fmt, fs, io};
+
+use serde::Deserialize;
+
+use super::{SpeechEngine, VoiceInfo};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Code {
@@ -26,6 +30,12 @@ impl fmt::Display for Code {
     }
 }
 
+impl Default for Code {
+    fn default() -> Self {
+        Code::new()
+    }
+}
+
 impl Code {
     pub fn new() -> Self {
         Code::JaJP
@@ -87,10 +97,238 @@ impl Name {
                 8 => Some(Name::JaJPWavenetB),
                 9 => Some(Name::JaJPWavenetC),
                 10 => Some(Name::JaJPWavenetD),
-                _ => Some(Name::new()),
+                _ => None,
             },
         }
     }
+
+    /// 定義済みの全音声
+    pub fn all() -> Vec<Self> {
+        (0..=10).filter_map(|n| Name::from(Code::JaJP, n)).collect()
+    }
+
+    /// 音声の言語コード
+    pub fn code(&self) -> Code {
+        Code::JaJP
+    }
+
+    /// 音声の性別
+    pub fn gender(&self) -> Gender {
+        match self {
+            Name::JaJPStandardA
+            | Name::JaJPStandardB
+            | Name::JaJPNeural2B
+            | Name::JaJPWavenetA
+            | Name::JaJPWavenetB => Gender::Female,
+            _ => Gender::Male,
+        }
+    }
+}
+
+impl Default for Name {
+    fn default() -> Self {
+        Name::new()
+    }
+}
+
+/// 音声の性別
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Gender {
+    Female,
+    Male,
+    Neutral,
+    #[default]
+    Unspecified,
+}
+
+/// 音声の料金区分
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tier {
+    /// 標準
+    #[default]
+    Standard,
+    /// プレミアム（WaveNet, Neural2 など）
+    Premium,
+}
+
+impl Tier {
+    /// 音声名から料金区分を推定する
+    pub fn from_voice_id(id: &str) -> Self {
+        if id.contains("-Standard-") {
+            Tier::Standard
+        } else {
+            Tier::Premium
+        }
+    }
+}
+
+/// 音声カタログの1件
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Voice {
+    /// 言語コード (例: ja-JP)
+    pub language: String,
+    /// 音声名 (例: ja-JP-Neural2-B)
+    pub id: String,
+    #[serde(default)]
+    pub gender: Gender,
+    #[serde(default)]
+    pub tier: Tier,
+    /// 音声本来のサンプリングレート
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: u32,
+}
+
+fn default_sample_rate() -> u32 {
+    24000
+}
+
+/// 音声の検索条件
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VoiceQuery {
+    pub language: String,
+    pub gender: Option<Gender>,
+    pub tier: Option<Tier>,
+}
+
+/// 音声カタログの検索エラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VoiceError {
+    /// カタログにない音声名
+    UnknownVoice(String),
+    /// カタログにない言語
+    UnknownLanguage(String),
+    /// 条件に合う音声がない
+    NoMatch(String),
+}
+
+impl fmt::Display for VoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VoiceError::UnknownVoice(id) => write!(f, "unknown voice '{}'", id),
+            VoiceError::UnknownLanguage(lang) => write!(f, "no voices for language '{}'", lang),
+            VoiceError::NoMatch(query) => write!(f, "no voice matches {}", query),
+        }
+    }
+}
+
+impl std::error::Error for VoiceError {}
+
+impl From<VoiceError> for io::Error {
+    fn from(e: VoiceError) -> Self {
+        io::Error::new(io::ErrorKind::NotFound, e)
+    }
+}
+
+/// 言語・音声名・性別・区分を持つ音声の一覧
+/// JSON ファイルから読み込むか、エンジンの list_voices から生成する
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct VoiceCatalog {
+    voices: Vec<Voice>,
+}
+
+impl VoiceCatalog {
+    pub fn new(voices: Vec<Voice>) -> Self {
+        VoiceCatalog { voices }
+    }
+
+    /// 組み込みの ja-JP 音声
+    pub fn builtin() -> Self {
+        VoiceCatalog::new(
+            Name::all()
+                .into_iter()
+                .map(|name| Voice {
+                    language: name.code().to_string(),
+                    id: name.to_string(),
+                    gender: name.gender(),
+                    tier: Tier::from_voice_id(&name.to_string()),
+                    sample_rate: default_sample_rate(),
+                })
+                .collect(),
+        )
+    }
+
+    /// JSON ファイルから読み込む
+    /// `{"voices": [{"language": "en-US", "id": "en-US-Neural2-A", "gender": "male", "tier": "premium", "sample_rate": 24000}]}`
+    pub fn from_file(path: &str) -> Result<Self, io::Error> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// エンジンの list_voices からカタログを作る
+    /// 複数言語に対応する音声は言語ごとに登録する
+    pub async fn discover(
+        engine: &dyn SpeechEngine,
+        lang: Option<&str>,
+    ) -> Result<Self, io::Error> {
+        let voices = engine.list_voices(lang).await?;
+        Ok(VoiceCatalog::from_voice_infos(voices))
+    }
+
+    pub fn from_voice_infos(infos: Vec<VoiceInfo>) -> Self {
+        let voices = infos
+            .into_iter()
+            .flat_map(|info| {
+                info.language_codes
+                    .iter()
+                    .map(move |language| Voice {
+                        language: language.clone(),
+                        id: info.name.clone(),
+                        gender: info.gender,
+                        tier: Tier::from_voice_id(&info.name),
+                        sample_rate: info.sample_rate.unwrap_or_else(default_sample_rate),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        VoiceCatalog::new(voices)
+    }
+
+    pub fn voices(&self) -> &[Voice] {
+        &self.voices
+    }
+
+    /// 登録されている言語の一覧
+    pub fn languages(&self) -> Vec<&str> {
+        let set: BTreeSet<&str> = self.voices.iter().map(|v| v.language.as_str()).collect();
+        set.into_iter().collect()
+    }
+
+    /// 音声名で検索する
+    pub fn get(&self, id: &str) -> Result<&Voice, VoiceError> {
+        self.voices
+            .iter()
+            .find(|v| v.id == id)
+            .ok_or_else(|| VoiceError::UnknownVoice(id.to_string()))
+    }
+
+    /// 言語で検索する
+    pub fn by_language(&self, language: &str) -> Result<Vec<&Voice>, VoiceError> {
+        let voices: Vec<&Voice> = self
+            .voices
+            .iter()
+            .filter(|v| v.language == language)
+            .collect();
+        if voices.is_empty() {
+            return Err(VoiceError::UnknownLanguage(language.to_string()));
+        }
+        Ok(voices)
+    }
+
+    /// 言語・性別・区分で検索する
+    pub fn find(&self, query: &VoiceQuery) -> Result<Vec<&Voice>, VoiceError> {
+        let voices: Vec<&Voice> = self
+            .by_language(&query.language)?
+            .into_iter()
+            .filter(|v| query.gender.is_none_or(|g| v.gender == g))
+            .filter(|v| query.tier.is_none_or(|t| v.tier == t))
+            .collect();
+        if voices.is_empty() {
+            return Err(VoiceError::NoMatch(format!("{:?}", query)));
+        }
+        Ok(voices)
+    }
 }
 
 #[cfg(test)]
@@ -125,8 +363,83 @@ mod tests {
         let code = Code::JaJP;
 
         for i in 0..count {
-            let name = Name::from(code, i).unwrap();
-            println!("name: {}", name);
+            let name = Name::from(code, i);
+            assert_eq!(name.is_some(), i <= 10);
         }
     }
+
+    #[test]
+    fn test_builtin_catalog() {
+        let catalog = VoiceCatalog::builtin();
+        assert_eq!(catalog.voices().len(), 11);
+        assert_eq!(catalog.languages(), vec!["ja-JP"]);
+
+        let voice = catalog.get("ja-JP-Neural2-B").unwrap();
+        assert_eq!(voice.gender, Gender::Female);
+        assert_eq!(voice.tier, Tier::Premium);
+        assert_eq!(
+            catalog.get("ja-JP-Standard-C").unwrap().tier,
+            Tier::Standard
+        );
+    }
+
+    #[test]
+    fn test_catalog_from_json() {
+        let catalog: VoiceCatalog = serde_json::from_str(
+            r#"{"voices": [
+                {"language": "en-US", "id": "en-US-Neural2-A", "gender": "male", "tier": "premium", "sample_rate": 24000},
+                {"language": "en-US", "id": "en-US-Standard-C", "gender": "female"},
+                {"language": "ko-KR", "id": "ko-KR-Wavenet-A", "gender": "female", "tier": "premium", "sample_rate": 22050}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(catalog.languages(), vec!["en-US", "ko-KR"]);
+        assert_eq!(catalog.by_language("en-US").unwrap().len(), 2);
+        assert_eq!(catalog.get("ko-KR-Wavenet-A").unwrap().sample_rate, 22050);
+        assert_eq!(catalog.get("en-US-Standard-C").unwrap().sample_rate, 24000);
+
+        let query = VoiceQuery {
+            language: "en-US".to_string(),
+            gender: Some(Gender::Female),
+            tier: Some(Tier::Standard),
+        };
+        let found = catalog.find(&query).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "en-US-Standard-C");
+    }
+
+    #[test]
+    fn test_catalog_errors() {
+        let catalog = VoiceCatalog::builtin();
+
+        assert_eq!(
+            catalog.get("ja-JP-Standard-Z"),
+            Err(VoiceError::UnknownVoice("ja-JP-Standard-Z".to_string()))
+        );
+        assert_eq!(
+            catalog.by_language("fr-FR"),
+            Err(VoiceError::UnknownLanguage("fr-FR".to_string()))
+        );
+
+        let query = VoiceQuery {
+            language: "ja-JP".to_string(),
+            gender: Some(Gender::Neutral),
+            tier: None,
+        };
+        assert!(matches!(catalog.find(&query), Err(VoiceError::NoMatch(_))));
+    }
+
+    #[test]
+    fn test_catalog_from_voice_infos() {
+        let catalog = VoiceCatalog::from_voice_infos(vec![VoiceInfo {
+            name: "multi-Neural2-A".to_string(),
+            language_codes: vec!["en-US".to_string(), "en-GB".to_string()],
+            gender: Gender::Neutral,
+            sample_rate: Some(16000),
+        }]);
+
+        assert_eq!(catalog.languages(), vec!["en-GB", "en-US"]);
+        assert_eq!(catalog.by_language("en-GB").unwrap()[0].sample_rate, 16000);
+    }
 }
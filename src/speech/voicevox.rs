@@ -0,0 +1,498 @@
+use std::{fs, io, time::Duration};
+
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{
+    timepoint::{self, Timepoint},
+    voice::Gender,
+    SpeechEngine, SynthesisRequest, Synthesized, VoiceInfo,
+};
+use crate::{ffmpeg::probe, models::config::VoicevoxConfig};
+
+/// VOICEVOX 互換の HTTP エンジンによる音声合成
+/// `audio_query` で読み上げのクエリを作り、`synthesis` で WAV を生成する
+/// 音声名にはスタイル ID（例: "3"）を指定する
+pub struct VoicevoxEngine {
+    client: reqwest::Client,
+    config: VoicevoxConfig,
+}
+
+/// GET /speakers の要素
+#[derive(Debug, Deserialize)]
+struct Speaker {
+    name: String,
+    styles: Vec<Style>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Style {
+    name: String,
+    id: u32,
+}
+
+/// VOICEVOX のスタイルはすべて日本語
+const LANGUAGE: &str = "ja-JP";
+
+/// pitchScale の使える範囲（VOICEVOX のエディタと同じ ±0.15）
+const PITCH_SCALE_LIMIT: f64 = 0.15;
+
+/// 有声のモーラがないときに使う対数 F0（約 200Hz）
+const DEFAULT_LOG_F0: f64 = 5.3;
+
+impl VoicevoxEngine {
+    pub fn new(config: VoicevoxConfig) -> Self {
+        VoicevoxEngine {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.endpoint.trim_end_matches('/'), path)
+    }
+
+    /// 音声名をスタイル ID に変換する
+    fn style_id(voice: &str) -> Result<u32, io::Error> {
+        voice.parse::<u32>().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("VOICEVOX voice must be a style id, got `{}`", voice),
+            )
+        })
+    }
+
+    /// 速度・高さ・音量をクエリに反映する
+    fn apply_prosody(&self, query: &mut Value, request: &SynthesisRequest) {
+        let prosody = &request.prosody;
+        let scale = |query: &Value, key: &str, default: f64| {
+            query.get(key).and_then(Value::as_f64).unwrap_or(default)
+        };
+
+        let speed = scale(query, "speedScale", 1.0) * prosody.rate();
+        let base = scale(query, "pitchScale", 0.0);
+        let pitch = pitch_scale(base, prosody.pitch(), mean_log_f0(query));
+        if (pitch - base).abs() >= PITCH_SCALE_LIMIT {
+            warn!(
+                "VOICEVOX: pitch {:+} st is beyond pitchScale ±{}; clamped",
+                prosody.pitch(),
+                PITCH_SCALE_LIMIT
+            );
+        }
+        let pitch = pitch.clamp(-PITCH_SCALE_LIMIT, PITCH_SCALE_LIMIT);
+        let volume = scale(query, "volumeScale", 1.0) * 10f64.powf(prosody.gain() / 20.0);
+
+        query["speedScale"] = speed.into();
+        query["pitchScale"] = pitch.into();
+        query["volumeScale"] = volume.into();
+        if let Some(rate) = self.config.output_sampling_rate {
+            query["outputSamplingRate"] = rate.into();
+        }
+    }
+}
+
+/// クエリの有声のモーラの対数 F0（`pitch`）の平均
+fn mean_log_f0(query: &Value) -> f64 {
+    let pitches: Vec<f64> = query
+        .get("accent_phrases")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|phrase| phrase.get("moras").and_then(Value::as_array))
+        .flatten()
+        .filter_map(|mora| mora.get("pitch").and_then(Value::as_f64))
+        .filter(|pitch| *pitch > 0.0)
+        .collect();
+    if pitches.is_empty() {
+        DEFAULT_LOG_F0
+    } else {
+        pitches.iter().sum::<f64>() / pitches.len() as f64
+    }
+}
+
+/// 半音を pitchScale に換算する
+/// VOICEVOX はモーラの対数 F0 を 2^pitchScale 倍するので、平均の対数 F0 を L とすると
+/// s 半音（対数 F0 に s・ln2/12 を足す）は 2^p = 1 + s・ln2 / (12L) になる
+fn pitch_scale(base: f64, semitones: f64, log_f0: f64) -> f64 {
+    if semitones == 0.0 {
+        return base;
+    }
+    let log_f0 = log_f0 * 2f64.powf(base);
+    let ratio = 1.0 + semitones * 2f64.ln() / 12.0 / log_f0;
+    base + ratio.max(f64::MIN_POSITIVE).log2()
+}
+
+/// クエリのモーラの長さから、句読点の区切り（pause_mora）の直後の時刻を求める
+/// 区切りの順に `timepoint::clause_mark` の名前を付ける
+/// エンジンの区切りが字幕の区切り（`clauses` 個）と数が合わなければ、ずれた位置で区切らないよう何も返さない
+fn clause_timepoints(query: &Value, clauses: usize) -> Vec<Timepoint> {
+    let number = |value: &Value, key: &str, default: f64| {
+        value.get(key).and_then(Value::as_f64).unwrap_or(default)
+    };
+    let mora_length =
+        |mora: &Value| number(mora, "consonant_length", 0.0) + number(mora, "vowel_length", 0.0);
+    let speed = number(query, "speedScale", 1.0);
+    let pause_scale = number(query, "pauseLengthScale", 1.0);
+    let Some(phrases) = query.get("accent_phrases").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    let mut seconds = number(query, "prePhonemeLength", 0.0);
+    let mut timepoints = Vec::new();
+    for (i, phrase) in phrases.iter().enumerate() {
+        let moras = phrase.get("moras").and_then(Value::as_array);
+        seconds += moras.into_iter().flatten().map(mora_length).sum::<f64>();
+        let Some(pause) = phrase.get("pause_mora").filter(|p| !p.is_null()) else {
+            continue;
+        };
+        seconds += mora_length(pause) * pause_scale;
+        if i + 1 < phrases.len() {
+            timepoints.push(Timepoint::new(
+                &timepoint::clause_mark(timepoints.len() + 1),
+                Duration::from_secs_f64(seconds / speed),
+            ));
+        }
+    }
+    if timepoints.len() != clauses {
+        debug!(
+            "VOICEVOX: {} pauses for {} clauses; not splitting",
+            timepoints.len(),
+            clauses
+        );
+        return Vec::new();
+    }
+    timepoints
+}
+
+fn http_error(e: reqwest::Error) -> io::Error {
+    io::Error::other(format!("VOICEVOX request failed: {}", e))
+}
+
+/// エラー応答を io::Error に変換する（4xx は再試行しても変わらない）
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, io::Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let kind = if status.is_client_error() {
+        io::ErrorKind::InvalidInput
+    } else {
+        io::ErrorKind::Other
+    };
+    Err(io::Error::new(
+        kind,
+        format!("VOICEVOX error: {}: {}", status, body),
+    ))
+}
+
+#[async_trait]
+impl SpeechEngine for VoicevoxEngine {
+    fn name(&self) -> &str {
+        "voicevox"
+    }
+
+    fn supports_prosody(&self) -> bool {
+        true
+    }
+
+    async fn synthesize(&self, request: &SynthesisRequest) -> Result<Synthesized, io::Error> {
+        let speaker = Self::style_id(&request.voice)?.to_string();
+
+        let response = self
+            .client
+            .post(self.url("audio_query"))
+            .query(&[("text", request.text.as_str()), ("speaker", &speaker)])
+            .send()
+            .await
+            .map_err(http_error)?;
+        let mut query: Value = check(response).await?.json().await.map_err(http_error)?;
+        self.apply_prosody(&mut query, request);
+        let timepoints = clause_timepoints(&query, request.clauses);
+
+        let response = self
+            .client
+            .post(self.url("synthesis"))
+            .query(&[("speaker", &speaker)])
+            .json(&query)
+            .send()
+            .await
+            .map_err(http_error)?;
+        let audio = check(response).await?.bytes().await.map_err(http_error)?;
+        fs::write(&request.output, &audio)?;
+
+        Ok(Synthesized {
+            path: request.output.clone(),
+            duration: probe::duration(&request.output)?,
+            timepoints,
+        })
+    }
+
+    async fn list_voices(&self, lang: Option<&str>) -> Result<Vec<VoiceInfo>, io::Error> {
+        if lang.is_some_and(|l| l != LANGUAGE) {
+            return Ok(vec![]);
+        }
+        let response = self
+            .client
+            .get(self.url("speakers"))
+            .send()
+            .await
+            .map_err(http_error)?;
+        let speakers: Vec<Speaker> = check(response).await?.json().await.map_err(http_error)?;
+
+        let voices = speakers
+            .iter()
+            .flat_map(|speaker| {
+                speaker.styles.iter().map(move |style| {
+                    debug!(
+                        "voicevox: {} = {}（{}）",
+                        style.id, speaker.name, style.name
+                    );
+                    VoiceInfo {
+                        name: style.id.to_string(),
+                        language_codes: vec![LANGUAGE.to_string()],
+                        gender: Gender::Unspecified,
+                        sample_rate: self.config.output_sampling_rate,
+                    }
+                })
+            })
+            .collect();
+        Ok(voices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{audio::wav::write_wav, speech::prosody::Prosody};
+    use serde_json::json;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// モックサーバが受け取ったリクエスト（メソッド・パスとクエリ・本文）
+    type Requests = Arc<Mutex<Vec<(String, String, String)>>>;
+
+    /// 固定の応答を返す最小限の HTTP サーバ
+    async fn serve(wav: Vec<u8>) -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Requests::default();
+        let recorded = requests.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let wav = wav.clone();
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    loop {
+                        let Some((method, target, body)) = read_request(&mut stream).await else {
+                            return;
+                        };
+                        recorded
+                            .lock()
+                            .unwrap()
+                            .push((method.clone(), target.clone(), body));
+                        let path = target.split('?').next().unwrap_or_default();
+                        let (status, content_type, body) = match (method.as_str(), path) {
+                            ("POST", "/audio_query") if target.contains("speaker=3") => (
+                                "200 OK",
+                                "application/json",
+                                br#"{"speedScale":1.0,"pitchScale":0.0,"volumeScale":1.0,"accent_phrases":[]}"#.to_vec(),
+                            ),
+                            ("POST", "/audio_query") => (
+                                "422 Unprocessable Entity",
+                                "application/json",
+                                br#"{"detail":"unknown speaker"}"#.to_vec(),
+                            ),
+                            ("POST", "/synthesis") => ("200 OK", "audio/wav", wav.clone()),
+                            ("GET", "/speakers") => (
+                                "200 OK",
+                                "application/json",
+                                r#"[{"name":"ずんだもん","speaker_uuid":"x","styles":[{"name":"ノーマル","id":3},{"name":"あまあま","id":1}]}]"#.as_bytes().to_vec(),
+                            ),
+                            _ => ("404 Not Found", "text/plain", b"not found".to_vec()),
+                        };
+                        let header = format!(
+                            "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\n\r\n",
+                            status,
+                            content_type,
+                            body.len()
+                        );
+                        stream.write_all(header.as_bytes()).await.unwrap();
+                        stream.write_all(&body).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        (format!("http://{}", addr), requests)
+    }
+
+    /// リクエストを1件読み込む
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> Option<(String, String, String)> {
+        let mut buf = Vec::new();
+        let header_end = loop {
+            if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+            let mut chunk = [0u8; 4096];
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        };
+
+        let header = String::from_utf8_lossy(&buf[..header_end]).to_string();
+        let mut lines = header.lines();
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_string();
+        let target = request_line.next()?.to_string();
+        let length = lines
+            .filter_map(|l| l.split_once(':'))
+            .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+
+        while buf.len() < header_end + length {
+            let mut chunk = [0u8; 4096];
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        let body = String::from_utf8_lossy(&buf[header_end..header_end + length]).to_string();
+        Some((method, target, body))
+    }
+
+    fn canned_wav() -> Vec<u8> {
+        let path = std::env::temp_dir().join("auto-mv-voicevox-canned.wav");
+        write_wav(path.to_str().unwrap(), 24000, 1, &[0; 24000]).unwrap();
+        fs::read(path).unwrap()
+    }
+
+    fn engine(endpoint: String) -> VoicevoxEngine {
+        VoicevoxEngine::new(VoicevoxConfig {
+            endpoint,
+            output_sampling_rate: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_synthesize() {
+        let (endpoint, requests) = serve(canned_wav()).await;
+        let engine = engine(endpoint);
+
+        let output = std::env::temp_dir().join("auto-mv-voicevox.wav");
+        let request = SynthesisRequest::new("こんにちは", output.to_str().unwrap(), "ja-JP", "3")
+            .with_prosody(Prosody {
+                rate: Some(1.5),
+                pitch: Some(12.0),
+                gain: Some(0.0),
+            });
+        let synthesized = engine.synthesize(&request).await.unwrap();
+        assert_eq!(synthesized.duration, Duration::from_secs(1));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let (method, target, _) = &requests[0];
+        assert_eq!(method, "POST");
+        assert!(target.starts_with("/audio_query?text=%E3%81%93"));
+        assert!(target.ends_with("&speaker=3"));
+
+        let (_, target, body) = &requests[1];
+        assert_eq!(target, "/synthesis?speaker=3");
+        let query: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(query["speedScale"], 1.5);
+        // +12 半音は pitchScale の範囲を超えるので上限に揃える
+        assert_eq!(query["pitchScale"], PITCH_SCALE_LIMIT);
+        assert_eq!(query["volumeScale"], 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_synthesize_errors() {
+        let (endpoint, requests) = serve(canned_wav()).await;
+        let engine = engine(endpoint);
+        let output = std::env::temp_dir().join("auto-mv-voicevox-error.wav");
+
+        // スタイル ID 以外の音声名は送信前に弾く
+        let request =
+            SynthesisRequest::new("あ", output.to_str().unwrap(), "ja-JP", "ja-JP-Standard-A");
+        let e = engine.synthesize(&request).await.unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert!(requests.lock().unwrap().is_empty());
+
+        // 4xx は再試行しないエラーになる
+        let request = SynthesisRequest::new("あ", output.to_str().unwrap(), "ja-JP", "999");
+        let e = engine.synthesize(&request).await.unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert!(e.to_string().contains("unknown speaker"));
+    }
+
+    #[test]
+    fn test_pitch_scale() {
+        // 2^p 倍した対数 F0 が 2 半音分（2・ln2/12）だけ上がる
+        let p = pitch_scale(0.0, 2.0, 5.5);
+        assert!((5.5 * 2f64.powf(p) - 5.5 - 2.0 * 2f64.ln() / 12.0).abs() < 1e-9);
+        assert!(pitch_scale(0.0, -2.0, 5.5) < 0.0);
+        assert_eq!(pitch_scale(0.05, 0.0, 5.5), 0.05);
+
+        let mora = |pitch: f64| json!({"pitch": pitch});
+        let query = json!({"accent_phrases": [
+            {"moras": [mora(5.0), mora(0.0), mora(6.0)]},
+        ]});
+        assert_eq!(mean_log_f0(&query), 5.5);
+        assert_eq!(mean_log_f0(&json!({})), DEFAULT_LOG_F0);
+    }
+
+    #[test]
+    fn test_clause_timepoints() {
+        // 「はい、そう。です」: 各モーラ 0.1 秒、区切りは 0.3 秒、速度 2 倍
+        let mora = json!({"consonant_length": 0.04, "vowel_length": 0.06});
+        let pause = json!({"consonant_length": null, "vowel_length": 0.3});
+        let query = json!({
+            "speedScale": 2.0,
+            "prePhonemeLength": 0.1,
+            "accent_phrases": [
+                {"moras": [mora, mora], "pause_mora": pause},
+                {"moras": [mora, mora], "pause_mora": pause},
+                {"moras": [mora, mora], "pause_mora": null},
+            ]
+        });
+
+        let timepoints = clause_timepoints(&query, 2);
+        assert_eq!(timepoints.len(), 2);
+        assert_eq!(timepoints[0].name, "clause-1");
+        assert!((timepoints[0].time.as_secs_f64() - 0.3).abs() < 1e-9);
+        assert_eq!(timepoints[1].name, "clause-2");
+        assert!((timepoints[1].time.as_secs_f64() - 0.55).abs() < 1e-9);
+        assert!(clause_timepoints(&json!({}), 0).is_empty());
+
+        // エンジンの区切りと字幕の区切りの数が合わなければ区切らない
+        assert!(clause_timepoints(&query, 1).is_empty());
+        assert!(clause_timepoints(&query, 3).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_voices() {
+        let (endpoint, _) = serve(canned_wav()).await;
+        let engine = engine(endpoint);
+
+        let voices = engine.list_voices(Some("ja-JP")).await.unwrap();
+        assert_eq!(
+            voices.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(),
+            vec!["3", "1"]
+        );
+        assert!(engine.list_voices(Some("en-US")).await.unwrap().is_empty());
+    }
+}